@@ -0,0 +1,123 @@
+//! Benchmarks for the work done on every frame of the virtualized editor view
+//! on a document of several megabytes: working out the render range, reading
+//! the visible lines and resyncing the syntax highlighter after an edit.
+//!
+//! Run with `cargo bench --bench viewport`.
+
+// The editor modules are compiled straight from the binary's sources
+#![allow(dead_code, unused_imports)]
+
+#[path = "../src/theme.rs"]
+mod theme;
+#[path = "../src/file/mod.rs"]
+mod file;
+#[path = "../src/editor/mod.rs"]
+mod editor;
+#[path = "../src/syntax/mod.rs"]
+mod syntax;
+
+use std::hint::black_box;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use editor::{Buffer, Viewport};
+use syntax::Highlighter;
+
+/// Lines in the generated document (about 4 MB of Rust)
+const LINE_COUNT: usize = 100_000;
+
+/// How long each benchmark runs for
+const RUN_TIME: Duration = Duration::from_secs(2);
+
+fn main() {
+    let text = generate_document(LINE_COUNT);
+    let mut buffer = Buffer::from_file(PathBuf::from("bench.rs"), text.clone());
+    println!("document: {} lines, {:.1} MB", buffer.line_count(), text.len() as f64 / 1e6);
+
+    let mut viewport = Viewport::new();
+    let line_count = buffer.line_count();
+    let middle = viewport.line_top(line_count / 2);
+
+    bench("render_range while scrolling", || {
+        viewport.scroll_top = (viewport.scroll_top + 97.0) % middle;
+        black_box(viewport.render_range(line_count));
+    });
+
+    viewport.scroll_top = middle;
+    let range = viewport.render_range(line_count);
+    bench("lines_in render range (middle)", || {
+        black_box(buffer.lines_in(range.clone()).count());
+    });
+
+    let mut highlighter = Highlighter::new();
+    bench("highlight render range (cold cache)", || {
+        highlighter.reset();
+        black_box(highlighter.highlight(&buffer, range.clone()));
+    });
+
+    bench("highlight render range (warm cache)", || {
+        black_box(highlighter.highlight(&buffer, range.clone()));
+    });
+
+    // Typing near the top invalidates everything below it until the
+    // highlighter state converges again
+    buffer.set_cursor_position(10, 0, false);
+    bench("highlight resync after typing above the view", || {
+        buffer.insert_char('x');
+        black_box(highlighter.highlight(&buffer, range.clone()));
+    });
+
+    buffer.set_cursor_position(range.start + 5, 0, false);
+    bench("highlight resync after typing in the view", || {
+        buffer.insert_char('x');
+        black_box(highlighter.highlight(&buffer, range.clone()));
+    });
+
+    // Opening a block comment changes the state of every line after it
+    bench("highlight resync after toggling a block comment", || {
+        buffer.set_cursor_position(range.start + 5, 0, false);
+        buffer.insert_str("/*");
+        black_box(highlighter.highlight(&buffer, range.clone()));
+        buffer.undo();
+        black_box(highlighter.highlight(&buffer, range.clone()));
+    });
+}
+
+// Run `f` repeatedly for RUN_TIME (after a short warm-up) and print the mean
+fn bench(name: &str, mut f: impl FnMut()) {
+    let warm_up = Instant::now();
+    while warm_up.elapsed() < RUN_TIME / 10 {
+        f();
+    }
+
+    let mut iterations = 0u32;
+    let start = Instant::now();
+    while start.elapsed() < RUN_TIME {
+        f();
+        iterations += 1;
+    }
+    let mean = start.elapsed() / iterations;
+    println!("{name:<50} {mean:>12.2?}/iter ({iterations} iterations)");
+}
+
+// Rust-looking source with comments, strings and nesting, so every
+// tokenizer state gets exercised
+fn generate_document(lines: usize) -> String {
+    let templates = [
+        "/// Documentation for item {n}, with `code` and a [link]",
+        "pub fn function_{n}(value: &str, count: usize) -> Result<String, Error> {",
+        "    let message = format!(\"line {n}: {} of {}\", value, count);",
+        "    // TODO: handle the {n}th case properly",
+        "    if count > {n} && value.starts_with('x') {",
+        "        return Err(Error::TooMany { limit: {n}, found: count });",
+        "    }",
+        "    Ok(message.repeat(count % 3))",
+        "}",
+        "",
+    ];
+    let mut text = String::new();
+    for n in 0..lines {
+        text.push_str(&templates[n % templates.len()].replace("{n}", &n.to_string()));
+        text.push('\n');
+    }
+    text
+}
\ No newline at end of file
@@ -1,70 +1,814 @@
 //! Main application component and state management
 
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
 use dioxus::prelude::*;
-use crate::editor::Buffer;
-use crate::ui::StatusBar;
+use dioxus::desktop::tao::event::Event as WindowLoopEvent;
+use dioxus::desktop::WindowEvent;
+use crate::cli::LaunchArgs;
+use crate::clipboard::{self, SystemClipboard};
+use crate::command::{Command, CommandContext, KeyChord, KeyMatch, Keymap, RecentCommands, COMMANDS};
+use crate::document::{Document, DocumentId, Workspace};
+use crate::editor::{next_match, prev_match, Buffer, Position, Search, SearchMatch, SearchQuery, Viewport};
+use crate::settings::{self, Settings};
+use crate::file::{
+    self, FileContents, FileEncoding, FileError, FileWatcher, LineEnding, RecoveredBuffer, RecoveryJournal,
+};
+use crate::syntax::Highlighter;
+use crate::ui::{
+    window_title, CommandPalette, ConfirmDialog, EditorLine, EncodingDialog, FindBar, GutterCell, LineNumberMode,
+    PaletteCommand, RecoveryDialog, RecoveryItem, StatusBar, TabBar, TabInfo, TitleBar,
+};
+
+/// Max delay between clicks to count as a double/triple click
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Fallback monospace character width (px) until the probe is measured
+const DEFAULT_CHAR_WIDTH: f64 = 8.4;
+
+/// DOM id of the scrollable editor view
+const EDITOR_VIEW_ID: &str = "editor-view";
+
+/// DOM id of the find bar's search input
+const FIND_INPUT_ID: &str = "find-input";
+
+/// How often unsaved buffers are written to the crash recovery journal
+const RECOVERY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Text used by the character-width probe
+const PROBE_TEXT: &str = "0000000000";
+
+/// Tracks consecutive clicks for double/triple click selection
+#[derive(Debug, Clone, Copy)]
+struct ClickState {
+    at: Instant,
+    line: usize,
+    count: u32,
+}
+
+/// New contents of a file changed on disk while its buffer had unsaved edits
+#[derive(Debug, Clone, PartialEq)]
+struct ExternalChange {
+    doc: DocumentId,
+    path: PathBuf,
+    text: String,
+}
 
 /// Main application component
 pub fn app() -> Element {
     // Initialize with an empty buffer
     let mut buffer = use_signal(Buffer::new);
 
+    // Open documents; the active one lives in `buffer` and `viewport`
+    let mut workspace = use_signal(Workspace::new);
+
+    // Set while asking whether to discard a dirty tab being closed: the tab
+    // that was active before, to go back to if the close is cancelled
+    let mut pending_close = use_signal(|| None::<usize>);
+
+    // Set while asking whether to quit and discard unsaved changes in any tab
+    let mut pending_quit = use_signal(|| false);
+
+    // Set while asking whether to reload a dirty file another program changed
+    let mut pending_conflict = use_signal(|| None::<ExternalChange>);
+
+    // Documents opened from the command line with --wait; closing the last
+    // of them ends the session so the waiting process can continue
+    let mut wait_documents = use_signal(Vec::<DocumentId>::new);
+
+    // File waiting for the user to pick an encoding to reopen it with
+    let mut pending_reopen = use_signal(|| None::<PathBuf>);
+
+    // This session's crash recovery journal (None if it couldn't be created)
+    let mut journal = use_hook(|| {
+        let journal = RecoveryJournal::start()
+            .map_err(|e| tracing::warn!("Crash recovery unavailable: {}", e))
+            .ok();
+        CopyValue::new(journal)
+    });
+
+    // Unsaved changes left behind by crashed sessions, offered at startup
+    let mut recovered = use_signal(move || journal.read().as_ref().map(|j| j.orphans()).unwrap_or_default());
+    let mut shown_diff = use_signal(|| None::<(usize, String)>);
+
+    // Syntax highlight cache for the active document; not reactive, it only
+    // saves work between renders
+    let mut highlighter = use_hook(|| CopyValue::new(Highlighter::new()));
+
+    // Scroll position to restore once a switched-to document is rendered
+    let mut pending_scroll = use_signal(|| None::<f64>);
+
+    // Find/replace bar state
+    let mut find_open = use_signal(|| false);
+    let mut search_query = use_signal(SearchQuery::default);
+    let mut replacement = use_signal(String::new);
+
+    // System clipboard for cut/copy/paste
+    let mut system_clipboard = use_signal(SystemClipboard::new);
+    // Text last copied from a block selection, pasted back as a rectangle
+    let mut copied_block = use_signal(|| None::<String>);
+
     // Track if editor is focused
     let mut is_focused = use_signal(|| false);
 
+    // Last file operation error, shown in the status bar
+    let mut error_message = use_signal(|| None::<String>);
+
+    // User settings, and why settings.json was rejected (the previous
+    // settings stay in effect until it is fixed)
+    let settings_path = use_hook(settings::settings_path);
+    let initial_settings = use_hook(|| match settings_path.as_deref().map(Settings::load) {
+        Some(Err(e)) => (Settings::default(), Some(e.to_string())),
+        Some(Ok(loaded)) => (loaded, None),
+        None => (Settings::default(), None),
+    });
+    let mut settings = use_signal(|| initial_settings.0.clone());
+    let mut settings_error = use_signal(|| initial_settings.1.clone());
+
+    // Key bindings, and what was wrong with keybindings.json
+    let keymap_path = use_hook(settings::keymap_path);
+    let initial_keymap = use_hook(|| load_keymap(keymap_path.as_deref(), Keymap::default()));
+    let mut keymap = use_signal(|| initial_keymap.0.clone());
+    let mut keymap_error = use_signal(|| initial_keymap.1.clone());
+
+    // Keys of a multi-key sequence typed so far
+    let mut pending_keys = use_signal(Vec::<KeyChord>::new);
+
+    // Command palette state; recent commands are remembered across sessions
+    let mut palette_open = use_signal(|| false);
+    let mut recent_commands = use_signal(|| {
+        RecentCommands::path().map(|path| RecentCommands::load(&path)).unwrap_or_default()
+    });
+
+    // Gutter line-number mode
+    let mut line_number_mode = use_signal(LineNumberMode::default);
+
+    // Scroll state for virtualized rendering
+    let mut viewport = use_signal(Viewport::new);
+    let mut editor_element = use_signal(|| None::<Rc<MountedData>>);
+
+    // Mouse selection state
+    let mut is_dragging = use_signal(|| false);
+    // Corner (line, visual column) where an Alt+Shift block drag started
+    let mut block_drag = use_signal(|| None::<Position>);
+    let mut last_click = use_signal(|| None::<ClickState>);
+    let mut char_width = use_signal(|| DEFAULT_CHAR_WIDTH);
+    let mut probe_element = use_signal(|| None::<Rc<MountedData>>);
+
     // Track cursor position for status bar
     let cursor_line = use_memo(move || buffer.read().cursor_line() + 1);
-    let cursor_col = use_memo(move || buffer.read().cursor_col() + 1);
+    let cursor_col = use_memo(move || buffer.read().cursor_display_col() + 1);
     let line_count = use_memo(move || buffer.read().line_count().max(1));
+    let text_revision = use_memo(move || buffer.read().revision());
 
     // Check if buffer is empty for placeholder
     let is_empty = use_memo(move || buffer.read().is_empty());
 
-    // Handle keyboard input
-    let onkeydown = move |evt: Event<KeyboardData>| {
-        let key = evt.key();
-        let modifiers = evt.modifiers();
+    // Filename and dirty state for the title bar
+    let filename = use_memo(move || buffer.read().filename());
+    let is_dirty = use_memo(move || buffer.read().is_dirty());
+
+    // Line-ending style and encoding the buffer is saved with
+    let line_ending = use_memo(move || buffer.read().line_ending());
+    let mixed_line_endings = use_memo(move || buffer.read().has_mixed_line_endings());
+    let encoding = use_memo(move || buffer.read().encoding());
+
+    // Indentation inserted by Tab and Enter
+    let indent = use_memo(move || buffer.read().indent());
+
+    // Compiled search while the find bar is open (Err holds a regex error)
+    let search = use_memo(move || {
+        let query = search_query.read();
+        if !find_open() || query.pattern.is_empty() {
+            return None;
+        }
+        Some(Search::new(&query).map_err(|e| regex_error_message(&e)))
+    });
+
+    // Every match in the buffer, and which one is selected. After an edit only
+    // the changed lines are searched again, starting from the matches found
+    // last time for the same search, document and revision.
+    let mut match_cache = use_hook(|| CopyValue::new(None::<(Search, DocumentId, u64, Vec<SearchMatch>)>));
+    let search_matches = use_memo(move || {
+        let Some(Ok(search)) = search() else {
+            match_cache.set(None);
+            return Vec::new();
+        };
+        let buffer = buffer.read();
+        let doc = workspace.peek().active_id();
+        let matches = match &*match_cache.read() {
+            Some((cached, cached_doc, revision, matches)) if *cached == search && *cached_doc == doc => {
+                buffer.find_all_since(&search, matches, *revision)
+            }
+            _ => buffer.find_all(&search),
+        };
+        match_cache.set(Some((search, doc, buffer.revision(), matches.clone())));
+        matches
+    });
+    let current_match = use_memo(move || {
+        let selection = buffer.read().selection()?;
+        search_matches.read().iter().position(|m| (m.start(), m.end()) == selection)
+    });
+
+    // Recovered buffers as listed in the recovery dialog
+    let recovery_items = use_memo(move || {
+        recovered.read()
+            .iter()
+            .flat_map(|journal| journal.buffers.iter().map(|b| recovery_item(b, journal.saved_at)))
+            .collect::<Vec<_>>()
+    });
+
+    // One tab per open document
+    let tabs = use_memo(move || {
+        let buf = buffer.read();
+        workspace.read()
+            .buffers(&buf)
+            .map(|b| TabInfo {
+                title: b.filename().unwrap_or_else(|| "Untitled".to_string()),
+                is_dirty: b.is_dirty(),
+            })
+            .collect::<Vec<_>>()
+    });
+
+    // Number of tabs with unsaved changes, for the quit prompt
+    let dirty_count = use_memo(move || tabs.read().iter().filter(|tab| tab.is_dirty).count());
+
+    // Keep the native window title in sync
+    let window = dioxus::desktop::use_window();
+    use_effect(move || {
+        window.set_title(&window_title(filename().as_deref(), is_dirty()));
+    });
+
+    // Make a document the live one, keeping the measured view size
+    let mut show_document = move |doc: Document| {
+        let scroll_top = doc.viewport.scroll_top;
+        highlighter.write().reset();
+        buffer.set(doc.buffer);
+        viewport.write().show(doc.viewport);
+        pending_scroll.set(Some(scroll_top));
+    };
+
+    // Take the live document back out of the signals
+    let mut take_document = move || Document {
+        id: workspace.read().active_id(),
+        buffer: std::mem::take(&mut *buffer.write()),
+        viewport: viewport(),
+    };
+
+    let mut switch_tab = move |idx: usize| {
+        if idx == workspace.read().active() {
+            return;
+        }
+        let current = take_document();
+        let doc = workspace.write().switch(current, idx);
+        show_document(doc);
+    };
+
+    let mut open_document = move |doc: Document| {
+        let current = take_document();
+        let doc = workspace.write().open(current, doc);
+        show_document(doc);
+    };
+
+    // Exit the app cleanly, so the recovery journal isn't offered next time
+    let mut quit = move || {
+        if let Some(journal) = journal.write().take() {
+            journal.finish();
+        }
+        dioxus::desktop::window().close();
+    };
+
+    // Exit the app, asking first if any tab has unsaved changes
+    let mut request_quit = move || {
+        let dirty = workspace.read().buffers(&buffer.read()).any(|b| b.is_dirty());
+        if dirty {
+            pending_quit.set(true);
+        } else {
+            quit();
+        }
+    };
+
+    // Closing the window (title bar button, Alt+F4...) only hides it, so it
+    // goes through the same check; it is shown again if there is something
+    // to ask about
+    dioxus::desktop::use_wry_event_handler(move |event, _| {
+        if let WindowLoopEvent::WindowEvent { event: WindowEvent::CloseRequested, .. } = event {
+            request_quit();
+            if pending_quit() {
+                // Runs after the window has been hidden
+                spawn(async move {
+                    let window = dioxus::desktop::window();
+                    window.set_visible(true);
+                    window.set_focus();
+                });
+            }
+        }
+    });
+
+    let mut close_active_tab = move || {
+        let closed = workspace.read().active_id();
+        let doc = workspace.write().close_active();
+        show_document(doc);
+
+        let was_waited_on = wait_documents.read().contains(&closed);
+        if was_waited_on {
+            wait_documents.write().retain(|&id| id != closed);
+            if wait_documents.read().is_empty() {
+                request_quit();
+            }
+        }
+    };
+
+    // Close a tab, asking first if it has unsaved changes
+    let mut request_close = move |idx: usize| {
+        let previous = workspace.read().active();
+        switch_tab(idx);
+        if buffer.read().is_dirty() {
+            pending_close.set(Some(previous));
+        } else {
+            close_active_tab();
+        }
+    };
+
+    // Show a failure to open a file; non-UTF-8 files offer to reopen with an encoding
+    let mut report_open_error = move |e: FileError| {
+        tracing::error!("Failed to open file: {}", e);
+        error_message.set(Some(e.to_string()));
+        if let FileError::InvalidUtf8(path) = e {
+            pending_reopen.set(Some(path));
+        }
+    };
+
+    // Open the files (or stdin text) given on the command line, one tab each
+    let launch_args = use_context::<LaunchArgs>();
+    use_hook(move || {
+        let targets = launch_args.targets.clone();
+        let mut stdin_text = launch_args.stdin_text.clone();
+        let wait = launch_args.wait;
 
-        match key {
-            // Character input
-            Key::Character(ref c) if !modifiers.ctrl() && !modifiers.alt() => {
-                if let Some(ch) = c.chars().next() {
-                    buffer.write().insert_char(ch);
+        spawn(async move {
+            for target in targets {
+                let mut doc = match target.path {
+                    None => Document::new(Buffer::new_with_text(&stdin_text.take().unwrap_or_default())),
+                    Some(path) => match load_document(path).await {
+                        Ok(doc) => doc,
+                        Err(e) => {
+                            report_open_error(e);
+                            continue;
+                        }
+                    },
+                };
+                if let Some(jump) = target.jump {
+                    doc.buffer.set_cursor_position(jump.line - 1, jump.col - 1, false);
+                }
+                if wait {
+                    wait_documents.write().push(doc.id);
                 }
+                open_document(doc);
             }
+        });
+    });
 
-            // Backspace
-            Key::Backspace => {
-                buffer.write().delete_backward();
+    // Open a file chosen from a dialog in a new tab (or switch to it if already open)
+    let open_file = move || {
+        spawn(async move {
+            let current = buffer.read().path().cloned();
+            let Some(path) = file::pick_open_path(current.as_deref()).await else {
+                return;
+            };
+            let existing = workspace.read().find(&buffer.read(), &path);
+            if let Some(idx) = existing {
+                switch_tab(idx);
+                return;
             }
+            match load_document(path).await {
+                Ok(doc) => {
+                    open_document(doc);
+                    error_message.set(None);
+                }
+                Err(e) => report_open_error(e),
+            }
+        });
+    };
 
-            // Delete
-            Key::Delete => {
-                buffer.write().delete_forward();
+    // Reopen a file with the chosen encoding, replacing its tab if it is open
+    let reopen_with_encoding = move |encoding: FileEncoding| {
+        let Some(path) = pending_reopen.write().take() else {
+            return;
+        };
+        spawn(async move {
+            match file::read_file_with_encoding(&path, encoding).await {
+                Ok(contents) => {
+                    let existing = workspace.read().find(&buffer.read(), &path);
+                    match existing {
+                        Some(idx) => {
+                            switch_tab(idx);
+                            show_document(Document::new(file_buffer(path, contents)));
+                        }
+                        None => open_document(Document::new(file_buffer(path, contents))),
+                    }
+                    error_message.set(None);
+                }
+                Err(e) => report_open_error(e),
+            }
+        });
+    };
+
+    // Another program rewrote an open file: reload it if it has no unsaved
+    // changes, otherwise ask first
+    let external_change = move |path: PathBuf| {
+        spawn(async move {
+            let found = {
+                let buf = buffer.read();
+                let workspace = workspace.read();
+                let encoding = workspace.buffers(&buf).find(|b| b.path() == Some(&path)).map(|b| b.encoding());
+                workspace.find_id(&buf, &path).zip(encoding)
+            };
+            let Some((doc, encoding)) = found else {
+                return;
+            };
+            let text = match file::read_file_with_encoding(&path, encoding).await {
+                Ok(contents) => contents.text,
+                Err(e) => {
+                    tracing::warn!("Failed to reload {}: {}", path.display(), e);
+                    return;
+                }
+            };
+
+            let mut workspace = workspace.write();
+            let mut active = buffer.write();
+            let Some(buf) = workspace.buffer_mut(&mut active, doc) else {
+                return;
+            };
+            if buf.text() == LineEnding::normalize(&text) {
+                return;
+            }
+            if buf.is_dirty() {
+                pending_conflict.set(Some(ExternalChange { doc, path, text }));
+            } else {
+                buf.reload(&text);
             }
+        });
+    };
 
-            // Enter
-            Key::Enter => {
-                buffer.write().insert_char('\n');
+    // Watch open files for external changes
+    let mut watcher = use_hook(move || {
+        let watcher = match FileWatcher::new() {
+            Ok((watcher, mut changes)) => {
+                spawn(async move {
+                    while let Some(paths) = changes.next().await {
+                        for path in paths {
+                            external_change(path);
+                        }
+                    }
+                });
+                Some(watcher)
             }
+            Err(e) => {
+                tracing::warn!("File change detection unavailable: {}", e);
+                None
+            }
+        };
+        CopyValue::new(watcher)
+    });
 
-            // Arrow keys
-            Key::ArrowUp => buffer.write().move_up(),
-            Key::ArrowDown => buffer.write().move_down(),
-            Key::ArrowLeft => buffer.write().move_left(),
-            Key::ArrowRight => buffer.write().move_right(),
+    // Paths of all open files, kept in sync with the watcher
+    let open_paths = use_memo(move || {
+        let buf = buffer.read();
+        workspace.read()
+            .buffers(&buf)
+            .filter_map(|b| b.path().cloned())
+            .collect::<Vec<_>>()
+    });
+    use_effect(move || {
+        let paths = open_paths.read();
+        if let Some(watcher) = watcher.write().as_mut() {
+            watcher.sync(paths.iter());
+        }
+    });
 
-            // Home/End
-            Key::Home => buffer.write().move_to_line_start(),
-            Key::End => buffer.write().move_to_line_end(),
+    // Periodically record unsaved buffers so they survive a crash
+    use_hook(move || {
+        spawn(async move {
+            // What was recorded last: path and revision of each dirty buffer
+            let mut recorded = Vec::new();
+            loop {
+                tokio::time::sleep(RECOVERY_INTERVAL).await;
+                let Some(journal) = journal.read().clone() else {
+                    return;
+                };
+                let (state, buffers) = {
+                    let buf = buffer.read();
+                    let workspace = workspace.read();
+                    let dirty: Vec<&Buffer> = workspace.buffers(&buf).filter(|b| b.is_dirty()).collect();
+                    let state: Vec<_> = dirty.iter().map(|b| (b.path().cloned(), b.revision())).collect();
+                    if state == recorded {
+                        continue;
+                    }
+                    let buffers: Vec<_> = dirty
+                        .iter()
+                        .map(|b| RecoveredBuffer { path: b.path().cloned(), text: b.text() })
+                        .collect();
+                    (state, buffers)
+                };
+                match journal.record(buffers).await {
+                    Ok(()) => recorded = state,
+                    Err(e) => tracing::warn!("Failed to write recovery journal: {}", e),
+                }
+            }
+        });
+    });
 
-            // Tab
-            Key::Tab => {
-                evt.prevent_default();
-                buffer.write().insert_char('\t');
+    // Reopen every recovered buffer with its unsaved changes, then drop the journals
+    let restore_recovered = move |_| {
+        let journals = recovered.take();
+        shown_diff.set(None);
+        spawn(async move {
+            for journal in journals {
+                for RecoveredBuffer { path, text } in journal.buffers.iter().cloned() {
+                    let existing = path.as_ref().and_then(|p| workspace.read().find(&buffer.read(), p));
+                    if let Some(idx) = existing {
+                        switch_tab(idx);
+                        buffer.write().replace_text(&text);
+                        continue;
+                    }
+                    let mut doc = match path {
+                        Some(path) => match load_document(path.clone()).await {
+                            Ok(doc) => doc,
+                            Err(e) => {
+                                // Deleted or unreadable: keep the recovered text
+                                // under its path rather than losing it
+                                tracing::warn!("Restoring {} without its file: {}", path.display(), e);
+                                let mut buffer = Buffer::new();
+                                buffer.set_path(path);
+                                Document::new(buffer)
+                            }
+                        },
+                        None => Document::default(),
+                    };
+                    doc.buffer.replace_text(&text);
+                    open_document(doc);
+                }
+                journal.discard();
+            }
+        });
+    };
+
+    let discard_recovered = move |_| {
+        for journal in recovered.take() {
+            journal.discard();
+        }
+        shown_diff.set(None);
+    };
+
+    // Show (or hide) how a recovered buffer differs from the file on disk
+    let toggle_recovery_diff = move |idx: usize| {
+        if shown_diff.read().as_ref().is_some_and(|(shown, _)| *shown == idx) {
+            shown_diff.set(None);
+            return;
+        }
+        let Some(recovered_buffer) = recovered.read().iter().flat_map(|j| j.buffers.iter()).nth(idx).cloned() else {
+            return;
+        };
+        spawn(async move {
+            let RecoveredBuffer { path, text } = recovered_buffer;
+            let on_disk = match &path {
+                Some(path) => file::read_file(path).await.map(|c| c.text).unwrap_or_default(),
+                None => String::new(),
+            };
+            let name = path.as_deref().map(file::get_filename).unwrap_or_else(|| "Untitled".to_string());
+            let diff = file::recovery_diff(&name, &LineEnding::normalize(&on_disk), &text);
+            shown_diff.set(Some((idx, diff)));
+        });
+    };
+
+    // Save the buffer, asking for a path if it has none (or for Save As)
+    let save_file = move |save_as: bool| {
+        spawn(async move {
+            let current = buffer.read().path().cloned();
+            let path = match current {
+                Some(path) if !save_as => path,
+                _ => match file::pick_save_path(current.as_deref()).await {
+                    Some(path) => path,
+                    None => return,
+                },
+            };
+
+            // Snapshot the text so edits made during the write keep the buffer dirty
+            let (content, line_ending, encoding, revision) = {
+                let buf = buffer.read();
+                (buf.text(), buf.line_ending(), buf.encoding(), buf.revision())
+            };
+            let doc_id = workspace.read().active_id();
+
+            // Our own write isn't an external change
+            if let Some(watcher) = watcher.write().as_mut() {
+                watcher.suspend(&path);
+            }
+            let result = file::write_file(&path, &content, line_ending, encoding).await;
+            if let Some(watcher) = watcher.write().as_mut() {
+                watcher.resume(&path);
             }
 
-            _ => {}
+            match result {
+                Ok(()) => {
+                    // The user may have switched tabs while the file was written
+                    let mut workspace = workspace.write();
+                    let mut active = buffer.write();
+                    let Some(buf) = workspace.buffer_mut(&mut active, doc_id) else {
+                        // Closed in the meantime
+                        return;
+                    };
+                    buf.set_path(path);
+                    buf.mark_saved_at(revision);
+                    error_message.set(None);
+                }
+                Err(e) => {
+                    tracing::error!("Failed to save file: {}", e);
+                    error_message.set(Some(e.to_string()));
+                }
+            }
+        });
+    };
+
+    // Select the next (or previous) match after (or before) the cursor
+    let mut find_next = move |forward: bool| {
+        let Some(Ok(search)) = search() else {
+            return;
+        };
+        let (matches, (start, end)) = {
+            let buf = buffer.read();
+            (buf.find_all(&search), selection_or_cursor(&buf))
+        };
+        let idx = if forward { next_match(&matches, end) } else { prev_match(&matches, start) };
+        if let Some(idx) = idx {
+            buffer.write().select_match(matches[idx]);
+        }
+    };
+
+    // Find as you type: select the first match from where the search started
+    use_effect(move || {
+        let Some(Ok(search)) = search() else {
+            return;
+        };
+        let (matches, (start, _)) = {
+            let buf = buffer.peek();
+            (buf.find_all(&search), selection_or_cursor(&buf))
+        };
+        if let Some(idx) = next_match(&matches, start) {
+            buffer.write().select_match(matches[idx]);
+        }
+    });
+
+    let replace_current = move |_| {
+        let Some(Ok(search)) = search() else {
+            return;
+        };
+        if let Some(idx) = current_match() {
+            let m = search_matches.read()[idx];
+            buffer.write().replace_match(&search, m, &replacement.read());
+        }
+        find_next(true);
+    };
+
+    let replace_all = move |_| {
+        if let Some(Ok(search)) = search() {
+            buffer.write().replace_all(&search, &replacement.read());
+        }
+    };
+
+    // Open the find bar, searching for the selected text if it fits on one line
+    let mut open_find = move || {
+        let selected = buffer.read().selected_text().filter(|text| !text.contains('\n'));
+        if let Some(text) = selected {
+            search_query.write().pattern = text;
+        }
+        find_open.set(true);
+        // Already-open bar: move focus back to it
+        document::eval(&format!("document.getElementById('{FIND_INPUT_ID}')?.select();"));
+    };
+
+    let close_find = move |_| {
+        find_open.set(false);
+        document::eval(&format!("document.getElementById('{EDITOR_VIEW_ID}').focus();"));
+    };
+
+    // Run a command from a key binding or the command palette
+    let mut run_command = move |command: Command| match command {
+        Command::Copy => {
+            let block = clipboard::copy(&buffer.read(), &mut *system_clipboard.write());
+            copied_block.set(block);
+        }
+        Command::Cut => {
+            let block = clipboard::cut(&mut buffer.write(), &mut *system_clipboard.write());
+            copied_block.set(block);
+        }
+        Command::Paste => {
+            let block = copied_block.read();
+            clipboard::paste(&mut buffer.write(), &mut *system_clipboard.write(), block.as_deref());
+        }
+        Command::Open => open_file(),
+        Command::Save => save_file(false),
+        Command::SaveAs => save_file(true),
+        Command::Find => open_find(),
+        Command::FindNext => find_next(true),
+        Command::FindPrevious => find_next(false),
+        Command::CloseTab => request_close(workspace.read().active()),
+        Command::NextTab => {
+            let idx = workspace.read().next_index();
+            switch_tab(idx);
+        }
+        Command::PreviousTab => {
+            let idx = workspace.read().prev_index();
+            switch_tab(idx);
+        }
+        Command::ShowPalette => palette_open.set(true),
+        _ => {
+            let context = CommandContext {
+                word_rules: settings.read().word_rules(),
+                page_lines: viewport.read().visible_line_count().saturating_sub(1).max(1),
+            };
+            let before = buffer.read().cursor_line();
+            command.apply(&mut buffer.write(), &context);
+
+            // Paging scrolls the view along with the cursor
+            let paged = matches!(
+                command,
+                Command::CursorPageUp | Command::CursorPageDown | Command::SelectPageUp | Command::SelectPageDown
+            );
+            if paged {
+                let after = buffer.read().cursor_line();
+                let moved = viewport.read().line_top(after) - viewport.read().line_top(before);
+                let top = (viewport.read().scroll_top + moved).max(0.0);
+                viewport.write().scroll_top = top;
+                scroll_editor_to(top);
+            }
+        }
+    };
+
+    // Every command but the palette itself, recently used ones first
+    let palette_commands = use_memo(move || {
+        let keymap = keymap.read();
+        let recent = recent_commands.read();
+        let others = COMMANDS.iter().map(|(command, _, _)| *command).filter(|c| !recent.contains(*c));
+        recent.iter()
+            .chain(others)
+            .filter(|command| *command != Command::ShowPalette)
+            .map(|command| PaletteCommand {
+                command,
+                keys: keymap.keys_for(command),
+                recent: recent.contains(command),
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let mut close_palette = move |_| {
+        palette_open.set(false);
+        document::eval(&format!("document.getElementById('{EDITOR_VIEW_ID}').focus();"));
+    };
+
+    // Run a command chosen in the palette against the active buffer
+    let run_from_palette = move |command: Command| {
+        close_palette(());
+        recent_commands.write().record(command);
+        if let Some(path) = RecentCommands::path() {
+            if let Err(e) = recent_commands.read().save(&path) {
+                tracing::warn!("Failed to save recent commands: {}", e);
+            }
+        }
+        run_command(command);
+    };
+
+    // Keys run their bound command (possibly after more keys of a sequence);
+    // unbound character keys type text
+    let onkeydown = move |evt: Event<KeyboardData>| {
+        let key = evt.key();
+        let modifiers = evt.modifiers();
+        let Some(chord) = KeyChord::from_event(&key, modifiers) else {
+            return;
+        };
+
+        let mut keys = pending_keys.take();
+        keys.push(chord);
+        let resolved = keymap.read().resolve(&keys);
+        match resolved {
+            KeyMatch::Command(command) => {
+                evt.prevent_default();
+                run_command(command);
+            }
+            KeyMatch::Prefix => {
+                evt.prevent_default();
+                pending_keys.set(keys);
+            }
+            KeyMatch::Unbound => {
+                let typed = keys.len() == 1 && !modifiers.ctrl() && !modifiers.alt();
+                if let (Key::Character(c), true) = (&key, typed) {
+                    if let Some(ch) = c.chars().next() {
+                        buffer.write().insert_char(ch);
+                    }
+                }
+            }
         }
     };
 
@@ -77,9 +821,238 @@ pub fn app() -> Element {
         is_focused.set(false);
     };
 
-    // Get cursor position for rendering
+    // Mouse down on a line: place cursor, or select word/line on double/triple click
+    let mut on_line_mousedown = move |line_idx: usize, evt: Event<MouseData>| {
+        let cell = cell_from_x(evt.element_coordinates().x, char_width());
+        let col = buffer.read().col_at_visual(line_idx, cell);
+        let now = Instant::now();
+
+        let count = match last_click() {
+            Some(prev) if prev.line == line_idx && now.duration_since(prev.at) < MULTI_CLICK_INTERVAL => {
+                prev.count % 3 + 1
+            }
+            _ => 1,
+        };
+        last_click.set(Some(ClickState { at: now, line: line_idx, count }));
+
+        // Alt+Click adds (or removes) a cursor; Alt+Shift+drag selects a block
+        if evt.modifiers().alt() {
+            if evt.modifiers().shift() {
+                let corner = Position::new(line_idx, cell);
+                buffer.write().select_block(corner, corner);
+                block_drag.set(Some(corner));
+            } else {
+                buffer.write().add_cursor_at(line_idx, col);
+            }
+            return;
+        }
+
+        match count {
+            2 => buffer.write().select_word_at(line_idx, col),
+            3 => buffer.write().select_line(line_idx),
+            _ => {
+                let extend = evt.modifiers().shift();
+                buffer.write().set_cursor_position(line_idx, col, extend);
+                is_dragging.set(true);
+            }
+        }
+    };
+
+    // Dragging over a line extends the selection (or the block)
+    let mut on_line_mousemove = move |line_idx: usize, evt: Event<MouseData>| {
+        let cell = cell_from_x(evt.element_coordinates().x, char_width());
+        if let Some(anchor) = block_drag() {
+            buffer.write().select_block(anchor, Position::new(line_idx, cell));
+        } else if is_dragging() {
+            let col = buffer.read().col_at_visual(line_idx, cell);
+            buffer.write().set_cursor_position(line_idx, col, true);
+        }
+    };
+
+    let onmouseup = move |_| {
+        is_dragging.set(false);
+        block_drag.set(None);
+    };
+
+    // Measure the monospace character width and line height from a hidden probe span
+    let measure_probe = move || async move {
+        let Some(probe) = probe_element() else {
+            return;
+        };
+        if let Ok(rect) = probe.get_client_rect().await {
+            if rect.size.width > 0.0 {
+                char_width.set(rect.size.width / PROBE_TEXT.len() as f64);
+            }
+            if rect.size.height > 0.0 {
+                viewport.write().set_line_height(rect.size.height);
+            }
+        }
+    };
+
+    let on_probe_mounted = move |evt: Event<MountedData>| async move {
+        probe_element.set(Some(evt.data()));
+        measure_probe().await;
+    };
+
+    // Apply changed settings: re-measure the font and update scrolling.
+    // Lines wrap differently with another font or tab size, so their
+    // measured heights are dropped.
+    use_effect(move || {
+        let settings = settings.read();
+        let mut viewport = viewport.write();
+        viewport.scroll_padding = settings.scroll_padding as usize;
+        viewport.set_line_height(settings.line_height_px());
+        viewport.clear_heights();
+        spawn(measure_probe());
+    });
+
+    // Keep the buffer's tab stops (for columns and indenting) in line with the
+    // tab_size setting, including for documents switched to later
+    use_effect(move || {
+        let tab_size = settings.read().tab_size as usize;
+        if buffer.read().tab_size() != tab_size {
+            buffer.write().set_tab_size(tab_size);
+        }
+    });
+
+    // Hot-reload settings.json and keybindings.json when they change
+    use_hook(move || {
+        let paths: Vec<PathBuf> = settings_path.iter().chain(keymap_path.iter()).cloned().collect();
+        // The directory has to exist to be watched
+        if let Some(dir) = settings::config_dir() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let (mut watcher, mut changes) = match FileWatcher::new() {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                tracing::warn!("Settings won't be reloaded: {}", e);
+                return;
+            }
+        };
+        for path in &paths {
+            if let Err(e) = watcher.watch(path) {
+                tracing::warn!("{} won't be reloaded: {}", path.display(), e);
+            }
+        }
+        spawn(async move {
+            // Keep watching for as long as the loop runs
+            let _watcher = watcher;
+            while let Some(changed) = changes.next().await {
+                if let Some(path) = settings_path.as_ref().filter(|path| changed.contains(path)) {
+                    match Settings::load(path) {
+                        Ok(loaded) => {
+                            if *settings.peek() != loaded {
+                                settings.set(loaded);
+                            }
+                            settings_error.set(None);
+                        }
+                        Err(e) => {
+                            tracing::warn!("{}", e);
+                            settings_error.set(Some(e.to_string()));
+                        }
+                    }
+                }
+                if keymap_path.as_ref().is_some_and(|path| changed.contains(path)) {
+                    let (loaded, error) = load_keymap(keymap_path.as_deref(), keymap.peek().clone());
+                    keymap.set(loaded);
+                    keymap_error.set(error);
+                }
+            }
+        });
+    });
+
+    // Keep the editor element around to query its scroll offset and size
+    let on_editor_mounted = move |evt: Event<MountedData>| async move {
+        let element = evt.data();
+        if let Ok(rect) = element.get_client_rect().await {
+            viewport.write().height = rect.size.height;
+            viewport.write().set_width(rect.size.width);
+        }
+        editor_element.set(Some(element));
+    };
+
+    let onscroll = move |_| async move {
+        let Some(element) = editor_element() else {
+            return;
+        };
+        if let Ok(offset) = element.get_scroll_offset().await {
+            viewport.write().scroll_top = offset.y;
+        }
+    };
+
+    let onresize = move |evt: Event<ResizeData>| {
+        if let Ok(size) = evt.get_content_box_size() {
+            viewport.write().height = size.height;
+            viewport.write().set_width(size.width);
+        }
+    };
+
+    // Scroll to keep the cursor SCROLL_PADDING lines away from the view edges
+    use_effect(move || {
+        let line = cursor_line() - 1;
+        let total = line_count();
+        let reveal = viewport.peek().scroll_to_reveal(line, total);
+        if let Some(top) = reveal {
+            viewport.write().scroll_top = top;
+            scroll_editor_to(top);
+        }
+    });
+
+    // Measure the rendered rows once they are laid out, so lines that wrap
+    // take up their real height in the scroll offsets and spacers
+    let layout = use_memo(move || {
+        let viewport = viewport.read();
+        let settings = settings.read();
+        (viewport.render_range(line_count()), viewport.width(), viewport.line_height, settings.tab_size)
+    });
+    use_effect(move || {
+        let _ = layout();
+        let revision = text_revision();
+        if viewport.peek().revision() != revision {
+            viewport.write().sync(&buffer.peek());
+        }
+        spawn(async move {
+            let Ok(rows) = measure_rows().await else {
+                return;
+            };
+            // Line numbers are stale if the text changed in the meantime
+            let current = buffer.peek().revision() == revision;
+            if current && viewport.peek().heights_changed(&rows) {
+                viewport.write().set_heights(&rows);
+            }
+        });
+    });
+
+    // Restore a switched-to document's scroll position once its lines are rendered
+    use_effect(move || {
+        if let Some(top) = pending_scroll() {
+            pending_scroll.set(None);
+            scroll_editor_to(top);
+        }
+    });
+
+    // Get cursor positions and selections for rendering
     let cursor_line_idx = buffer.read().cursor_line();
-    let cursor_col_idx = buffer.read().cursor_col();
+    let cursor_positions = buffer.read().cursor_positions();
+    let selections = buffer.read().selections();
+
+    // Only lines in (or near) the viewport get DOM nodes; spacers stand in for the rest
+    let editor_style = {
+        let settings = settings.read();
+        format!(
+            "font-size: {}px; line-height: {}; tab-size: {}; --cursor-blink-duration: {}ms;",
+            settings.font_size,
+            settings.line_height,
+            settings.tab_size,
+            settings.cursor_blink_rate * 2,
+        )
+    };
+    let cursor_blinks = is_focused() && settings.read().cursor_blink_rate > 0;
+
+    let render_range = viewport.read().render_range(line_count());
+    let top_spacer = viewport.read().line_top(render_range.start);
+    let bottom_spacer = viewport.read().line_top(line_count()) - viewport.read().line_top(render_range.end);
+    let line_spans = highlighter.write().highlight(&buffer.read(), render_range.clone());
 
     rsx! {
         // Link to Tailwind CSS (compiled by Dioxus CLI)
@@ -89,49 +1062,105 @@ pub fn app() -> Element {
         div {
             class: "flex flex-col h-screen bg-background text-text font-mono",
 
+            // Title bar with filename and dirty indicator
+            TitleBar {
+                filename: filename(),
+                is_dirty: is_dirty(),
+            }
+
+            // Find/replace bar
+            if find_open() {
+                FindBar {
+                    input_id: FIND_INPUT_ID,
+                    query: search_query(),
+                    replacement: replacement(),
+                    error: search().and_then(|search| search.err()),
+                    on_query: move |query| search_query.set(query),
+                    on_replacement: move |text| replacement.set(text),
+                    on_next: move |_| find_next(true),
+                    on_prev: move |_| find_next(false),
+                    on_replace: replace_current,
+                    on_replace_all: replace_all,
+                    on_close: close_find,
+                }
+            }
+
+            // Open documents
+            TabBar {
+                tabs: tabs(),
+                active: workspace.read().active(),
+                on_select: switch_tab,
+                on_close: request_close,
+            }
+
             // Editor area
             div {
                 class: "flex-1 flex flex-col m-2 border-brutal border-border overflow-hidden",
 
                 // Editable content area
                 div {
+                    id: EDITOR_VIEW_ID,
                     class: "editor-view flex-1 cursor-text whitespace-pre-wrap focus:border-primary focus:outline-none",
+                    style: "{editor_style}",
                     tabindex: 0,
+                    onmounted: on_editor_mounted,
+                    onscroll,
+                    onresize,
                     onkeydown,
                     onfocus,
                     onblur,
+                    onmouseup,
+                    onmouseleave: onmouseup,
 
-                    // Show placeholder when empty
-                    if is_empty() {
+                    // Hidden probe used to measure character width
+                    span {
+                        class: "char-probe",
+                        onmounted: on_probe_mounted,
+                        "{PROBE_TEXT}"
+                    }
+
+                    div { style: "height: {top_spacer}px;" }
+
+                    // Render each visible line with its gutter cell, cursors and selections
+                    for ((line_idx, line), spans) in buffer.read().lines_in(render_range).zip(line_spans) {
                         div {
-                            class: "placeholder-text absolute",
-                            "Start typing..."
-                        }
-                        // Still show cursor even when empty
-                        span {
-                            class: if is_focused() { "cursor-blink" } else { "cursor-static" },
-                        }
-                    } else {
-                        // Render each line with cursor
-                        for (line_idx, line) in buffer.read().lines().enumerate() {
+                            key: "{line_idx}",
+                            "data-line": "{line_idx}",
+                            class: if cursor_positions.iter().any(|p| p.line == line_idx) { "editor-row current-line" } else { "editor-row" },
+
+                            GutterCell {
+                                line_idx,
+                                cursor_line: cursor_line_idx,
+                                mode: line_number_mode(),
+                            }
+
                             div {
-                                key: "{line_idx}",
                                 class: "editor-line",
+                                onmousedown: move |evt| on_line_mousedown(line_idx, evt),
+                                onmousemove: move |evt| on_line_mousemove(line_idx, evt),
 
-                                if line_idx == cursor_line_idx {
-                                    // Line with cursor - split into before and after
-                                    span { "{line.chars().take(cursor_col_idx).collect::<String>()}" }
-                                    span {
-                                        class: if is_focused() { "cursor-blink" } else { "cursor-static" },
+                                // Show placeholder when empty
+                                if is_empty() {
+                                    div {
+                                        class: "placeholder-text absolute",
+                                        "Start typing..."
                                     }
-                                    span { "{line.chars().skip(cursor_col_idx).collect::<String>()}" }
-                                } else {
-                                    // Regular line without cursor
-                                    "{line}"
+                                }
+
+                                EditorLine {
+                                    cursor_cols: cursor_positions.iter().filter(|p| p.line == line_idx).map(|p| p.col).collect::<Vec<_>>(),
+                                    selections: line_selections(&selections, line_idx).0,
+                                    selection_eol: line_selections(&selections, line_idx).1,
+                                    matches: matches_on_line(&search_matches.read(), line_idx),
+                                    spans,
+                                    cursor_blinks,
+                                    text: line,
                                 }
                             }
                         }
                     }
+
+                    div { style: "height: {bottom_spacer}px;" }
                 }
             }
 
@@ -140,7 +1169,240 @@ pub fn app() -> Element {
                 line: cursor_line(),
                 column: cursor_col(),
                 total_lines: line_count(),
+                error: error_message().or(settings_error()).or(keymap_error()),
+                line_number_mode: line_number_mode(),
+                on_toggle_line_numbers: move |_| line_number_mode.set(line_number_mode().toggled()),
+                line_ending: line_ending(),
+                mixed_line_endings: mixed_line_endings(),
+                on_cycle_line_ending: move |_| buffer.write().set_line_ending(line_ending().next()),
+                indent: indent(),
+                on_cycle_indent: move |_| buffer.write().set_indent(indent().next()),
+                encoding: encoding(),
+                on_reopen_with_encoding: move |_| {
+                    let path = buffer.read().path().cloned();
+                    pending_reopen.set(path);
+                },
+                match_count: search().is_some().then(|| search_matches.read().len()),
+                current_match: current_match(),
+            }
+
+            // Confirm discarding unsaved changes when closing a tab
+            if let Some(previous) = pending_close() {
+                ConfirmDialog {
+                    title: "Unsaved changes",
+                    message: format!(
+                        "{} has unsaved changes. Close it and discard them?",
+                        filename().unwrap_or_else(|| "Untitled".to_string())
+                    ),
+                    confirm_label: "Discard",
+                    on_confirm: move |_| {
+                        pending_close.set(None);
+                        close_active_tab();
+                    },
+                    on_cancel: move |_| {
+                        pending_close.set(None);
+                        switch_tab(previous);
+                    },
+                }
+            }
+
+            // Confirm discarding unsaved changes when quitting
+            if pending_quit() {
+                ConfirmDialog {
+                    title: "Unsaved changes",
+                    message: format!(
+                        "{} open {} unsaved changes. Quit and discard them?",
+                        dirty_count(),
+                        if dirty_count() == 1 { "file has" } else { "files have" }
+                    ),
+                    confirm_label: "Discard and Quit",
+                    on_confirm: move |_| {
+                        pending_quit.set(false);
+                        quit();
+                    },
+                    on_cancel: move |_| pending_quit.set(false),
+                }
             }
+
+            // Reload a file changed by another program, or keep the unsaved edits
+            if let Some(change) = pending_conflict() {
+                ConfirmDialog {
+                    title: "File changed on disk",
+                    message: format!(
+                        "{} was changed by another program. Reload it and discard your unsaved changes?",
+                        file::get_filename(&change.path)
+                    ),
+                    confirm_label: "Reload",
+                    on_confirm: move |_| {
+                        let Some(change) = pending_conflict.write().take() else {
+                            return;
+                        };
+                        let mut workspace = workspace.write();
+                        let mut active = buffer.write();
+                        if let Some(buf) = workspace.buffer_mut(&mut active, change.doc) {
+                            buf.reload(&change.text);
+                        }
+                    },
+                    on_cancel: move |_| pending_conflict.set(None),
+                }
+            }
+
+            // Command palette
+            if palette_open() {
+                CommandPalette {
+                    commands: palette_commands(),
+                    on_run: run_from_palette,
+                    on_close: close_palette,
+                }
+            }
+
+            // Offer to restore unsaved changes from a crashed session
+            if !recovery_items.read().is_empty() {
+                RecoveryDialog {
+                    items: recovery_items(),
+                    diff: shown_diff(),
+                    on_diff: toggle_recovery_diff,
+                    on_restore: restore_recovered,
+                    on_discard: discard_recovered,
+                    on_cancel: move |_| {
+                        recovered.set(Vec::new());
+                        shown_diff.set(None);
+                    },
+                }
+            }
+
+            // Pick an encoding for a file that isn't valid UTF-8
+            if let Some(path) = pending_reopen() {
+                EncodingDialog {
+                    filename: file::get_filename(&path),
+                    discards_changes: is_dirty() && buffer.read().path() == Some(&path),
+                    on_select: reopen_with_encoding,
+                    on_cancel: move |_| pending_reopen.set(None),
+                }
+            }
+        }
+    }
+}
+
+// Load keybindings.json, keeping `current` if it can't be used at all, and
+// describe anything wrong with it
+fn load_keymap(path: Option<&Path>, current: Keymap) -> (Keymap, Option<String>) {
+    let Some(path) = path else {
+        return (current, None);
+    };
+    match Keymap::load(path) {
+        Ok((keymap, problems)) if problems.is_empty() => (keymap, None),
+        Ok((keymap, problems)) => {
+            let message = format!("Key bindings in {}: {}", path.display(), problems.join("; "));
+            tracing::warn!("{}", message);
+            (keymap, Some(message))
         }
+        Err(e) => {
+            tracing::warn!("{}", e);
+            (current, Some(e.to_string()))
+        }
+    }
+}
+
+// Dialog entry for a recovered buffer recorded at `saved_at`
+fn recovery_item(buffer: &RecoveredBuffer, saved_at: SystemTime) -> RecoveryItem {
+    let minutes = saved_at.elapsed().unwrap_or_default().as_secs() / 60;
+    let when = match minutes {
+        0 => "just now".to_string(),
+        1..=59 => format!("{minutes} min ago"),
+        60..=1439 => format!("{} h ago", minutes / 60),
+        _ => format!("{} days ago", minutes / 1440),
+    };
+    match &buffer.path {
+        Some(path) => RecoveryItem {
+            title: file::get_filename(path),
+            detail: format!("{} · {}", path.display(), when),
+        },
+        None => RecoveryItem { title: "Untitled".to_string(), detail: when },
+    }
+}
+
+// Read a file into a new document; a missing file opens empty with its path
+// set, so it gets created on first save
+async fn load_document(path: PathBuf) -> Result<Document, FileError> {
+    let buffer = match file::read_file(&path).await {
+        Ok(contents) => file_buffer(path, contents),
+        Err(FileError::NotFound(_)) => {
+            let mut buffer = Buffer::new();
+            buffer.set_path(path);
+            buffer
+        }
+        Err(e) => return Err(e),
+    };
+    Ok(Document::new(buffer))
+}
+
+// A buffer holding a file's decoded contents
+fn file_buffer(path: PathBuf, contents: FileContents) -> Buffer {
+    let mut buffer = Buffer::from_file(path, contents.text);
+    buffer.set_encoding(contents.encoding);
+    buffer
+}
+
+// Selection bounds, or the cursor position twice when nothing is selected
+fn selection_or_cursor(buffer: &Buffer) -> (Position, Position) {
+    buffer.selection().unwrap_or_else(|| {
+        let cursor = Position::new(buffer.cursor_line(), buffer.cursor_col());
+        (cursor, cursor)
+    })
+}
+
+// Column ranges of the (line-sorted) search matches on one line
+fn matches_on_line(matches: &[SearchMatch], line_idx: usize) -> Vec<(usize, usize)> {
+    let first = matches.partition_point(|m| m.line < line_idx);
+    matches[first..]
+        .iter()
+        .take_while(|m| m.line == line_idx)
+        .map(|m| (m.start_col, m.end_col))
+        .collect()
+}
+
+// Short message for an invalid regex (the full error spans several lines)
+fn regex_error_message(error: &regex::Error) -> String {
+    let detail = error.to_string();
+    let detail = detail.lines().last().unwrap_or_default().trim_start_matches("error: ");
+    format!("Invalid regex: {detail}")
+}
+
+// Set the editor view's scroll offset in the DOM
+fn scroll_editor_to(top: f64) {
+    document::eval(&format!(
+        "document.getElementById('{EDITOR_VIEW_ID}').scrollTop = {top};"
+    ));
+}
+
+// Heights of the rendered editor rows as (line, height) pairs
+async fn measure_rows() -> Result<Vec<(usize, f64)>, document::EvalError> {
+    document::eval(&format!(
+        "const view = document.getElementById('{EDITOR_VIEW_ID}');
+        if (!view) return [];
+        return Array.from(view.querySelectorAll('.editor-row'), row =>
+            [Number(row.dataset.line), row.getBoundingClientRect().height]);"
+    ))
+    .join()
+    .await
+}
+
+// Convert an x offset within a line into the nearest visual cell boundary
+// (tabs and wide characters span several cells; see `Buffer::col_at_visual`)
+fn cell_from_x(x: f64, char_width: f64) -> usize {
+    (x / char_width).round().max(0.0) as usize
+}
+
+// Selected column ranges on a line, plus whether the line break is selected
+fn line_selections(selections: &[(Position, Position)], line_idx: usize) -> (Vec<(usize, usize)>, bool) {
+    let mut ranges = Vec::new();
+    let mut eol = false;
+    for (start, end) in selections.iter().filter(|(start, end)| start.line <= line_idx && line_idx <= end.line) {
+        let from = if line_idx == start.line { start.col } else { 0 };
+        let to = if line_idx == end.line { end.col } else { usize::MAX };
+        ranges.push((from, to));
+        eol |= line_idx < end.line;
     }
+    (ranges, eol)
 }
\ No newline at end of file
@@ -0,0 +1,235 @@
+//! Command-line arguments for the stringr binary
+//!
+//! Usage: `stringr [--wait] [+LINE[:COL]] [FILE | -]...`
+//!
+//! A `+LINE[:COL]` argument applies to the file that follows it, and `-`
+//! reads the buffer from stdin.
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// Usage text printed for `--help`
+pub const USAGE: &str = "\
+Usage: stringr [OPTIONS] [+LINE[:COL]] [FILE | -]...
+
+Arguments:
+  FILE           File to open (created on first save if it doesn't exist)
+  -              Read the buffer from stdin
+  +LINE[:COL]    Jump to LINE (and COL) in the next file
+
+Options:
+  -w, --wait     Block until the files are closed (for $EDITOR / git)
+  -h, --help     Print this help
+  -V, --version  Print the version";
+
+/// A 1-based line/column to jump to after opening
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Jump {
+    pub line: usize,
+    pub col: usize,
+}
+
+/// Something to open on launch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenTarget {
+    /// File path, or None to read from stdin
+    pub path: Option<PathBuf>,
+    /// Optional cursor jump
+    pub jump: Option<Jump>,
+}
+
+/// Parsed launch options, handed to the app through the Dioxus context
+#[derive(Debug, Clone, Default)]
+pub struct LaunchArgs {
+    /// Files (or stdin) to open, in order
+    pub targets: Vec<OpenTarget>,
+    /// Block until the opened files are closed
+    pub wait: bool,
+    /// Text read from stdin when `-` was given
+    pub stdin_text: Option<String>,
+}
+
+impl LaunchArgs {
+    /// Check if any target reads from stdin
+    pub fn reads_stdin(&self) -> bool {
+        self.targets.iter().any(|t| t.path.is_none())
+    }
+}
+
+/// What the command line asked for
+#[derive(Debug, Clone)]
+pub enum Command {
+    Launch(LaunchArgs),
+    Help,
+    Version,
+}
+
+/// Errors from parsing the command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliError {
+    // Unrecognised --flag
+    UnknownOption(String),
+    // Malformed +LINE[:COL]
+    InvalidJump(String),
+    // +LINE[:COL] with no file after it
+    DanglingJump(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::UnknownOption(opt) => write!(f, "Unknown option: {}", opt),
+            CliError::InvalidJump(arg) => write!(f, "Invalid position '{}', expected +LINE[:COL]", arg),
+            CliError::DanglingJump(arg) => write!(f, "Position '{}' must be followed by a file", arg),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Parse arguments (without the program name)
+pub fn parse<I>(args: I) -> Result<Command, CliError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut launch = LaunchArgs::default();
+    let mut pending_jump: Option<(Jump, String)> = None;
+    let mut options_done = false;
+
+    for arg in args {
+        if !options_done {
+            match arg.as_str() {
+                "--" => {
+                    options_done = true;
+                    continue;
+                }
+                "-w" | "--wait" => {
+                    launch.wait = true;
+                    continue;
+                }
+                "-h" | "--help" => return Ok(Command::Help),
+                "-V" | "--version" => return Ok(Command::Version),
+                _ => {}
+            }
+
+            if let Some(spec) = arg.strip_prefix('+') {
+                let jump = parse_jump(spec).ok_or_else(|| CliError::InvalidJump(arg.clone()))?;
+                pending_jump = Some((jump, arg));
+                continue;
+            }
+            if arg.starts_with('-') && arg != "-" {
+                return Err(CliError::UnknownOption(arg));
+            }
+        }
+
+        let path = if arg == "-" { None } else { Some(PathBuf::from(arg)) };
+        launch.targets.push(OpenTarget {
+            path,
+            jump: pending_jump.take().map(|(jump, _)| jump),
+        });
+    }
+
+    if let Some((_, arg)) = pending_jump {
+        return Err(CliError::DanglingJump(arg));
+    }
+    Ok(Command::Launch(launch))
+}
+
+// Parse "LINE" or "LINE:COL" (1-based, COL defaults to 1)
+fn parse_jump(spec: &str) -> Option<Jump> {
+    let (line, col) = match spec.split_once(':') {
+        Some((line, col)) => (line, Some(col)),
+        None => (spec, None),
+    };
+    let line = line.parse().ok().filter(|&n| n > 0)?;
+    let col = match col {
+        Some(col) => col.parse().ok().filter(|&n| n > 0)?,
+        None => 1,
+    };
+    Some(Jump { line, col })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn launch(args: &[&str]) -> LaunchArgs {
+        match parse(args.iter().map(|arg| arg.to_string())) {
+            Ok(Command::Launch(launch)) => launch,
+            other => panic!("expected a launch for {args:?}, got {other:?}"),
+        }
+    }
+
+    fn error(args: &[&str]) -> CliError {
+        parse(args.iter().map(|arg| arg.to_string())).unwrap_err()
+    }
+
+    fn target(path: &str, jump: Option<(usize, usize)>) -> OpenTarget {
+        OpenTarget {
+            path: Some(PathBuf::from(path)),
+            jump: jump.map(|(line, col)| Jump { line, col }),
+        }
+    }
+
+    #[test]
+    fn files_open_in_order() {
+        let args = launch(&["a.txt", "b.txt"]);
+        assert_eq!(args.targets, vec![target("a.txt", None), target("b.txt", None)]);
+        assert!(!args.wait);
+        assert!(launch(&[]).targets.is_empty());
+    }
+
+    #[test]
+    fn jump_applies_to_the_next_file() {
+        let args = launch(&["+12", "a.txt", "+3:7", "b.txt", "c.txt"]);
+        assert_eq!(args.targets, vec![
+            target("a.txt", Some((12, 1))),
+            target("b.txt", Some((3, 7))),
+            target("c.txt", None),
+        ]);
+    }
+
+    #[test]
+    fn jump_must_be_positive_numbers() {
+        for arg in ["+0", "+1:0", "+x", "+", "+2:", "+-1"] {
+            assert_eq!(error(&[arg, "a.txt"]), CliError::InvalidJump(arg.to_string()));
+        }
+    }
+
+    #[test]
+    fn jump_without_a_file_is_an_error() {
+        assert_eq!(error(&["a.txt", "+5"]), CliError::DanglingJump("+5".to_string()));
+    }
+
+    #[test]
+    fn dash_reads_stdin() {
+        let args = launch(&["+2", "-"]);
+        assert_eq!(args.targets, vec![OpenTarget { path: None, jump: Some(Jump { line: 2, col: 1 }) }]);
+        assert!(args.reads_stdin());
+        assert!(!launch(&["a.txt"]).reads_stdin());
+    }
+
+    #[test]
+    fn double_dash_ends_options() {
+        let args = launch(&["--wait", "--", "--help", "+3", "-"]);
+        assert!(args.wait);
+        assert_eq!(args.targets, vec![
+            target("--help", None),
+            target("+3", None),
+            OpenTarget { path: None, jump: None },
+        ]);
+    }
+
+    #[test]
+    fn flags() {
+        assert!(launch(&["-w", "a.txt"]).wait);
+        assert!(matches!(parse(["a.txt".to_string(), "--help".to_string()]), Ok(Command::Help)));
+        assert!(matches!(parse(["-V".to_string()]), Ok(Command::Version)));
+    }
+
+    #[test]
+    fn unknown_flags_are_errors() {
+        assert_eq!(error(&["--wiat"]), CliError::UnknownOption("--wiat".to_string()));
+        assert_eq!(error(&["a.txt", "-x"]), CliError::UnknownOption("-x".to_string()));
+    }
+}
\ No newline at end of file
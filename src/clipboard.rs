@@ -0,0 +1,256 @@
+//! Clipboard access for cut/copy/paste
+//!
+//! The editor talks to the `Clipboard` trait so the system clipboard (arboard)
+//! can be swapped for an in-memory one.
+
+use crate::editor::Buffer;
+
+/// Minimal clipboard interface used by the editor
+pub trait Clipboard {
+    /// Read text from the clipboard, if any
+    fn get_text(&mut self) -> Option<String>;
+    /// Replace the clipboard contents with text
+    fn set_text(&mut self, text: String);
+}
+
+/// System clipboard backed by arboard
+pub struct SystemClipboard {
+    // None if the platform clipboard could not be opened
+    inner: Option<arboard::Clipboard>,
+}
+
+impl SystemClipboard {
+    pub fn new() -> Self {
+        let inner = match arboard::Clipboard::new() {
+            Ok(clipboard) => Some(clipboard),
+            Err(e) => {
+                tracing::warn!("System clipboard unavailable: {}", e);
+                None
+            }
+        };
+        Self { inner }
+    }
+}
+
+impl Default for SystemClipboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        let clipboard = self.inner.as_mut()?;
+        match clipboard.get_text() {
+            Ok(text) => Some(text),
+            Err(e) => {
+                tracing::debug!("Clipboard read failed: {}", e);
+                None
+            }
+        }
+    }
+
+    fn set_text(&mut self, text: String) {
+        if let Some(clipboard) = self.inner.as_mut() {
+            if let Err(e) = clipboard.set_text(text) {
+                tracing::warn!("Clipboard write failed: {}", e);
+            }
+        }
+    }
+}
+
+/// In-memory clipboard (for tests and headless use)
+#[derive(Debug, Clone, Default)]
+pub struct MemoryClipboard {
+    text: Option<String>,
+}
+
+impl MemoryClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn set_text(&mut self, text: String) {
+        self.text = Some(text);
+    }
+}
+
+/// Copy the selection (or the current line) to the clipboard. Returns the
+/// text if it was copied from a block selection, so it can be pasted back
+/// as a rectangle.
+pub fn copy(buffer: &Buffer, clipboard: &mut impl Clipboard) -> Option<String> {
+    let text = buffer.copy_text();
+    let block = buffer.has_block_selection().then(|| text.clone());
+    clipboard.set_text(text);
+    block
+}
+
+/// Cut the selection (or the current line) to the clipboard. Returns the
+/// text if it was cut from a block selection.
+pub fn cut(buffer: &mut Buffer, clipboard: &mut impl Clipboard) -> Option<String> {
+    let block = buffer.has_block_selection();
+    let text = buffer.cut_text();
+    if text.is_empty() {
+        return None;
+    }
+    clipboard.set_text(text.clone());
+    block.then_some(text)
+}
+
+/// Paste clipboard text at the cursors, replacing the selections. Text that
+/// is still the last copied `block` is pasted as a rectangle.
+pub fn paste(buffer: &mut Buffer, clipboard: &mut impl Clipboard, block: Option<&str>) {
+    if let Some(text) = clipboard.get_text() {
+        if text.is_empty() {
+            return;
+        }
+        if block == Some(text.as_str()) {
+            buffer.paste_block(&text);
+        } else {
+            buffer.paste(&text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::Position;
+
+    // Buffer with one caret at the start of each of its first `n` lines
+    fn with_cursors(text: &str, n: usize) -> Buffer {
+        let mut buffer = Buffer::new_with_text(text);
+        for _ in 1..n {
+            buffer.add_cursor_below();
+        }
+        buffer
+    }
+
+    #[test]
+    fn copy_selection() {
+        let mut buffer = Buffer::new_with_text("hello world");
+        buffer.set_cursor_position(0, 5, true);
+        let mut clipboard = MemoryClipboard::new();
+        assert_eq!(copy(&buffer, &mut clipboard), None);
+        assert_eq!(clipboard.get_text().as_deref(), Some("hello"));
+        assert_eq!(buffer.text(), "hello world");
+    }
+
+    #[test]
+    fn copy_and_cut_whole_line_without_selection() {
+        let mut buffer = Buffer::new_with_text("one\ntwo\nthree");
+        buffer.set_cursor_position(1, 1, false);
+        let mut clipboard = MemoryClipboard::new();
+        copy(&buffer, &mut clipboard);
+        assert_eq!(clipboard.get_text().as_deref(), Some("two\n"));
+
+        cut(&mut buffer, &mut clipboard);
+        assert_eq!(buffer.text(), "one\nthree");
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "one\ntwo\nthree");
+    }
+
+    #[test]
+    fn last_line_is_copied_with_a_line_break() {
+        let mut buffer = Buffer::new_with_text("one\ntwo");
+        buffer.set_cursor_position(1, 1, false);
+        buffer.add_cursor_at(0, 0);
+        let mut clipboard = MemoryClipboard::new();
+        copy(&buffer, &mut clipboard);
+        assert_eq!(clipboard.get_text().as_deref(), Some("one\ntwo\n"));
+
+        buffer.set_cursor_position(1, 1, false);
+        cut(&mut buffer, &mut clipboard);
+        assert_eq!(clipboard.get_text().as_deref(), Some("two\n"));
+        assert_eq!(buffer.text(), "one\n");
+
+        // Pasting it back at the start of a line inserts a whole line
+        buffer.set_cursor_position(0, 0, false);
+        paste(&mut buffer, &mut clipboard, None);
+        assert_eq!(buffer.text(), "two\none\n");
+    }
+
+    #[test]
+    fn cut_empty_buffer_keeps_clipboard() {
+        let mut buffer = Buffer::new();
+        let mut clipboard = MemoryClipboard::new();
+        clipboard.set_text("kept".to_string());
+        assert_eq!(cut(&mut buffer, &mut clipboard), None);
+        assert_eq!(clipboard.get_text().as_deref(), Some("kept"));
+    }
+
+    #[test]
+    fn paste_replaces_selection() {
+        let mut buffer = Buffer::new_with_text("hello world");
+        buffer.set_cursor_position(0, 5, true);
+        let mut clipboard = MemoryClipboard::new();
+        clipboard.set_text("bye\r\nnow".to_string());
+        paste(&mut buffer, &mut clipboard, None);
+        assert_eq!(buffer.text(), "bye\nnow world");
+    }
+
+    #[test]
+    fn multi_cursor_round_trip() {
+        let mut buffer = with_cursors("a1\nb2\nc3", 3);
+        buffer.move_with_selection(true, Buffer::move_right);
+        let mut clipboard = MemoryClipboard::new();
+        assert_eq!(cut(&mut buffer, &mut clipboard), None);
+        assert_eq!(clipboard.get_text().as_deref(), Some("a\nb\nc"));
+        assert_eq!(buffer.text(), "1\n2\n3");
+        assert_eq!(buffer.cursor_count(), 3);
+
+        // One line per cursor: each cursor gets its own line back
+        paste(&mut buffer, &mut clipboard, None);
+        assert_eq!(buffer.text(), "a1\nb2\nc3");
+    }
+
+    #[test]
+    fn multi_cursor_line_copy_dedups_shared_lines() {
+        let mut buffer = Buffer::new_with_text("ab\ncd");
+        buffer.add_cursor_at(0, 2);
+        let mut clipboard = MemoryClipboard::new();
+        copy(&buffer, &mut clipboard);
+        assert_eq!(clipboard.get_text().as_deref(), Some("ab\n"));
+    }
+
+    #[test]
+    fn block_round_trip() {
+        let mut buffer = Buffer::new_with_text("abcd\nefgh\nijkl");
+        buffer.select_block(Position::new(0, 1), Position::new(2, 3));
+        let mut clipboard = MemoryClipboard::new();
+        let block = cut(&mut buffer, &mut clipboard);
+        assert_eq!(block.as_deref(), Some("bc\nfg\njk"));
+        assert_eq!(buffer.text(), "ad\neh\nil");
+
+        // Pasting with a single caret puts the rectangle back
+        buffer.set_cursor_position(0, 1, false);
+        paste(&mut buffer, &mut clipboard, block.as_deref());
+        assert_eq!(buffer.text(), "abcd\nefgh\nijkl");
+    }
+
+    #[test]
+    fn block_pastes_past_short_lines_and_end() {
+        let mut buffer = Buffer::new_with_text("abc\nd");
+        let mut clipboard = MemoryClipboard::new();
+        clipboard.set_text("X\nY\nZ".to_string());
+        buffer.set_cursor_position(0, 2, false);
+        paste(&mut buffer, &mut clipboard, Some("X\nY\nZ"));
+        assert_eq!(buffer.text(), "abXc\nd Y\n  Z");
+    }
+
+    #[test]
+    fn stale_block_pastes_as_text() {
+        let mut buffer = Buffer::new_with_text("abc");
+        let mut clipboard = MemoryClipboard::new();
+        clipboard.set_text("X\nY".to_string());
+        buffer.set_cursor_position(0, 1, false);
+        paste(&mut buffer, &mut clipboard, Some("old"));
+        assert_eq!(buffer.text(), "aX\nYbc");
+    }
+}
\ No newline at end of file
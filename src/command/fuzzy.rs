@@ -0,0 +1,95 @@
+// Fuzzy matching for the command palette
+// A query matches when its characters appear in order in the text
+// ("svas" matches "Save As"); matches at word starts and runs of
+// consecutive characters rank higher.
+
+/// Bonus for a character matched at the start of a word
+const WORD_START_BONUS: u32 = 5;
+
+/// Bonus for a character matched right after the previous one
+const CONSECUTIVE_BONUS: u32 = 6;
+
+/// Score how well `query` matches `text`, ignoring case and spaces in the
+/// query; `None` if it doesn't match. Higher is better.
+///
+/// Scores the best way of lining the query up with the text, so "fo" finds
+/// the word start in "file open" rather than the first "o" it comes across.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let wanted: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect();
+    let text: Vec<char> = text.chars().collect();
+    if wanted.is_empty() {
+        return Some(0);
+    }
+
+    // Score of each text char as a match, if it matches at all
+    let char_score = |i: usize| {
+        let mut score = 1;
+        if is_word_start(i.checked_sub(1).map(|prev| text[prev]), text[i]) {
+            score += WORD_START_BONUS;
+        }
+        score
+    };
+    let matches = |i: usize, want: char| text[i].to_lowercase().eq(std::iter::once(want));
+
+    // ending[i]: best score for the query so far with its last char matched
+    // at text char i
+    let mut ending: Vec<Option<u32>> =
+        (0..text.len()).map(|i| matches(i, wanted[0]).then(|| char_score(i))).collect();
+
+    for &want in &wanted[1..] {
+        let mut next = vec![None; text.len()];
+        // Best score ending anywhere before the previous char
+        let mut best_before: Option<u32> = None;
+        for i in 1..text.len() {
+            if matches(i, want) {
+                let consecutive = ending[i - 1].map(|score| score + CONSECUTIVE_BONUS);
+                next[i] = consecutive.max(best_before).map(|score| score + char_score(i));
+            }
+            best_before = best_before.max(ending[i - 1]);
+        }
+        ending = next;
+    }
+
+    ending.into_iter().flatten().max()
+}
+
+// Start of text, after a separator, or a camelCase hump
+fn is_word_start(prev: Option<char>, ch: char) -> bool {
+    match prev {
+        None => true,
+        Some(prev) => !prev.is_alphanumeric() || (prev.is_lowercase() && ch.is_uppercase()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_characters_in_order_ignoring_case_and_spaces() {
+        assert!(fuzzy_score("svas", "Save As").is_some());
+        assert!(fuzzy_score("SAVE as", "save as").is_some());
+        assert_eq!(fuzzy_score("", "Save As"), Some(0));
+        assert_eq!(fuzzy_score("sa", "As"), None);
+        assert_eq!(fuzzy_score("saves", "Save"), None);
+    }
+
+    #[test]
+    fn best_alignment_wins_over_the_first_one() {
+        // Taking the first "f" would miss the word start and run in "fold"
+        assert_eq!(fuzzy_score("fo", "buffer fold"), Some(2 + WORD_START_BONUS + CONSECUTIVE_BONUS));
+        assert_eq!(fuzzy_score("fo", "buffer fold"), fuzzy_score("fo", "fold"));
+        // A consecutive run later on beats scattered early matches
+        assert_eq!(fuzzy_score("ab", "a_xab"), Some(2 + CONSECUTIVE_BONUS));
+    }
+
+    #[test]
+    fn word_starts_and_runs_rank_higher() {
+        let rank = |text| fuzzy_score("fo", text).unwrap();
+        assert!(rank("Fold") > rank("Buffer Overview"));
+        assert!(rank("File Open") > rank("Buffer Overview"));
+        assert!(rank("fooBar") > rank("afoo"));
+        assert!(fuzzy_score("go", "goToLine").unwrap() > fuzzy_score("go", "toggleOverlay").unwrap());
+        assert!(fuzzy_score("tl", "toggleLineNumbers").unwrap() > fuzzy_score("tl", "totalCount").unwrap());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,458 @@
+// Key bindings
+// Maps key chords ("Ctrl+S") and multi-key sequences ("Ctrl+K Ctrl+C") to
+// commands. The defaults can be overridden from keybindings.json, a list of
+// `{ "key": "Ctrl+K Ctrl+C", "command": "buffer.undo" }` entries; a `null`
+// command removes a default binding.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use dioxus::prelude::{Key, Modifiers};
+use serde::Deserialize;
+use super::registry::Command;
+
+/// Keys with names (anything else is a single character)
+const NAMED_KEYS: &[&str] = &[
+    "ArrowUp", "ArrowDown", "ArrowLeft", "ArrowRight", "Home", "End", "PageUp", "PageDown",
+    "Backspace", "Delete", "Enter", "Tab", "Escape", "Insert", "Space",
+    "F1", "F2", "F3", "F4", "F5", "F6", "F7", "F8", "F9", "F10", "F11", "F12",
+];
+
+/// Shorter names accepted in keybindings.json
+const KEY_ALIASES: &[(&str, &str)] = &[
+    ("Up", "ArrowUp"),
+    ("Down", "ArrowDown"),
+    ("Left", "ArrowLeft"),
+    ("Right", "ArrowRight"),
+    ("Esc", "Escape"),
+    ("Del", "Delete"),
+    ("Return", "Enter"),
+    ("PgUp", "PageUp"),
+    ("PgDn", "PageDown"),
+];
+
+/// Built-in bindings
+const DEFAULT_BINDINGS: &[(&str, Command)] = &[
+    ("ArrowUp", Command::CursorUp),
+    ("ArrowDown", Command::CursorDown),
+    ("ArrowLeft", Command::CursorLeft),
+    ("ArrowRight", Command::CursorRight),
+    ("Home", Command::CursorSmartHome),
+    ("End", Command::CursorLineEnd),
+    ("Ctrl+ArrowLeft", Command::CursorWordLeft),
+    ("Ctrl+ArrowRight", Command::CursorWordRight),
+    ("Ctrl+ArrowUp", Command::CursorParagraphUp),
+    ("Ctrl+ArrowDown", Command::CursorParagraphDown),
+    ("Ctrl+Home", Command::CursorDocStart),
+    ("Ctrl+End", Command::CursorDocEnd),
+    ("PageUp", Command::CursorPageUp),
+    ("PageDown", Command::CursorPageDown),
+    ("Shift+ArrowUp", Command::SelectUp),
+    ("Shift+ArrowDown", Command::SelectDown),
+    ("Shift+ArrowLeft", Command::SelectLeft),
+    ("Shift+ArrowRight", Command::SelectRight),
+    ("Shift+Home", Command::SelectSmartHome),
+    ("Shift+End", Command::SelectLineEnd),
+    ("Ctrl+Shift+ArrowLeft", Command::SelectWordLeft),
+    ("Ctrl+Shift+ArrowRight", Command::SelectWordRight),
+    ("Ctrl+Shift+ArrowUp", Command::SelectParagraphUp),
+    ("Ctrl+Shift+ArrowDown", Command::SelectParagraphDown),
+    ("Ctrl+Shift+Home", Command::SelectDocStart),
+    ("Ctrl+Shift+End", Command::SelectDocEnd),
+    ("Shift+PageUp", Command::SelectPageUp),
+    ("Shift+PageDown", Command::SelectPageDown),
+    ("Ctrl+Alt+ArrowUp", Command::AddCursorAbove),
+    ("Ctrl+Alt+ArrowDown", Command::AddCursorBelow),
+    ("Ctrl+D", Command::AddNextOccurrence),
+    ("Escape", Command::RemoveSecondaryCursors),
+    ("Alt+Shift+ArrowUp", Command::BlockSelectUp),
+    ("Alt+Shift+ArrowDown", Command::BlockSelectDown),
+    ("Alt+Shift+ArrowLeft", Command::BlockSelectLeft),
+    ("Alt+Shift+ArrowRight", Command::BlockSelectRight),
+    ("Backspace", Command::DeleteBackward),
+    ("Shift+Backspace", Command::DeleteBackward),
+    ("Delete", Command::DeleteForward),
+    ("Enter", Command::InsertNewline),
+    ("Shift+Enter", Command::InsertNewline),
+    ("Tab", Command::InsertTab),
+    ("Shift+Tab", Command::Outdent),
+    ("Ctrl+Z", Command::Undo),
+    ("Ctrl+Shift+Z", Command::Redo),
+    ("Ctrl+C", Command::Copy),
+    ("Ctrl+X", Command::Cut),
+    ("Ctrl+V", Command::Paste),
+    ("Ctrl+O", Command::Open),
+    ("Ctrl+S", Command::Save),
+    ("Ctrl+Shift+S", Command::SaveAs),
+    ("Ctrl+F", Command::Find),
+    ("F3", Command::FindNext),
+    ("Shift+F3", Command::FindPrevious),
+    ("Ctrl+W", Command::CloseTab),
+    ("Ctrl+Tab", Command::NextTab),
+    ("Ctrl+Shift+Tab", Command::PreviousTab),
+    ("Ctrl+Shift+P", Command::ShowPalette),
+];
+
+/// One key press with its modifiers
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub meta: bool,
+    /// Key name from NAMED_KEYS, or a single lowercase character
+    pub key: String,
+}
+
+impl KeyChord {
+    /// Chord for a key event; `None` for a lone modifier key
+    pub fn from_event(key: &Key, modifiers: Modifiers) -> Option<Self> {
+        let mut shift = modifiers.shift();
+        let key = match key {
+            Key::Character(c) if c == " " => "Space".to_string(),
+            Key::Character(c) => {
+                let mut chars = c.chars();
+                let ch = chars.next()?;
+                if chars.next().is_some() {
+                    return None;
+                }
+                // Shift is already part of characters like `?`
+                if !ch.is_alphabetic() {
+                    shift = false;
+                }
+                ch.to_lowercase().to_string()
+            }
+            Key::Shift | Key::Control | Key::Alt | Key::Meta | Key::AltGraph | Key::CapsLock => return None,
+            other => other.to_string(),
+        };
+        Some(KeyChord { ctrl: modifiers.ctrl(), alt: modifiers.alt(), shift, meta: modifiers.meta(), key })
+    }
+
+    /// Parse a chord like `Ctrl+Shift+Z` (case-insensitive)
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut chord = KeyChord { ctrl: false, alt: false, shift: false, meta: false, key: String::new() };
+        // The last part is the key; a trailing `+` means the plus key itself
+        let (modifiers, key) = if text == "+" {
+            ("", "+")
+        } else if let Some(rest) = text.strip_suffix("++") {
+            (rest, "+")
+        } else {
+            text.rsplit_once('+').unwrap_or(("", text))
+        };
+
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            let flag = match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => &mut chord.ctrl,
+                "alt" | "option" => &mut chord.alt,
+                "shift" => &mut chord.shift,
+                "meta" | "cmd" | "super" | "win" => &mut chord.meta,
+                _ => return Err(format!("unknown modifier `{}` in `{}`", modifier, text)),
+            };
+            *flag = true;
+        }
+
+        chord.key = canonical_key(key).ok_or_else(|| format!("unknown key `{}` in `{}`", key, text))?;
+        // Key events report the shifted character (`?`, not Shift+/)
+        if chord.shift && chord.key.chars().count() == 1 && !chord.key.chars().all(char::is_alphabetic) {
+            return Err(format!("use the shifted character instead of Shift in `{}`", text));
+        }
+        Ok(chord)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (on, name) in [(self.ctrl, "Ctrl+"), (self.alt, "Alt+"), (self.shift, "Shift+"), (self.meta, "Meta+")] {
+            if on {
+                f.write_str(name)?;
+            }
+        }
+        if self.key.chars().count() == 1 {
+            write!(f, "{}", self.key.to_uppercase())
+        } else {
+            f.write_str(&self.key)
+        }
+    }
+}
+
+// Normalized key name: a NAMED_KEYS entry or one lowercase character
+fn canonical_key(key: &str) -> Option<String> {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => return Some(ch.to_lowercase().to_string()),
+        (None, _) => return None,
+        _ => {}
+    }
+    let key = KEY_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(key))
+        .map_or(key, |(_, name)| name);
+    NAMED_KEYS.iter().find(|name| name.eq_ignore_ascii_case(key)).map(|name| name.to_string())
+}
+
+/// Parse a space-separated key sequence like `Ctrl+K Ctrl+C`
+pub fn parse_sequence(text: &str) -> Result<Vec<KeyChord>, String> {
+    let sequence = text.split_whitespace().map(KeyChord::parse).collect::<Result<Vec<_>, _>>()?;
+    if sequence.is_empty() {
+        return Err("empty key".to_string());
+    }
+    Ok(sequence)
+}
+
+/// Format a key sequence the way it is written in keybindings.json
+pub fn format_sequence(sequence: &[KeyChord]) -> String {
+    sequence.iter().map(KeyChord::to_string).collect::<Vec<_>>().join(" ")
+}
+
+/// What the keys pressed so far amount to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMatch {
+    /// A complete binding
+    Command(Command),
+    /// The start of a longer sequence; wait for the next key
+    Prefix,
+    /// Not bound
+    Unbound,
+}
+
+/// Key sequence -> command bindings
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    bindings: HashMap<Vec<KeyChord>, Command>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = DEFAULT_BINDINGS
+            .iter()
+            .map(|(key, command)| (parse_sequence(key).expect("default bindings parse"), *command))
+            .collect();
+        Keymap { bindings }
+    }
+}
+
+// Entry in keybindings.json
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BindingEntry {
+    key: String,
+    command: Option<String>,
+}
+
+impl Keymap {
+    /// The default bindings with the ones from `path` applied on top.
+    /// Entries that can't be used are skipped and described in the returned
+    /// list, as are bindings that shadow each other.
+    pub fn load(path: &Path) -> Result<(Self, Vec<String>), KeymapError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((Self::default(), Vec::new())),
+            Err(e) => return Err(KeymapError::Io(path.to_path_buf(), e)),
+        };
+        let entries: Vec<BindingEntry> = serde_json::from_str(&text)
+            .map_err(|e| KeymapError::Parse(path.to_path_buf(), e))?;
+
+        let mut keymap = Self::default();
+        let mut problems = Vec::new();
+        let mut seen: HashMap<Vec<KeyChord>, Option<String>> = HashMap::new();
+
+        for entry in entries {
+            let sequence = match parse_sequence(&entry.key) {
+                Ok(sequence) => sequence,
+                Err(e) => {
+                    problems.push(e);
+                    continue;
+                }
+            };
+            let command = match entry.command.as_deref().map(|name| (name, Command::from_name(name))) {
+                Some((_, Some(command))) => Some(command),
+                Some((name, None)) => {
+                    problems.push(format!("unknown command `{}` for {}", name, entry.key));
+                    continue;
+                }
+                None => None,
+            };
+
+            if let Some(previous) = seen.insert(sequence.clone(), entry.command.clone()) {
+                problems.push(format!(
+                    "{} is bound more than once ({} and {}); the last one wins",
+                    format_sequence(&sequence),
+                    previous.as_deref().unwrap_or("null"),
+                    entry.command.as_deref().unwrap_or("null"),
+                ));
+            }
+            match command {
+                Some(command) => keymap.bindings.insert(sequence, command),
+                None => keymap.bindings.remove(&sequence),
+            };
+        }
+
+        problems.extend(keymap.conflicts());
+        Ok((keymap, problems))
+    }
+
+    /// Bindings that can never run because a shorter one is a prefix of them
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts: Vec<String> = self.bindings
+            .iter()
+            .filter_map(|(sequence, command)| {
+                let shadow = (1..sequence.len()).find_map(|len| {
+                    let prefix = &sequence[..len];
+                    self.bindings.get(prefix).map(|shadow| (prefix, shadow))
+                })?;
+                Some(format!(
+                    "{} ({}) can never run: {} is bound to {}",
+                    format_sequence(sequence),
+                    command.name(),
+                    format_sequence(shadow.0),
+                    shadow.1.name(),
+                ))
+            })
+            .collect();
+        conflicts.sort();
+        conflicts
+    }
+
+    /// Look up the keys pressed so far
+    pub fn resolve(&self, keys: &[KeyChord]) -> KeyMatch {
+        if let Some(command) = self.bindings.get(keys) {
+            return KeyMatch::Command(*command);
+        }
+        let is_prefix = self.bindings.keys().any(|sequence| sequence.len() > keys.len() && sequence.starts_with(keys));
+        if is_prefix { KeyMatch::Prefix } else { KeyMatch::Unbound }
+    }
+
+    /// Key sequences bound to a command, shortest first
+    pub fn keys_for(&self, command: Command) -> Vec<String> {
+        let mut keys: Vec<&Vec<KeyChord>> = self.bindings
+            .iter()
+            .filter(|(_, bound)| **bound == command)
+            .map(|(sequence, _)| sequence)
+            .collect();
+        keys.sort_by_key(|sequence| (sequence.len(), format_sequence(sequence)));
+        keys.into_iter().map(|sequence| format_sequence(sequence)).collect()
+    }
+}
+
+/// Why keybindings.json couldn't be used at all
+#[derive(Debug)]
+pub enum KeymapError {
+    /// The file couldn't be read
+    Io(PathBuf, io::Error),
+    /// Not a JSON list of `{ "key", "command" }` entries
+    Parse(PathBuf, serde_json::Error),
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeymapError::Io(path, e) => write!(f, "Can't read {}: {}", path.display(), e),
+            KeymapError::Parse(path, e) => write!(f, "Error in {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(text: &str) -> KeyChord {
+        KeyChord::parse(text).unwrap()
+    }
+
+    fn keymap(bindings: &[(&str, Command)]) -> Keymap {
+        let bindings = bindings
+            .iter()
+            .map(|(key, command)| (parse_sequence(key).unwrap(), *command))
+            .collect();
+        Keymap { bindings }
+    }
+
+    #[test]
+    fn parse_reads_modifiers_in_any_case_and_order() {
+        let expected = KeyChord { ctrl: true, alt: false, shift: true, meta: false, key: "z".to_string() };
+        assert_eq!(chord("Ctrl+Shift+Z"), expected);
+        assert_eq!(chord("shift+control+z"), expected);
+        assert_eq!(chord("Cmd+S"), KeyChord { meta: true, key: "s".to_string(), ..chord("a") });
+    }
+
+    #[test]
+    fn parse_normalizes_key_names() {
+        assert_eq!(chord("Ctrl+Up").key, "ArrowUp");
+        assert_eq!(chord("esc").key, "Escape");
+        assert_eq!(chord("pgdn").key, "PageDown");
+        assert_eq!(chord("f12").key, "F12");
+    }
+
+    #[test]
+    fn parse_handles_the_plus_key() {
+        assert_eq!(chord("+").key, "+");
+        assert_eq!(chord("Ctrl++"), KeyChord { ctrl: true, key: "+".to_string(), ..chord("a") });
+    }
+
+    #[test]
+    fn parse_rejects_bad_chords() {
+        assert!(KeyChord::parse("Hyper+A").is_err());
+        assert!(KeyChord::parse("Ctrl+Nope").is_err());
+        assert!(KeyChord::parse("Ctrl+").is_err());
+        // `?` already implies Shift
+        assert!(KeyChord::parse("Shift+/").is_err());
+        assert!(parse_sequence("  ").is_err());
+    }
+
+    #[test]
+    fn chords_format_back_to_what_parses() {
+        for text in ["Ctrl+Shift+Z", "Alt+ArrowUp", "Ctrl+K Ctrl+C", "F3", "Ctrl++"] {
+            assert_eq!(format_sequence(&parse_sequence(text).unwrap()), text);
+        }
+    }
+
+    #[test]
+    fn resolve_waits_for_the_rest_of_a_sequence() {
+        let keymap = keymap(&[("Ctrl+K Ctrl+C", Command::Copy), ("Ctrl+S", Command::Save)]);
+        assert_eq!(keymap.resolve(&[chord("Ctrl+S")]), KeyMatch::Command(Command::Save));
+        assert_eq!(keymap.resolve(&[chord("Ctrl+K")]), KeyMatch::Prefix);
+        assert_eq!(keymap.resolve(&[chord("Ctrl+K"), chord("Ctrl+C")]), KeyMatch::Command(Command::Copy));
+        assert_eq!(keymap.resolve(&[chord("Ctrl+K"), chord("Ctrl+X")]), KeyMatch::Unbound);
+        assert_eq!(keymap.resolve(&[chord("Ctrl+Q")]), KeyMatch::Unbound);
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        assert!(Keymap::default().conflicts().is_empty());
+        assert_eq!(Keymap::default().resolve(&[chord("Ctrl+Z")]), KeyMatch::Command(Command::Undo));
+    }
+
+    #[test]
+    fn conflicts_report_shadowed_sequences() {
+        let keymap = keymap(&[("Ctrl+K", Command::Cut), ("Ctrl+K Ctrl+C", Command::Copy)]);
+        assert_eq!(
+            keymap.conflicts(),
+            vec!["Ctrl+K Ctrl+C (clipboard.copy) can never run: Ctrl+K is bound to clipboard.cut"],
+        );
+        assert_eq!(keymap.resolve(&[chord("Ctrl+K")]), KeyMatch::Command(Command::Cut));
+    }
+
+    #[test]
+    fn load_applies_overrides_and_reports_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keybindings.json");
+        fs::write(&path, r#"[
+            { "key": "Ctrl+K Ctrl+C", "command": "clipboard.copy" },
+            { "key": "Ctrl+Z", "command": null },
+            { "key": "Ctrl+Nope", "command": "buffer.undo" },
+            { "key": "Ctrl+Q", "command": "no.such.command" }
+        ]"#).unwrap();
+        let (keymap, problems) = Keymap::load(&path).unwrap();
+        assert_eq!(keymap.resolve(&[chord("Ctrl+K"), chord("Ctrl+C")]), KeyMatch::Command(Command::Copy));
+        assert_eq!(keymap.resolve(&[chord("Ctrl+Z")]), KeyMatch::Unbound);
+        assert_eq!(problems, vec![
+            "unknown key `Nope` in `Ctrl+Nope`",
+            "unknown command `no.such.command` for Ctrl+Q",
+        ]);
+    }
+}
\ No newline at end of file
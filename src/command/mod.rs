@@ -0,0 +1,13 @@
+// Commands and key bindings
+// Named editor commands, the keymap that triggers them, and the fuzzy
+// matching and history used by the command palette
+
+mod fuzzy;
+mod keymap;
+mod recent;
+mod registry;
+
+pub use fuzzy::fuzzy_score;
+pub use keymap::{KeyChord, KeyMatch, Keymap};
+pub use recent::RecentCommands;
+pub use registry::{Command, CommandContext, COMMANDS};
\ No newline at end of file
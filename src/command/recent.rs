@@ -0,0 +1,111 @@
+// Recently run commands
+// Listed first in the command palette and remembered across sessions
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use super::registry::Command;
+
+/// How many recent commands are remembered
+const MAX_RECENT: usize = 10;
+
+/// Recently run commands, newest first
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RecentCommands {
+    commands: Vec<Command>,
+}
+
+impl RecentCommands {
+    /// Where the list is kept between sessions
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("stringr").join("recent-commands.json"))
+    }
+
+    /// Load the list; a missing or unreadable file gives an empty one
+    pub fn load(path: &Path) -> Self {
+        let names: Vec<String> = fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        // Commands may have been renamed or removed since
+        let commands = names.iter().filter_map(|name| Command::from_name(name)).take(MAX_RECENT).collect();
+        RecentCommands { commands }
+    }
+
+    /// Save the list (by command name)
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let names: Vec<&str> = self.commands.iter().map(|command| command.name()).collect();
+        fs::write(path, serde_json::to_vec(&names).map_err(io::Error::other)?)
+    }
+
+    /// Move (or add) a command to the front
+    pub fn record(&mut self, command: Command) {
+        self.commands.retain(|c| *c != command);
+        self.commands.insert(0, command);
+        self.commands.truncate(MAX_RECENT);
+    }
+
+    /// Whether a command was run recently
+    pub fn contains(&self, command: Command) -> bool {
+        self.commands.contains(&command)
+    }
+
+    /// Recent commands, newest first
+    pub fn iter(&self) -> impl Iterator<Item = Command> + '_ {
+        self.commands.iter().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::registry::COMMANDS;
+
+    fn recent(commands: &[Command]) -> RecentCommands {
+        let mut recent = RecentCommands::default();
+        for &command in commands {
+            recent.record(command);
+        }
+        recent
+    }
+
+    #[test]
+    fn record_puts_the_newest_first_without_duplicates() {
+        let recent = recent(&[Command::Copy, Command::Cut, Command::Undo, Command::Cut]);
+        assert_eq!(recent.iter().collect::<Vec<_>>(), vec![Command::Cut, Command::Undo, Command::Copy]);
+        assert!(recent.contains(Command::Copy));
+        assert!(!recent.contains(Command::Redo));
+    }
+
+    #[test]
+    fn record_forgets_the_oldest_past_the_limit() {
+        let all: Vec<Command> = COMMANDS.iter().map(|(command, _, _)| *command).take(MAX_RECENT + 2).collect();
+        let recent = recent(&all);
+        let expected: Vec<Command> = all.iter().rev().take(MAX_RECENT).copied().collect();
+        assert_eq!(recent.iter().collect::<Vec<_>>(), expected);
+        assert!(!recent.contains(all[0]));
+    }
+
+    #[test]
+    fn save_and_load_keep_the_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("recent-commands.json");
+        let recent = recent(&[Command::Copy, Command::Cut, Command::Undo]);
+        recent.save(&path).unwrap();
+        assert_eq!(RecentCommands::load(&path), recent);
+    }
+
+    #[test]
+    fn load_skips_unknown_names_and_bad_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recent-commands.json");
+        fs::write(&path, r#"["no.such.command", "clipboard.copy"]"#).unwrap();
+        assert_eq!(RecentCommands::load(&path), recent(&[Command::Copy]));
+        fs::write(&path, "not json").unwrap();
+        assert_eq!(RecentCommands::load(&path), RecentCommands::default());
+        assert_eq!(RecentCommands::load(&dir.path().join("missing.json")), RecentCommands::default());
+    }
+}
\ No newline at end of file
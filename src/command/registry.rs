@@ -0,0 +1,245 @@
+// Command registry
+// Every action a key binding (or the command palette) can trigger, with the
+// stable name used for it in keybindings.json
+
+use crate::editor::{Buffer, WordRules};
+
+/// An editor command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Command {
+    // Cursor movement
+    CursorUp,
+    CursorDown,
+    CursorLeft,
+    CursorRight,
+    CursorLineStart,
+    CursorLineEnd,
+    CursorSmartHome,
+    CursorWordLeft,
+    CursorWordRight,
+    CursorParagraphUp,
+    CursorParagraphDown,
+    CursorDocStart,
+    CursorDocEnd,
+    CursorPageUp,
+    CursorPageDown,
+
+    // Movement extending the selection
+    SelectUp,
+    SelectDown,
+    SelectLeft,
+    SelectRight,
+    SelectLineStart,
+    SelectLineEnd,
+    SelectSmartHome,
+    SelectWordLeft,
+    SelectWordRight,
+    SelectParagraphUp,
+    SelectParagraphDown,
+    SelectDocStart,
+    SelectDocEnd,
+    SelectPageUp,
+    SelectPageDown,
+
+    // Multiple cursors
+    AddCursorAbove,
+    AddCursorBelow,
+    AddNextOccurrence,
+    RemoveSecondaryCursors,
+
+    // Block selection
+    BlockSelectUp,
+    BlockSelectDown,
+    BlockSelectLeft,
+    BlockSelectRight,
+
+    // Editing
+    DeleteBackward,
+    DeleteForward,
+    InsertNewline,
+    InsertTab,
+    Outdent,
+    Undo,
+    Redo,
+
+    // Clipboard
+    Copy,
+    Cut,
+    Paste,
+
+    // Files
+    Open,
+    Save,
+    SaveAs,
+
+    // Find / replace
+    Find,
+    FindNext,
+    FindPrevious,
+
+    // Tabs
+    CloseTab,
+    NextTab,
+    PreviousTab,
+
+    // Command palette
+    ShowPalette,
+}
+
+/// Name and human-readable title of every command
+pub const COMMANDS: &[(Command, &str, &str)] = &[
+    (Command::CursorUp, "cursor.moveUp", "Move Cursor Up"),
+    (Command::CursorDown, "cursor.moveDown", "Move Cursor Down"),
+    (Command::CursorLeft, "cursor.moveLeft", "Move Cursor Left"),
+    (Command::CursorRight, "cursor.moveRight", "Move Cursor Right"),
+    (Command::CursorLineStart, "cursor.moveLineStart", "Move to Line Start"),
+    (Command::CursorLineEnd, "cursor.moveLineEnd", "Move to Line End"),
+    (Command::CursorSmartHome, "cursor.moveSmartHome", "Move to First Non-Blank / Line Start"),
+    (Command::CursorWordLeft, "cursor.moveWordLeft", "Move Word Left"),
+    (Command::CursorWordRight, "cursor.moveWordRight", "Move Word Right"),
+    (Command::CursorParagraphUp, "cursor.moveParagraphUp", "Move to Previous Paragraph"),
+    (Command::CursorParagraphDown, "cursor.moveParagraphDown", "Move to Next Paragraph"),
+    (Command::CursorDocStart, "cursor.moveDocStart", "Move to Document Start"),
+    (Command::CursorDocEnd, "cursor.moveDocEnd", "Move to Document End"),
+    (Command::CursorPageUp, "cursor.movePageUp", "Page Up"),
+    (Command::CursorPageDown, "cursor.movePageDown", "Page Down"),
+    (Command::SelectUp, "cursor.selectUp", "Select Up"),
+    (Command::SelectDown, "cursor.selectDown", "Select Down"),
+    (Command::SelectLeft, "cursor.selectLeft", "Select Left"),
+    (Command::SelectRight, "cursor.selectRight", "Select Right"),
+    (Command::SelectLineStart, "cursor.selectLineStart", "Select to Line Start"),
+    (Command::SelectLineEnd, "cursor.selectLineEnd", "Select to Line End"),
+    (Command::SelectSmartHome, "cursor.selectSmartHome", "Select to First Non-Blank / Line Start"),
+    (Command::SelectWordLeft, "cursor.selectWordLeft", "Select Word Left"),
+    (Command::SelectWordRight, "cursor.selectWordRight", "Select Word Right"),
+    (Command::SelectParagraphUp, "cursor.selectParagraphUp", "Select to Previous Paragraph"),
+    (Command::SelectParagraphDown, "cursor.selectParagraphDown", "Select to Next Paragraph"),
+    (Command::SelectDocStart, "cursor.selectDocStart", "Select to Document Start"),
+    (Command::SelectDocEnd, "cursor.selectDocEnd", "Select to Document End"),
+    (Command::SelectPageUp, "cursor.selectPageUp", "Select Page Up"),
+    (Command::SelectPageDown, "cursor.selectPageDown", "Select Page Down"),
+    (Command::AddCursorAbove, "cursor.addAbove", "Add Cursor Above"),
+    (Command::AddCursorBelow, "cursor.addBelow", "Add Cursor Below"),
+    (Command::AddNextOccurrence, "cursor.addNextOccurrence", "Add Cursor at Next Occurrence"),
+    (Command::RemoveSecondaryCursors, "cursor.removeSecondary", "Remove Extra Cursors"),
+    (Command::BlockSelectUp, "selection.blockUp", "Block Select Up"),
+    (Command::BlockSelectDown, "selection.blockDown", "Block Select Down"),
+    (Command::BlockSelectLeft, "selection.blockLeft", "Block Select Left"),
+    (Command::BlockSelectRight, "selection.blockRight", "Block Select Right"),
+    (Command::DeleteBackward, "buffer.deleteBackward", "Delete Backward"),
+    (Command::DeleteForward, "buffer.deleteForward", "Delete Forward"),
+    (Command::InsertNewline, "buffer.insertNewline", "Insert Line Break"),
+    (Command::InsertTab, "buffer.insertTab", "Indent / Insert Tab"),
+    (Command::Outdent, "buffer.outdent", "Outdent Lines"),
+    (Command::Undo, "buffer.undo", "Undo"),
+    (Command::Redo, "buffer.redo", "Redo"),
+    (Command::Copy, "clipboard.copy", "Copy"),
+    (Command::Cut, "clipboard.cut", "Cut"),
+    (Command::Paste, "clipboard.paste", "Paste"),
+    (Command::Open, "file.open", "Open File"),
+    (Command::Save, "file.save", "Save"),
+    (Command::SaveAs, "file.saveAs", "Save As"),
+    (Command::Find, "find.open", "Find / Replace"),
+    (Command::FindNext, "find.next", "Find Next"),
+    (Command::FindPrevious, "find.previous", "Find Previous"),
+    (Command::CloseTab, "tab.close", "Close Tab"),
+    (Command::NextTab, "tab.next", "Next Tab"),
+    (Command::PreviousTab, "tab.previous", "Previous Tab"),
+    (Command::ShowPalette, "palette.open", "Show Command Palette"),
+];
+
+/// What buffer commands need besides the buffer
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandContext {
+    /// Word boundaries for word motions
+    pub word_rules: WordRules,
+    /// Lines in a page of the view, for PageUp/PageDown
+    pub page_lines: usize,
+}
+
+impl Command {
+    /// Stable name, e.g. `cursor.moveUp`
+    pub fn name(self) -> &'static str {
+        self.entry().1
+    }
+
+    /// Title shown to the user, e.g. "Move Cursor Up"
+    pub fn title(self) -> &'static str {
+        self.entry().2
+    }
+
+    /// Look a command up by its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        COMMANDS.iter().find(|(_, n, _)| *n == name).map(|(command, _, _)| *command)
+    }
+
+    fn entry(self) -> &'static (Command, &'static str, &'static str) {
+        COMMANDS
+            .iter()
+            .find(|(command, _, _)| *command == self)
+            .expect("every command is listed in COMMANDS")
+    }
+
+    /// Run a command that only needs the buffer. Returns `false` for
+    /// commands that need the rest of the app (clipboard, files, find, tabs,
+    /// the command palette).
+    pub fn apply(self, buffer: &mut Buffer, context: &CommandContext) -> bool {
+        let rules = &context.word_rules;
+        let page = context.page_lines;
+        match self {
+            Command::CursorUp => buffer.move_with_selection(false, Buffer::move_up),
+            Command::CursorDown => buffer.move_with_selection(false, Buffer::move_down),
+            Command::CursorLeft => buffer.move_with_selection(false, Buffer::move_left),
+            Command::CursorRight => buffer.move_with_selection(false, Buffer::move_right),
+            Command::CursorLineStart => buffer.move_with_selection(false, Buffer::move_to_line_start),
+            Command::CursorLineEnd => buffer.move_with_selection(false, Buffer::move_to_line_end),
+            Command::CursorSmartHome => buffer.move_with_selection(false, Buffer::move_to_line_start_smart),
+            Command::CursorWordLeft => buffer.move_with_selection(false, |b| b.move_word_left(rules)),
+            Command::CursorWordRight => buffer.move_with_selection(false, |b| b.move_word_right(rules)),
+            Command::CursorParagraphUp => buffer.move_with_selection(false, Buffer::move_paragraph_up),
+            Command::CursorParagraphDown => buffer.move_with_selection(false, Buffer::move_paragraph_down),
+            Command::CursorDocStart => buffer.move_with_selection(false, Buffer::move_to_doc_start),
+            Command::CursorDocEnd => buffer.move_with_selection(false, Buffer::move_to_doc_end),
+            Command::CursorPageUp => buffer.move_with_selection(false, |b| b.move_page_up(page)),
+            Command::CursorPageDown => buffer.move_with_selection(false, |b| b.move_page_down(page)),
+            Command::SelectUp => buffer.move_with_selection(true, Buffer::move_up),
+            Command::SelectDown => buffer.move_with_selection(true, Buffer::move_down),
+            Command::SelectLeft => buffer.move_with_selection(true, Buffer::move_left),
+            Command::SelectRight => buffer.move_with_selection(true, Buffer::move_right),
+            Command::SelectLineStart => buffer.move_with_selection(true, Buffer::move_to_line_start),
+            Command::SelectLineEnd => buffer.move_with_selection(true, Buffer::move_to_line_end),
+            Command::SelectSmartHome => buffer.move_with_selection(true, Buffer::move_to_line_start_smart),
+            Command::SelectWordLeft => buffer.move_with_selection(true, |b| b.move_word_left(rules)),
+            Command::SelectWordRight => buffer.move_with_selection(true, |b| b.move_word_right(rules)),
+            Command::SelectParagraphUp => buffer.move_with_selection(true, Buffer::move_paragraph_up),
+            Command::SelectParagraphDown => buffer.move_with_selection(true, Buffer::move_paragraph_down),
+            Command::SelectDocStart => buffer.move_with_selection(true, Buffer::move_to_doc_start),
+            Command::SelectDocEnd => buffer.move_with_selection(true, Buffer::move_to_doc_end),
+            Command::SelectPageUp => buffer.move_with_selection(true, |b| b.move_page_up(page)),
+            Command::SelectPageDown => buffer.move_with_selection(true, |b| b.move_page_down(page)),
+            Command::AddCursorAbove => buffer.add_cursor_above(),
+            Command::AddCursorBelow => buffer.add_cursor_below(),
+            Command::AddNextOccurrence => {
+                buffer.add_next_occurrence();
+            }
+            Command::RemoveSecondaryCursors => buffer.clear_secondary_cursors(),
+            Command::BlockSelectUp => buffer.extend_block_up(),
+            Command::BlockSelectDown => buffer.extend_block_down(),
+            Command::BlockSelectLeft => buffer.extend_block_left(),
+            Command::BlockSelectRight => buffer.extend_block_right(),
+            Command::DeleteBackward => buffer.delete_backward(),
+            Command::DeleteForward => buffer.delete_forward(),
+            Command::InsertNewline => buffer.insert_newline(),
+            Command::InsertTab => buffer.indent_or_insert_tab(),
+            Command::Outdent => buffer.outdent_lines(),
+            Command::Undo => {
+                buffer.undo();
+            }
+            Command::Redo => {
+                buffer.redo();
+            }
+            _ => return false,
+        }
+        true
+    }
+}
\ No newline at end of file
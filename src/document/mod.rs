@@ -1 +1,6 @@
-// Document management module TODO
\ No newline at end of file
+// Document management module
+// Open documents (buffer + view state) and the workspace holding them
+
+mod workspace;
+
+pub use workspace::{Document, DocumentId, Workspace};
\ No newline at end of file
@@ -0,0 +1,290 @@
+// Workspace of open documents
+//
+// The active document is checked out into the app's signals while it is being
+// edited; its slot here only keeps its id until it is handed back.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::editor::{Buffer, Viewport};
+
+/// Stable identity of an open document (survives reordering and switching)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DocumentId(u64);
+
+impl DocumentId {
+    fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// An open document: its buffer plus per-document view state
+#[derive(Debug, Clone)]
+pub struct Document {
+    /// Identity of this document
+    pub id: DocumentId,
+    /// Text, cursor and history
+    pub buffer: Buffer,
+    /// Scroll position
+    pub viewport: Viewport,
+}
+
+impl Document {
+    pub fn new(buffer: Buffer) -> Self {
+        Self {
+            id: DocumentId::next(),
+            buffer,
+            viewport: Viewport::new(),
+        }
+    }
+
+    /// Check if this is an untouched, empty, untitled document
+    /// (safe to replace when opening a file)
+    pub fn is_blank(&self) -> bool {
+        self.buffer.path().is_none() && self.buffer.is_empty() && !self.buffer.is_dirty()
+    }
+
+    // Move the contents out, leaving the id behind
+    fn check_out(&mut self) -> Document {
+        Document {
+            id: self.id,
+            buffer: std::mem::take(&mut self.buffer),
+            viewport: std::mem::take(&mut self.viewport),
+        }
+    }
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Self::new(Buffer::new())
+    }
+}
+
+/// All open documents and which one is active
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    // One slot per tab, in tab order
+    documents: Vec<Document>,
+    // Index of the active tab
+    active: usize,
+}
+
+impl Workspace {
+    /// Create a workspace with a single empty document, checked out as the
+    /// active one
+    pub fn new() -> Self {
+        Self {
+            documents: vec![Document::default()],
+            active: 0,
+        }
+    }
+
+    /// Index of the active tab
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// Id of the active document
+    pub fn active_id(&self) -> DocumentId {
+        self.documents[self.active].id
+    }
+
+    /// Number of open documents
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Iterate over all buffers in tab order, using `current` for the active one
+    pub fn buffers<'a>(&'a self, current: &'a Buffer) -> impl Iterator<Item = &'a Buffer> + 'a {
+        self.documents.iter().enumerate().map(move |(idx, doc)| {
+            if idx == self.active { current } else { &doc.buffer }
+        })
+    }
+
+    /// Index of the document open at `path`, if any
+    pub fn find(&self, current: &Buffer, path: &Path) -> Option<usize> {
+        self.buffers(current).position(|buffer| buffer.path().is_some_and(|p| p == path))
+    }
+
+    /// Id of the document open at `path`, if any
+    pub fn find_id(&self, current: &Buffer, path: &Path) -> Option<DocumentId> {
+        self.find(current, path).map(|idx| self.documents[idx].id)
+    }
+
+    /// Buffer of the document `id`, using `current` for the active one;
+    /// `None` if it has been closed
+    pub fn buffer_mut<'a>(&'a mut self, current: &'a mut Buffer, id: DocumentId) -> Option<&'a mut Buffer> {
+        if self.active_id() == id {
+            return Some(current);
+        }
+        self.inactive_mut(id).map(|doc| &mut doc.buffer)
+    }
+
+    /// A document that is not checked out, by id
+    pub fn inactive_mut(&mut self, id: DocumentId) -> Option<&mut Document> {
+        let active = self.active;
+        self.documents
+            .iter_mut()
+            .enumerate()
+            .find(|(idx, doc)| *idx != active && doc.id == id)
+            .map(|(_, doc)| doc)
+    }
+
+    /// Hand back the active document and check out the one at `idx`
+    pub fn switch(&mut self, current: Document, idx: usize) -> Document {
+        let idx = idx.min(self.documents.len() - 1);
+        self.documents[self.active] = current;
+        self.active = idx;
+        self.documents[idx].check_out()
+    }
+
+    /// Index of the tab after the active one (wrapping)
+    pub fn next_index(&self) -> usize {
+        (self.active + 1) % self.documents.len()
+    }
+
+    /// Index of the tab before the active one (wrapping)
+    pub fn prev_index(&self) -> usize {
+        (self.active + self.documents.len() - 1) % self.documents.len()
+    }
+
+    /// Open a new document in a tab after the active one and check it out.
+    /// A blank active document is replaced instead of kept.
+    pub fn open(&mut self, current: Document, doc: Document) -> Document {
+        if current.is_blank() {
+            self.documents[self.active] = doc;
+        } else {
+            self.documents[self.active] = current;
+            self.active += 1;
+            self.documents.insert(self.active, doc);
+        }
+        self.documents[self.active].check_out()
+    }
+
+    /// Close the active document, discarding it, and check out its neighbour.
+    /// Closing the last document leaves a single empty one.
+    pub fn close_active(&mut self) -> Document {
+        self.documents.remove(self.active);
+        if self.documents.is_empty() {
+            self.documents.push(Document::default());
+        }
+        self.active = self.active.min(self.documents.len() - 1);
+        self.documents[self.active].check_out()
+    }
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn file(name: &str) -> Document {
+        Document::new(Buffer::from_file(PathBuf::from(name), format!("{name} contents")))
+    }
+
+    fn name(doc: &Document) -> String {
+        doc.buffer.filename().unwrap_or_default()
+    }
+
+    // Workspace with a.txt, b.txt and c.txt open, c.txt checked out
+    fn three_files() -> (Workspace, Document) {
+        let mut workspace = Workspace::new();
+        let blank = Document { id: workspace.active_id(), ..Document::default() };
+        let current = workspace.open(blank, file("a.txt"));
+        let current = workspace.open(current, file("b.txt"));
+        let current = workspace.open(current, file("c.txt"));
+        (workspace, current)
+    }
+
+    #[test]
+    fn opening_replaces_an_untouched_blank_document() {
+        let mut workspace = Workspace::new();
+        let blank = Document { id: workspace.active_id(), ..Document::default() };
+        let current = workspace.open(blank, file("a.txt"));
+        assert_eq!(workspace.len(), 1);
+        assert_eq!(workspace.active_id(), current.id);
+        assert_eq!(name(&current), "a.txt");
+    }
+
+    #[test]
+    fn opening_keeps_an_edited_blank_document() {
+        let mut workspace = Workspace::new();
+        let mut blank = Document { id: workspace.active_id(), ..Document::default() };
+        blank.buffer.insert_str("notes");
+        workspace.open(blank, file("a.txt"));
+        assert_eq!(workspace.len(), 2);
+        assert_eq!(workspace.active(), 1);
+    }
+
+    #[test]
+    fn opening_inserts_after_the_active_tab() {
+        let (mut workspace, current) = three_files();
+        let current = workspace.switch(current, 0);
+        let current = workspace.open(current, file("d.txt"));
+        assert_eq!(workspace.active(), 1);
+        let names: Vec<String> = workspace.buffers(&current.buffer).filter_map(Buffer::filename).collect();
+        assert_eq!(names, ["a.txt", "d.txt", "b.txt", "c.txt"]);
+    }
+
+    #[test]
+    fn reopening_an_open_path_finds_its_tab() {
+        let (mut workspace, current) = three_files();
+        let idx = workspace.find(&current.buffer, Path::new("a.txt")).unwrap();
+        let id = workspace.find_id(&current.buffer, Path::new("a.txt"));
+        let current = workspace.switch(current, idx);
+        assert_eq!(workspace.len(), 3);
+        assert_eq!(workspace.active(), 0);
+        assert_eq!(Some(current.id), id);
+        assert_eq!(current.buffer.text(), "a.txt contents");
+        // The checked-out document is found through `current`
+        assert_eq!(workspace.find(&current.buffer, Path::new("a.txt")), Some(0));
+        assert_eq!(workspace.find(&current.buffer, Path::new("z.txt")), None);
+    }
+
+    #[test]
+    fn switching_keeps_the_handed_back_document() {
+        let (mut workspace, mut current) = three_files();
+        current.buffer.insert_str("edited ");
+        let current = workspace.switch(current, 0);
+        let current = workspace.switch(current, 2);
+        assert_eq!(current.buffer.text(), "edited c.txt contents");
+    }
+
+    #[test]
+    fn closing_checks_out_a_neighbour() {
+        let (mut workspace, current) = three_files();
+        // Closing the middle tab moves to the one after it
+        let _ = workspace.switch(current, 1);
+        let current = workspace.close_active();
+        assert_eq!((workspace.active(), name(&current)), (1, "c.txt".to_string()));
+        // Closing the last tab moves to the one before it
+        let current = workspace.close_active();
+        assert_eq!((workspace.active(), name(&current)), (0, "a.txt".to_string()));
+        assert_eq!(workspace.active_id(), current.id);
+    }
+
+    #[test]
+    fn closing_the_only_document_leaves_a_blank_one() {
+        let mut workspace = Workspace::new();
+        let current = workspace.close_active();
+        assert_eq!(workspace.len(), 1);
+        assert!(current.is_blank());
+    }
+
+    #[test]
+    fn next_and_previous_wrap_around() {
+        let (mut workspace, current) = three_files();
+        assert_eq!((workspace.prev_index(), workspace.next_index()), (1, 0));
+        let _ = workspace.switch(current, 0);
+        assert_eq!((workspace.prev_index(), workspace.next_index()), (2, 1));
+
+        let single = Workspace::new();
+        assert_eq!((single.prev_index(), single.next_index()), (0, 0));
+    }
+}
\ No newline at end of file
@@ -4,20 +4,55 @@
 #![allow(dead_code)]
 
 use ropey::Rope;
+use std::ops::Range;
 use std::path::PathBuf;
-use super::cursor::Cursor;
+use crate::file::{FileEncoding, LineEnding};
+use crate::theme::editor::TAB_SIZE;
+use super::cursor::{Block, Cursor, CursorSet, Position};
+use super::grapheme;
+use super::history::{Edit, EditKind, History};
+use super::indent::Indent;
+use super::line_change::{ChangeLog, LineChange};
+use super::search::{Search, SearchMatch};
+use super::word::WordRules;
 
 // Text buffer structure
 #[derive(Debug, Clone)]
 pub struct Buffer {
     // Rope that contains the text
     rope: Rope,
-    // Cursor state
+    // Primary cursor (the one the view follows)
     cursor: Cursor,
-    // If rope has unsaved changes
-    dirty: bool,
+    // Secondary cursors for multi-cursor editing, in document order and never
+    // overlapping each other or the primary cursor
+    secondary: Vec<Cursor>,
+    // Index of the secondary cursor swapped into `cursor` while an edit or
+    // motion runs there
+    active_secondary: Option<usize>,
+    // Corners of the block selection the cursors were made from, until
+    // anything else moves them
+    block: Option<Block>,
     // File path if associated w/ a file
     path: Option<PathBuf>,
+    // Undo/redo history
+    history: History,
+    // Bumped on every change to the text
+    revision: u64,
+    // Lines touched by recent changes
+    changes: ChangeLog,
+    // Line-ending style used when saving (the rope only holds `\n`)
+    line_ending: LineEnding,
+    // Line-ending style at the last save, for the dirty check
+    saved_line_ending: LineEnding,
+    // The file had more than one line-ending style when loaded; saving
+    // converts them all to `line_ending`
+    mixed_line_endings: bool,
+    // Encoding (and BOM) of the file on disk
+    encoding: FileEncoding,
+    // Columns between tab stops, for visual columns
+    tab_size: usize,
+    // Indentation inserted by Tab and auto-indent
+    indent: Indent,
 }
 
 impl Buffer {
@@ -26,37 +61,91 @@ impl Buffer {
         Self {
             rope: Rope::new(),
             cursor: Cursor::new(),
-            dirty: false,
+            secondary: Vec::new(),
+            active_secondary: None,
+            block: None,
             path: None,
+            history: History::new(),
+            revision: 0,
+            changes: ChangeLog::default(),
+            line_ending: LineEnding::default(),
+            saved_line_ending: LineEnding::default(),
+            mixed_line_endings: false,
+            encoding: FileEncoding::default(),
+            tab_size: TAB_SIZE as usize,
+            indent: Indent::default(),
         }
     }
 
-    // Create a buffer w/ initial text
+    // Create a buffer w/ initial text (any line-ending style)
     pub fn new_with_text(text: &str) -> Self {
         Self {
-            rope: Rope::from_str(text),
+            rope: Rope::from_str(&LineEnding::normalize(text)),
             cursor: Cursor::new(),
-            dirty: false,
+            secondary: Vec::new(),
+            active_secondary: None,
+            block: None,
             path: None,
+            history: History::new(),
+            revision: 0,
+            changes: ChangeLog::default(),
+            line_ending: LineEnding::detect(text),
+            saved_line_ending: LineEnding::detect(text),
+            mixed_line_endings: LineEnding::is_mixed(text),
+            encoding: FileEncoding::default(),
+            tab_size: TAB_SIZE as usize,
+            indent: Indent::detect(text).unwrap_or_default(),
         }
     }
 
     /// Create a buffer from file content with associated path
     pub fn from_file(path: PathBuf, content: String) -> Self {
         Self {
-            rope: Rope::from_str(&content),
+            rope: Rope::from_str(&LineEnding::normalize(&content)),
             cursor: Cursor::new(),
-            dirty: false,
+            secondary: Vec::new(),
+            active_secondary: None,
+            block: None,
             path: Some(path),
+            history: History::new(),
+            revision: 0,
+            changes: ChangeLog::default(),
+            line_ending: LineEnding::detect(&content),
+            saved_line_ending: LineEnding::detect(&content),
+            mixed_line_endings: LineEnding::is_mixed(&content),
+            encoding: FileEncoding::default(),
+            tab_size: TAB_SIZE as usize,
+            indent: Indent::detect(&content).unwrap_or_default(),
         }
     }
 
     /// Load content from a file, replacing current buffer contents
     pub fn load_content(&mut self, path: PathBuf, content: String) {
-        self.rope = Rope::from_str(&content);
         self.path = Some(path);
         self.cursor = Cursor::new();
-        self.dirty = false;
+        self.replace_content(&content);
+    }
+
+    /// Replace the text with the file's current contents after another
+    /// program changed it, keeping the cursor as close to where it was as possible
+    pub fn reload(&mut self, content: &str) {
+        let Position { line, col } = self.cursor.position;
+        self.cursor = Cursor::new();
+        self.replace_content(content);
+        self.set_cursor_position(line, col, false);
+    }
+
+    // Swap in new clean text, dropping undo history
+    fn replace_content(&mut self, content: &str) {
+        self.drop_secondary();
+        self.rope = Rope::from_str(&LineEnding::normalize(content));
+        self.line_ending = LineEnding::detect(content);
+        self.saved_line_ending = self.line_ending;
+        self.mixed_line_endings = LineEnding::is_mixed(content);
+        self.indent = Indent::detect(content).unwrap_or_default();
+        self.history.clear();
+        self.revision += 1;
+        self.changes.reset(self.revision);
     }
 
     /// Get the filename (just the name, not full path)
@@ -94,111 +183,1137 @@ impl Buffer {
         line_start + self.cursor.position.col
     }
 
-    // Get length of a specific line (in chars, excluding newline)
+    // Char index of the previous cursor stop: the start of the grapheme cluster
+    // before the cursor, or the end of the previous line
+    fn prev_cursor_stop_idx(&self) -> usize {
+        let Position { line, col } = self.cursor.position;
+        if col > 0 {
+            let text = self.line_text(line);
+            self.rope.line_to_char(line) + grapheme::prev_boundary(&text, col)
+        } else if line > 0 {
+            self.rope.line_to_char(line - 1) + self.line_len(line - 1)
+        } else {
+            0
+        }
+    }
+
+    // Char index of the next cursor stop: the end of the grapheme cluster
+    // after the cursor, or the start of the next line
+    fn next_cursor_stop_idx(&self) -> usize {
+        let Position { line, col } = self.cursor.position;
+        if col < self.line_len(line) {
+            let text = self.line_text(line);
+            self.rope.line_to_char(line) + grapheme::next_boundary(&text, col)
+        } else if line + 1 < self.rope.len_lines() {
+            self.rope.line_to_char(line + 1)
+        } else {
+            self.rope.len_chars()
+        }
+    }
+
+    /// Cursor column as display width (tabs expand to the next tab stop,
+    /// wide characters count as 2 cells)
+    pub fn cursor_display_col(&self) -> usize {
+        self.visual_col(self.cursor.position)
+    }
+
+    // Visual column of a position, with tabs expanded
+    fn visual_col(&self, pos: Position) -> usize {
+        grapheme::visual_col(&self.line_text(pos.line), pos.col, self.tab_size)
+    }
+
+    /// Char column on a line at (or just before) a visual column, e.g. for
+    /// mapping a click position to a grapheme boundary
+    pub fn col_at_visual(&self, line: usize, visual: usize) -> usize {
+        grapheme::col_at_visual(&self.line_text(line), visual, self.tab_size)
+    }
+
+    /// Columns between tab stops
+    pub fn tab_size(&self) -> usize {
+        self.tab_size
+    }
+
+    /// Set the columns between tab stops (at least 1)
+    pub fn set_tab_size(&mut self, tab_size: usize) {
+        self.tab_size = tab_size.max(1);
+    }
+
+    /// Indentation inserted by Tab and auto-indent
+    pub fn indent(&self) -> Indent {
+        self.indent
+    }
+
+    /// Switch between tabs and spaces (or the indent width) for new indentation;
+    /// existing text is left alone
+    pub fn set_indent(&mut self, indent: Indent) {
+        self.indent = indent;
+    }
+
+    // Get length of a specific line (in chars, excluding its line break)
     fn line_len(&self, line_idx: usize) -> usize {
         if line_idx >= self.rope.len_lines() {
             return 0;
         }
         let line = self.rope.line(line_idx);
-        // -1 if line ends w/ newline (except last line)
         let len = line.len_chars();
-        if len > 0 && line.char(len - 1) == '\n' {
+        // Edits normalize to `\n`, but the rope also breaks lines on `\r` and
+        // Unicode separators; never count any of them as part of the line
+        let last = |n: usize| len.checked_sub(n).map(|i| line.char(i));
+        if last(2) == Some('\r') && last(1) == Some('\n') {
+            len - 2
+        } else if last(1).is_some_and(is_line_break) {
             len - 1
         } else {
             len
         }
     }
 
-    // Insert char at cursor position
-    pub fn insert_char(&mut self, ch: char) {
-        let idx = self.cursor_char_idx();
-        self.rope.insert_char(idx, ch);
-        self.dirty = true;
+    // Move the cursor to a char index
+    fn set_cursor_char_idx(&mut self, idx: usize) {
+        self.cursor.position = self.char_idx_to_position(idx);
+    }
 
-        // Move cursor forward
-        if ch == '\n' {
-            self.cursor.position.line += 1;
-            self.cursor.position.col = 0;
+    // Move a char index onto a grapheme boundary, in the direction of travel
+    // (word runs can end between a char and the combining mark after it)
+    fn snap_char_idx(&self, idx: usize, forward: bool) -> usize {
+        let Position { line, col } = self.char_idx_to_position(idx);
+        let text = self.line_text(line);
+        let snapped = grapheme::snap(&text, col);
+        let col = if forward && snapped < col { grapheme::next_boundary(&text, snapped) } else { snapped };
+        self.rope.line_to_char(line) + col
+    }
+
+    // Convert a char index into a (line, col) position
+    fn char_idx_to_position(&self, idx: usize) -> Position {
+        let idx = idx.min(self.rope.len_chars());
+        let line = self.rope.char_to_line(idx);
+        Position::new(line, idx - self.rope.line_to_char(line))
+    }
+
+    // Insert into the rope, recording the lines it touched
+    fn rope_insert(&mut self, idx: usize, text: &str) {
+        // Start a line early: joining a CR with an LF changes the line before
+        let start = self.rope.char_to_line(idx.saturating_sub(1));
+        let old_end = self.rope.char_to_line(idx) + 1;
+        self.rope.insert(idx, text);
+        let new_end = self.rope.char_to_line(idx + text.chars().count()) + 1;
+        self.changes.record(self.revision, LineChange { start, old_end, new_end });
+    }
+
+    // Remove from the rope, recording the lines it touched
+    fn rope_remove(&mut self, range: Range<usize>) {
+        let start = self.rope.char_to_line(range.start.saturating_sub(1));
+        let old_end = self.rope.char_to_line(range.end) + 1;
+        self.rope.remove(range.clone());
+        let new_end = self.rope.char_to_line(range.start) + 1;
+        self.changes.record(self.revision, LineChange { start, old_end, new_end });
+    }
+
+    // Flag the text as modified; whether it is dirty follows from the history
+    fn mark_changed(&mut self) {
+        self.revision += 1;
+    }
+
+    // Insert text at a char index, leaving the cursor after it, and record it
+    fn edit_insert(&mut self, idx: usize, text: &str, kind: EditKind) {
+        self.insert_and_record(idx, text, kind, false);
+    }
+
+    // Remove a char range, leaving the cursor at its start, and record it
+    fn edit_remove(&mut self, start: usize, end: usize, kind: EditKind) {
+        self.remove_and_record(start, end, kind, false);
+    }
+
+    // Insert text at a char index and record it. The cursor moves after the
+    // text, or with `keep_cursor` shifts along like the secondary cursors
+    // (for edits away from the cursor, such as indenting its line).
+    fn insert_and_record(&mut self, idx: usize, text: &str, kind: EditKind, keep_cursor: bool) {
+        if text.is_empty() {
+            return;
+        }
+        self.cursor.preferred_col = None;
+        let before = self.cursor_set();
+        let own = self.cursor_char_idxs(&self.cursor);
+        let others = self.secondary_char_idxs();
+        let len = text.chars().count();
+        self.rope_insert(idx, text);
+        self.mark_changed();
+        let shift = |i: usize| if i >= idx { i + len } else { i };
+        if keep_cursor {
+            self.cursor = self.shifted_cursor(&self.cursor, own, shift);
         } else {
-            self.cursor.position.col += 1;
+            self.cursor.clear_selection();
+            self.set_cursor_char_idx(idx + len);
         }
+        self.shift_secondary(others, shift);
+
+        let edit = Edit::Insert { idx, text: text.to_string() };
+        self.history.record(edit, kind, before, self.cursor_set());
     }
 
-    // Insert str at cursor position
-    pub fn insert_str(&mut self, text: &str) {
-        let idx = self.cursor_char_idx();
-        self.rope.insert(idx, text);
-        self.dirty = true;
+    // Remove a char range and record it. The cursor moves to its start, or
+    // with `keep_cursor` shifts along like the secondary cursors.
+    fn remove_and_record(&mut self, start: usize, end: usize, kind: EditKind, keep_cursor: bool) {
+        if start >= end {
+            return;
+        }
+        self.cursor.preferred_col = None;
+        let before = self.cursor_set();
+        let own = self.cursor_char_idxs(&self.cursor);
+        let others = self.secondary_char_idxs();
+        let text = self.rope.slice(start..end).to_string();
+        self.rope_remove(start..end);
+        self.mark_changed();
+        let shift = |i: usize| if i >= end { i - (end - start) } else { i.min(start) };
+        if keep_cursor {
+            self.cursor = self.shifted_cursor(&self.cursor, own, shift);
+        } else {
+            self.cursor.clear_selection();
+            self.set_cursor_char_idx(start);
+        }
+        self.shift_secondary(others, shift);
+
+        let edit = Edit::Delete { idx: start, text };
+        self.history.record(edit, kind, before, self.cursor_set());
+    }
+
+    // Char indices of a cursor's position and anchor
+    fn cursor_char_idxs(&self, cursor: &Cursor) -> (usize, Option<usize>) {
+        (
+            self.position_to_char_idx(cursor.position),
+            cursor.anchor.map(|anchor| self.position_to_char_idx(anchor)),
+        )
+    }
+
+    // Char indices of the secondary cursors, taken before an edit so they
+    // can be shifted past it
+    fn secondary_char_idxs(&self) -> Vec<(usize, Option<usize>)> {
+        self.secondary.iter().map(|c| self.cursor_char_idxs(c)).collect()
+    }
+
+    // A cursor moved to its (pre-edit) char indices mapped through `shift`
+    fn shifted_cursor(&self, cursor: &Cursor, (position, anchor): (usize, Option<usize>), shift: impl Fn(usize) -> usize) -> Cursor {
+        Cursor {
+            position: self.char_idx_to_position(shift(position)),
+            anchor: anchor.map(|anchor| self.char_idx_to_position(shift(anchor))),
+            preferred_col: cursor.preferred_col,
+        }
+    }
+
+    // Move the secondary cursors to their char indices mapped through `shift`
+    fn shift_secondary(&mut self, idxs: Vec<(usize, Option<usize>)>, shift: impl Fn(usize) -> usize) {
+        let moved: Vec<Cursor> = self
+            .secondary
+            .iter()
+            .zip(idxs)
+            .map(|(cursor, idxs)| self.shifted_cursor(cursor, idxs, &shift))
+            .collect();
+        self.secondary = moved;
+    }
+
+    // Cursors as recorded in the undo history, with the primary cursor in its
+    // place even while an edit runs at a secondary one
+    fn cursor_set(&self) -> CursorSet {
+        let mut set = CursorSet { primary: self.cursor.clone(), secondary: self.secondary.clone() };
+        if let Some(i) = self.active_secondary {
+            std::mem::swap(&mut set.primary, &mut set.secondary[i]);
+        }
+        set
+    }
+
+    // Put back cursors from the undo history
+    fn restore_cursors(&mut self, set: CursorSet) {
+        self.block = None;
+        self.cursor = set.primary;
+        self.secondary = set.secondary;
+        self.merge_cursors();
+    }
+
+    // Run `op` at every cursor, then merge cursors that ended up overlapping.
+    // Each secondary cursor is swapped into `cursor` while `op` runs there;
+    // `op` also gets the cursor's index in document order.
+    fn for_each_cursor(&mut self, mut op: impl FnMut(&mut Self, usize)) {
+        self.block = None;
+        let primary_rank = self.secondary.partition_point(|c| c.range() < self.cursor.range());
+        for i in 0..self.secondary.len() {
+            std::mem::swap(&mut self.cursor, &mut self.secondary[i]);
+            self.active_secondary = Some(i);
+            op(self, if i < primary_rank { i } else { i + 1 });
+            self.active_secondary = None;
+            std::mem::swap(&mut self.cursor, &mut self.secondary[i]);
+        }
+        op(self, primary_rank);
+        self.merge_cursors();
+    }
+
+    // Run an edit at every cursor as one undo step, which merges with the
+    // previous step like a single-cursor edit of `kind` would
+    fn edit_each_cursor(&mut self, kind: EditKind, mut op: impl FnMut(&mut Self, usize)) {
+        self.block = None;
+        if self.secondary.is_empty() {
+            op(self, 0);
+            return;
+        }
+        let selecting = self.cursors_in_order().iter().any(|c| c.has_selection());
+        self.history.begin_group();
+        self.for_each_cursor(op);
+        self.history.end_group_as(if selecting { EditKind::Other } else { kind });
+    }
 
-        // Update cursor position based on inserted text
-        for ch in text.chars() {
-            if ch == '\n' {
-                self.cursor.position.line += 1;
-                self.cursor.position.col = 0;
+    // Sort the cursors and merge the ones that overlap; a merge involving
+    // the primary cursor stays primary
+    fn merge_cursors(&mut self) {
+        if self.secondary.is_empty() {
+            return;
+        }
+        let mut cursors: Vec<(Cursor, bool)> = std::iter::once((self.cursor.clone(), true))
+            .chain(self.secondary.drain(..).map(|cursor| (cursor, false)))
+            .collect();
+        cursors.sort_by_key(|(cursor, _)| cursor.range());
+
+        // Sorted by start, a cursor can only overlap the last merged one
+        let mut merged: Vec<(Cursor, bool)> = Vec::with_capacity(cursors.len());
+        for (cursor, primary) in cursors {
+            match merged.last_mut() {
+                Some((last, last_primary)) if last.overlaps(&cursor) => {
+                    if primary {
+                        let mut cursor = cursor;
+                        cursor.merge(last);
+                        *last = cursor;
+                        *last_primary = true;
+                    } else {
+                        last.merge(&cursor);
+                    }
+                }
+                _ => merged.push((cursor, primary)),
+            }
+        }
+        for (cursor, primary) in merged {
+            if primary {
+                self.cursor = cursor;
             } else {
-                self.cursor.position.col += 1;
+                self.secondary.push(cursor);
             }
         }
     }
 
-    /// Delete the character before the cursor (backspace)
+    // Every cursor in document order, the primary one among them
+    fn cursors_in_order(&self) -> Vec<&Cursor> {
+        let primary_rank = self.secondary.partition_point(|c| c.range() < self.cursor.range());
+        let mut cursors: Vec<&Cursor> = self.secondary.iter().collect();
+        cursors.insert(primary_rank, &self.cursor);
+        cursors
+    }
+
+    // Insert char at every cursor, replacing any selection
+    pub fn insert_char(&mut self, ch: char) {
+        // Line breaks are always stored as `\n`
+        let ch = if ch == '\r' { '\n' } else { ch };
+        let mut buf = [0u8; 4];
+        let text = ch.encode_utf8(&mut buf);
+        self.edit_each_cursor(EditKind::Typing, |buffer, _| buffer.insert_at_cursor(text, EditKind::Typing));
+    }
+
+    // Insert str at every cursor, replacing any selection (a single undo step)
+    pub fn insert_str(&mut self, text: &str) {
+        let text = LineEnding::normalize(text);
+        self.edit_each_cursor(EditKind::Other, |buffer, _| buffer.insert_at_cursor(&text, EditKind::Other));
+    }
+
+    /// Paste text at every cursor as one undo step. With several cursors and
+    /// exactly as many lines of text, each cursor gets its own line.
+    pub fn paste(&mut self, text: &str) {
+        let text = LineEnding::normalize(text);
+        let lines: Vec<&str> = text.lines().collect();
+        let split = self.cursor_count() > 1 && lines.len() == self.cursor_count();
+        self.edit_each_cursor(EditKind::Other, |buffer, rank| {
+            let piece = if split { lines[rank] } else { &text };
+            buffer.insert_at_cursor(piece, EditKind::Other);
+        });
+    }
+
+    // Insert text at the cursor, replacing its selection
+    fn insert_at_cursor(&mut self, text: &str, kind: EditKind) {
+        if self.cursor.has_selection() {
+            self.history.begin_group();
+            self.delete_cursor_selection();
+            let idx = self.cursor_char_idx();
+            self.edit_insert(idx, text, EditKind::Other);
+            self.history.end_group();
+        } else {
+            let idx = self.cursor_char_idx();
+            self.edit_insert(idx, text, kind);
+        }
+    }
+
+    /// Delete the character before each cursor (backspace), or the selections
     pub fn delete_backward(&mut self) {
-        let idx = self.cursor_char_idx();
-        if idx == 0 {
-            return; // Nothing to delete
+        self.edit_each_cursor(EditKind::DeleteBackward, |buffer, _| {
+            if buffer.delete_cursor_selection() {
+                return;
+            }
+            let idx = buffer.cursor_char_idx();
+            if idx == 0 {
+                return; // Nothing to delete
+            }
+            // Remove the whole grapheme cluster (or line break) before the cursor
+            let start = buffer.prev_cursor_stop_idx();
+            buffer.edit_remove(start, idx, EditKind::DeleteBackward);
+        });
+    }
+
+    /// Delete the character at each cursor (delete key), or the selections
+    pub fn delete_forward(&mut self) {
+        self.edit_each_cursor(EditKind::DeleteForward, |buffer, _| {
+            if buffer.delete_cursor_selection() {
+                return;
+            }
+            let idx = buffer.cursor_char_idx();
+            if idx >= buffer.rope.len_chars() {
+                return; // Nothing to delete
+            }
+            // Cursor stays in place; remove the whole grapheme cluster (or line break)
+            let end = buffer.next_cursor_stop_idx();
+            buffer.edit_remove(idx, end, EditKind::DeleteForward);
+        });
+    }
+
+    /// Insert a line break at every cursor, carrying over the leading
+    /// whitespace of the line (up to the cursor)
+    pub fn insert_newline(&mut self) {
+        self.edit_each_cursor(EditKind::Other, |buffer, _| {
+            buffer.history.begin_group();
+            buffer.delete_cursor_selection();
+            let Position { line, col } = buffer.cursor.position;
+            let indent: String = buffer.line_text(line)
+                .chars()
+                .take(col)
+                .take_while(|ch| *ch == ' ' || *ch == '\t')
+                .collect();
+            let idx = buffer.cursor_char_idx();
+            buffer.edit_insert(idx, &format!("\n{indent}"), EditKind::Typing);
+            buffer.history.end_group();
+        });
+    }
+
+    /// Tab: indent every selected line when a selection spans lines,
+    /// otherwise insert one level of indentation at each cursor (spaces up to
+    /// the next indent stop with soft tabs)
+    pub fn indent_or_insert_tab(&mut self) {
+        let spans_lines = self.cursors_in_order().iter().any(|c| {
+            let (start, end) = c.range();
+            start.line != end.line
+        });
+        if spans_lines {
+            self.indent_lines();
+            return;
         }
+        let indent = self.indent;
+        self.edit_each_cursor(EditKind::Other, |buffer, _| {
+            let text = match indent {
+                Indent::Tabs => "\t".to_string(),
+                Indent::Spaces(width) => {
+                    let col = buffer.visual_col(buffer.cursor.range().0);
+                    " ".repeat(width - col % width.max(1))
+                }
+            };
+            buffer.insert_at_cursor(&text, EditKind::Typing);
+        });
+    }
 
-        // Check if we're deleting a newline
-        let char_to_delete = self.rope.char(idx - 1);
+    /// Add one level of indentation to every non-blank line the cursors are
+    /// on or select, as one undo step
+    pub fn indent_lines(&mut self) {
+        let unit = self.indent.unit();
+        self.block = None;
+        self.history.begin_group();
+        for line in self.cursor_lines() {
+            if self.is_blank_line(line) {
+                continue;
+            }
+            let idx = self.rope.line_to_char(line);
+            self.insert_and_record(idx, &unit, EditKind::Other, true);
+        }
+        self.history.end_group();
+    }
 
-        self.rope.remove(idx - 1..idx);
-        self.dirty = true;
+    /// Remove up to one level of indentation from every line the cursors are
+    /// on or select (Shift+Tab), as one undo step
+    pub fn outdent_lines(&mut self) {
+        let width = self.indent.width(self.tab_size);
+        self.block = None;
+        self.history.begin_group();
+        for line in self.cursor_lines() {
+            let text = self.line_text(line);
+            let count = if text.starts_with('\t') {
+                1
+            } else {
+                text.chars().take(width).take_while(|ch| *ch == ' ').count()
+            };
+            let idx = self.rope.line_to_char(line);
+            self.remove_and_record(idx, idx + count, EditKind::Other, true);
+        }
+        self.history.end_group();
+        self.merge_cursors();
+    }
 
-        // Move cursor back
-        if char_to_delete == '\n' {
-            self.cursor.position.line -= 1;
-            self.cursor.position.col = self.line_len(self.cursor.position.line);
+    // Lines the cursors are on or select, each once and in order; a
+    // selection ending at the start of a line doesn't include that line
+    fn cursor_lines(&self) -> Vec<usize> {
+        let mut lines: Vec<usize> = self
+            .cursors_in_order()
+            .iter()
+            .flat_map(|c| {
+                let (start, end) = c.range();
+                let last = if end.line > start.line && end.col == 0 { end.line - 1 } else { end.line };
+                start.line..=last
+            })
+            .collect();
+        lines.dedup();
+        lines
+    }
+
+    /// Group all edits until `end_edit_group` into a single undo step
+    pub fn begin_edit_group(&mut self) {
+        self.history.begin_group();
+    }
+
+    /// Close a group started with `begin_edit_group`
+    pub fn end_edit_group(&mut self) {
+        self.history.end_group();
+    }
+
+    /// Undo the most recent edit group, restoring the cursor
+    pub fn undo(&mut self) -> bool {
+        let Some(tx) = self.history.undo() else {
+            return false;
+        };
+        // Revert edits in reverse order
+        for edit in tx.edits.iter().rev() {
+            match edit {
+                Edit::Insert { idx, text } => {
+                    self.rope_remove(*idx..*idx + text.chars().count());
+                }
+                Edit::Delete { idx, text } => {
+                    self.rope_insert(*idx, text);
+                }
+            }
+        }
+        self.restore_cursors(tx.cursor_before);
+        self.mark_changed();
+        true
+    }
+
+    /// Redo the most recently undone edit group
+    pub fn redo(&mut self) -> bool {
+        let Some(tx) = self.history.redo() else {
+            return false;
+        };
+        for edit in &tx.edits {
+            match edit {
+                Edit::Insert { idx, text } => {
+                    self.rope_insert(*idx, text);
+                }
+                Edit::Delete { idx, text } => {
+                    self.rope_remove(*idx..*idx + text.chars().count());
+                }
+            }
+        }
+        self.restore_cursors(tx.cursor_after);
+        self.mark_changed();
+        true
+    }
+
+    /// Check if there is anything to undo
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    /// Check if there is anything to redo
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// Check if there's a non-empty selection
+    pub fn has_selection(&self) -> bool {
+        self.cursor.has_selection()
+    }
+
+    /// Get the selection as (start, end) positions, if any
+    pub fn selection(&self) -> Option<(Position, Position)> {
+        if self.cursor.has_selection() {
+            self.cursor.selection_range()
         } else {
-            self.cursor.position.col -= 1;
+            None
         }
     }
 
-    /// Delete the character at the cursor (delete key)
-    pub fn delete_forward(&mut self) {
-        let idx = self.cursor_char_idx();
-        if idx >= self.rope.len_chars() {
-            return; // Nothing to delete
+    /// Get the selection as a char index range, if any
+    pub fn selection_char_range(&self) -> Option<(usize, usize)> {
+        self.selection()
+            .map(|(start, end)| (self.position_to_char_idx(start), self.position_to_char_idx(end)))
+    }
+
+    /// Get the selected text, if any
+    pub fn selected_text(&self) -> Option<String> {
+        self.selection_char_range()
+            .map(|(start, end)| self.rope.slice(start..end).to_string())
+    }
+
+    /// Delete the selected text at every cursor. Returns false if nothing
+    /// was selected.
+    pub fn delete_selection(&mut self) -> bool {
+        let mut deleted = false;
+        self.edit_each_cursor(EditKind::Other, |buffer, _| deleted |= buffer.delete_cursor_selection());
+        deleted
+    }
+
+    // Delete the cursor's selection. Returns false if nothing was selected.
+    fn delete_cursor_selection(&mut self) -> bool {
+        match self.selection_char_range() {
+            Some((start, end)) => {
+                self.edit_remove(start, end, EditKind::Other);
+                true
+            }
+            None => {
+                self.cursor.clear_selection();
+                false
+            }
+        }
+    }
+
+    /// Text to copy: the selections (one line per cursor), or the cursors'
+    /// lines when nothing is selected. Copied lines always end with a line
+    /// break, even the last line of the buffer, so they paste as whole lines.
+    pub fn copy_text(&self) -> String {
+        let cursors = self.cursors_in_order();
+        if cursors.iter().any(|c| c.has_selection()) {
+            let pieces: Vec<String> = cursors
+                .iter()
+                .map(|c| match c.selection_range() {
+                    Some((start, end)) => {
+                        let (start, end) = (self.position_to_char_idx(start), self.position_to_char_idx(end));
+                        self.rope.slice(start..end).to_string()
+                    }
+                    None => String::new(),
+                })
+                .collect();
+            return pieces.join("\n");
+        }
+        if self.rope.len_chars() == 0 {
+            return String::new();
+        }
+        let mut lines: Vec<usize> = cursors.iter().map(|c| c.position.line).collect();
+        lines.dedup();
+        lines
+            .into_iter()
+            .map(|line| {
+                let (start, end) = self.line_char_range(line);
+                let mut text = self.rope.slice(start..end).to_string();
+                if !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                text
+            })
+            .collect()
+    }
+
+    /// Remove and return the selections, or the cursors' lines when nothing
+    /// is selected (a single undo step)
+    pub fn cut_text(&mut self) -> String {
+        let text = self.copy_text();
+        let cursors = self.cursors_in_order();
+        let selecting = cursors.iter().any(|c| c.has_selection());
+        let lines: Vec<usize> = cursors.iter().map(|c| c.position.line).collect();
+        self.edit_each_cursor(EditKind::Other, |buffer, rank| {
+            if selecting {
+                buffer.delete_cursor_selection();
+            } else if rank == 0 || lines[rank - 1] != lines[rank] {
+                // Cursors sharing a line cut it only once
+                let (start, end) = buffer.line_char_range(buffer.cursor.position.line);
+                buffer.edit_remove(start, end, EditKind::Other);
+            }
+        });
+        text
+    }
+
+    // Char range of a whole line including its line break
+    fn line_char_range(&self, line_idx: usize) -> (usize, usize) {
+        let start = self.rope.line_to_char(line_idx);
+        let end = if line_idx + 1 < self.rope.len_lines() {
+            self.rope.line_to_char(line_idx + 1)
+        } else {
+            self.rope.len_chars()
+        };
+        (start, end)
+    }
+
+    /// Clear the selection without moving the cursor
+    pub fn clear_selection(&mut self) {
+        self.cursor.clear_selection();
+    }
+
+    /// Number of cursors (1 unless multi-cursor editing)
+    pub fn cursor_count(&self) -> usize {
+        self.secondary.len() + 1
+    }
+
+    /// Positions of every cursor, in document order
+    pub fn cursor_positions(&self) -> Vec<Position> {
+        self.cursors_in_order().iter().map(|c| c.position).collect()
+    }
+
+    /// Every cursor's non-empty selection as (start, end), in document order
+    pub fn selections(&self) -> Vec<(Position, Position)> {
+        self.cursors_in_order()
+            .iter()
+            .filter(|c| c.has_selection())
+            .filter_map(|c| c.selection_range())
+            .collect()
+    }
+
+    /// Drop every cursor but the primary one
+    pub fn clear_secondary_cursors(&mut self) {
+        self.drop_secondary();
+    }
+
+    // Back to a single cursor (which also ends any block selection)
+    fn drop_secondary(&mut self) {
+        self.secondary.clear();
+        self.block = None;
+    }
+
+    /// Add a cursor at a (line, col) position (Alt+Click), making it the
+    /// primary one. Clicking on an existing caret removes it instead, as long
+    /// as another cursor is left.
+    pub fn add_cursor_at(&mut self, line: usize, col: usize) {
+        let line = line.min(self.rope.len_lines().saturating_sub(1));
+        let position = Position::new(line, grapheme::snap(&self.line_text(line), col));
+        self.history.break_group();
+        self.block = None;
+
+        if let Some(i) = self.secondary.iter().position(|c| !c.has_selection() && c.position == position) {
+            self.secondary.remove(i);
+            return;
+        }
+        if !self.cursor.has_selection() && self.cursor.position == position {
+            if let Some(next) = self.secondary.pop() {
+                self.cursor = next;
+            }
+            return;
         }
+        self.add_primary_cursor(Cursor::at(position));
+    }
+
+    /// Add a cursor on the line above the topmost cursor, at the same visual
+    /// column (Ctrl+Alt+Up)
+    pub fn add_cursor_above(&mut self) {
+        self.add_cursor_on_adjacent_line(false);
+    }
+
+    /// Add a cursor on the line below the bottommost cursor, at the same
+    /// visual column (Ctrl+Alt+Down)
+    pub fn add_cursor_below(&mut self) {
+        self.add_cursor_on_adjacent_line(true);
+    }
+
+    // Add a primary cursor next to the first or last cursor's line, keeping
+    // its preferred column so repeated adds line up past short lines
+    fn add_cursor_on_adjacent_line(&mut self, down: bool) {
+        self.history.break_group();
+        let cursors = self.cursors_in_order();
+        let edge = if down { cursors[cursors.len() - 1] } else { cursors[0] };
+        let line = if down {
+            edge.position.line + 1
+        } else {
+            match edge.position.line.checked_sub(1) {
+                Some(line) => line,
+                None => return,
+            }
+        };
+        if line >= self.rope.len_lines() {
+            return;
+        }
+        let preferred = edge.preferred_col.unwrap_or_else(|| self.visual_col(edge.position));
+        let col = grapheme::col_at_visual(&self.line_text(line), preferred, self.tab_size);
+        let cursor = Cursor { preferred_col: Some(preferred), ..Cursor::at(Position::new(line, col)) };
+        self.add_primary_cursor(cursor);
+    }
+
+    /// Select the word at the cursor, or add a cursor selecting the next
+    /// occurrence of the primary selection, wrapping around the end (Ctrl+D).
+    /// Returns false if there was nothing to select.
+    pub fn add_next_occurrence(&mut self) -> bool {
+        self.history.break_group();
+        let Some((start, end)) = self.selection_char_range() else {
+            let Position { line, col } = self.cursor.position;
+            let secondary = std::mem::take(&mut self.secondary);
+            self.select_word_at(line, col);
+            self.secondary = secondary;
+            self.merge_cursors();
+            return self.cursor.has_selection();
+        };
+
+        let needle = self.rope.slice(start..end).to_string();
+        let len = end - start;
+        let taken: Vec<(usize, usize)> = self
+            .cursors_in_order()
+            .iter()
+            .filter_map(|c| c.selection_range())
+            .map(|(a, b)| (self.position_to_char_idx(a), self.position_to_char_idx(b)))
+            .collect();
+        let text = self.rope.to_string();
+        let from = self.rope.char_to_byte(end);
+        let found = text[from..]
+            .match_indices(&needle)
+            .map(|(i, _)| from + i)
+            .chain(text[..from].match_indices(&needle).map(|(i, _)| i))
+            .map(|byte| self.rope.byte_to_char(byte))
+            .find(|&idx| !taken.contains(&(idx, idx + len)));
+        let Some(idx) = found else {
+            return false;
+        };
+
+        let cursor = Cursor {
+            anchor: Some(self.char_idx_to_position(idx)),
+            ..Cursor::at(self.char_idx_to_position(idx + len))
+        };
+        self.add_primary_cursor(cursor);
+        true
+    }
+
+    // Make a new cursor primary, keeping the old primary as a secondary one
+    fn add_primary_cursor(&mut self, cursor: Cursor) {
+        self.block = None;
+        let previous = std::mem::replace(&mut self.cursor, cursor);
+        self.secondary.push(previous);
+        self.merge_cursors();
+    }
+
+    /// Check whether the cursors form a block selection
+    pub fn has_block_selection(&self) -> bool {
+        self.block.is_some()
+    }
+
+    /// Select the rectangle between two corners given as (line, visual
+    /// column), with one cursor per line (Alt+Shift+drag). Lines shorter
+    /// than the block get a caret at their end.
+    pub fn select_block(&mut self, anchor: Position, head: Position) {
+        let last = self.rope.len_lines().saturating_sub(1);
+        let anchor = Position::new(anchor.line.min(last), anchor.col);
+        let head = Position::new(head.line.min(last), head.col);
+        self.history.break_group();
+
+        let top = anchor.line.min(head.line);
+        let mut cursors: Vec<Cursor> = (top..=anchor.line.max(head.line))
+            .map(|line| {
+                let text = self.line_text(line);
+                let start = grapheme::col_at_visual(&text, anchor.col, self.tab_size);
+                let end = grapheme::col_at_visual(&text, head.col, self.tab_size);
+                Cursor {
+                    position: Position::new(line, end),
+                    anchor: Some(Position::new(line, start)),
+                    preferred_col: Some(head.col),
+                }
+            })
+            .collect();
+        self.cursor = cursors.remove(head.line - top);
+        self.secondary = cursors;
+        self.block = Some(Block { anchor, head });
+    }
+
+    /// Grow the block selection by a line upwards, starting one at the
+    /// cursor if there is none (Alt+Shift+Up)
+    pub fn extend_block_up(&mut self) {
+        self.extend_block(|_, head| Position::new(head.line.saturating_sub(1), head.col));
+    }
+
+    /// Grow the block selection by a line downwards (Alt+Shift+Down)
+    pub fn extend_block_down(&mut self) {
+        self.extend_block(|buffer, head| {
+            let last = buffer.rope.len_lines().saturating_sub(1);
+            Position::new((head.line + 1).min(last), head.col)
+        });
+    }
+
+    /// Move the block selection's moving edge a column left (Alt+Shift+Left)
+    pub fn extend_block_left(&mut self) {
+        self.extend_block(|buffer, head| Position::new(head.line, buffer.step_visual_col(head, false)));
+    }
 
-        self.rope.remove(idx..idx + 1);
-        self.dirty = true;
-        // Cursor stays in place
+    /// Move the block selection's moving edge a column right, past the end
+    /// of the line if needed (Alt+Shift+Right)
+    pub fn extend_block_right(&mut self) {
+        self.extend_block(|buffer, head| Position::new(head.line, buffer.step_visual_col(head, true)));
+    }
+
+    // Move the head corner of the block selection (a new block starts at the
+    // primary cursor and its selection anchor)
+    fn extend_block(&mut self, step: impl FnOnce(&Self, Position) -> Position) {
+        let block = self.block.unwrap_or_else(|| {
+            let corner = |pos: Position| Position::new(pos.line, self.visual_col(pos));
+            Block {
+                anchor: corner(self.cursor.anchor.unwrap_or(self.cursor.position)),
+                head: corner(self.cursor.position),
+            }
+        });
+        let head = step(self, block.head);
+        self.select_block(block.anchor, head);
+    }
+
+    // Visual column of the grapheme boundary after (or before) a visual
+    // column on a line; past the line end, columns step one at a time
+    fn step_visual_col(&self, at: Position, forward: bool) -> usize {
+        let text = self.line_text(at.line);
+        let col = grapheme::col_at_visual(&text, at.col, self.tab_size);
+        let line_end = grapheme::visual_col(&text, usize::MAX, self.tab_size);
+        if forward {
+            if at.col >= line_end {
+                at.col + 1
+            } else {
+                grapheme::visual_col(&text, grapheme::next_boundary(&text, col), self.tab_size)
+            }
+        } else if at.col > line_end {
+            at.col - 1
+        } else {
+            let start = grapheme::visual_col(&text, col, self.tab_size);
+            if start < at.col {
+                start
+            } else {
+                grapheme::visual_col(&text, grapheme::prev_boundary(&text, col), self.tab_size)
+            }
+        }
+    }
+
+    /// Paste a rectangle of text (copied from a block selection) as one undo
+    /// step. With one cursor per row, each cursor gets its row; otherwise the
+    /// rows go on successive lines at the cursor's visual column, padding
+    /// short lines with spaces and adding lines past the end of the text.
+    pub fn paste_block(&mut self, text: &str) {
+        let text = LineEnding::normalize(text);
+        let rows: Vec<&str> = text.lines().collect();
+        if rows.len() == self.cursor_count() {
+            self.paste(&text);
+            return;
+        }
+
+        self.history.begin_group();
+        self.drop_secondary();
+        self.delete_cursor_selection();
+        let top = self.cursor.position.line;
+        let visual = self.visual_col(self.cursor.position);
+        for (i, row) in rows.iter().enumerate() {
+            let line = top + i;
+            if line >= self.rope.len_lines() {
+                self.edit_insert(self.rope.len_chars(), "\n", EditKind::Other);
+            }
+            let line_text = self.line_text(line);
+            let col = grapheme::col_at_visual(&line_text, visual, self.tab_size);
+            let pad = visual.saturating_sub(grapheme::visual_col(&line_text, col, self.tab_size));
+            let idx = self.rope.line_to_char(line) + col;
+            self.edit_insert(idx, &format!("{}{}", " ".repeat(pad), row), EditKind::Other);
+        }
+        self.history.end_group();
+    }
+
+    /// Run a cursor motion at every cursor, extending the selection if
+    /// `extend` is set (Shift held) or dropping it otherwise
+    pub fn move_with_selection(&mut self, extend: bool, mut motion: impl FnMut(&mut Self)) {
+        self.for_each_cursor(|buffer, _| {
+            if extend {
+                if buffer.cursor.anchor.is_none() {
+                    buffer.cursor.anchor = Some(buffer.cursor.position);
+                }
+            } else {
+                buffer.cursor.clear_selection();
+            }
+            motion(buffer);
+        });
+    }
+
+    /// Move the cursor to a (line, col) position, clamped to the text,
+    /// optionally extending the selection; drops any secondary cursors
+    pub fn set_cursor_position(&mut self, line: usize, col: usize, extend: bool) {
+        self.drop_secondary();
+        let line = line.min(self.rope.len_lines().saturating_sub(1));
+        let col = grapheme::snap(&self.line_text(line), col);
+        self.move_with_selection(extend, |buf| {
+            buf.start_motion();
+            buf.cursor.position = Position::new(line, col);
+        });
+    }
+
+    /// Select the word (or run of whitespace/punctuation) around a position
+    pub fn select_word_at(&mut self, line: usize, col: usize) {
+        let line = line.min(self.rope.len_lines().saturating_sub(1));
+        let chars: Vec<char> = self.rope.line(line).chars().take(self.line_len(line)).collect();
+        let col = col.min(chars.len());
+        if chars.is_empty() {
+            self.set_cursor_position(line, 0, false);
+            return;
+        }
+
+        // Classify by the char under the cursor (or just before it at line end)
+        let probe = col.min(chars.len() - 1);
+        let class = char_class(chars[probe]);
+        let mut start = probe;
+        while start > 0 && char_class(chars[start - 1]) == class {
+            start -= 1;
+        }
+        let mut end = probe + 1;
+        while end < chars.len() && char_class(chars[end]) == class {
+            end += 1;
+        }
+
+        self.drop_secondary();
+        self.start_motion();
+        self.cursor.anchor = Some(Position::new(line, start));
+        self.cursor.position = Position::new(line, end);
+    }
+
+    /// Select a whole line, including its line break
+    pub fn select_line(&mut self, line: usize) {
+        let line = line.min(self.rope.len_lines().saturating_sub(1));
+        self.drop_secondary();
+        self.start_motion();
+        self.cursor.anchor = Some(Position::new(line, 0));
+        if line + 1 < self.rope.len_lines() {
+            self.cursor.position = Position::new(line + 1, 0);
+        } else {
+            self.cursor.position = Position::new(line, self.line_len(line));
+        }
+    }
+
+    /// Find every match of a search, line by line
+    pub fn find_all(&self, search: &Search) -> Vec<SearchMatch> {
+        self.find_in_lines(search, 0..self.rope.len_lines())
+    }
+
+    /// Bring `previous`, the matches of the same search at `revision`, up to
+    /// date by searching only the lines changed since then
+    pub fn find_all_since(&self, search: &Search, previous: &[SearchMatch], revision: u64) -> Vec<SearchMatch> {
+        let Some(change) = self.line_changes_since(revision) else {
+            return self.find_all(search);
+        };
+        let before = previous.partition_point(|m| m.line < change.start);
+        let after = previous.partition_point(|m| m.line < change.old_end);
+        let mut matches = previous[..before].to_vec();
+        matches.extend(self.find_in_lines(search, change.start..change.new_end.min(self.rope.len_lines())));
+        matches.extend(previous[after..].iter().map(|m| SearchMatch {
+            line: m.line - change.old_end + change.new_end,
+            ..*m
+        }));
+        matches
+    }
+
+    // Matches of a search in a range of lines, reading each line straight
+    // from the rope when it isn't split across chunks
+    fn find_in_lines(&self, search: &Search, lines: Range<usize>) -> Vec<SearchMatch> {
+        lines
+            .flat_map(|line| {
+                let slice = self.rope.line(line).slice(..self.line_len(line));
+                let matches = match slice.as_str() {
+                    Some(text) => search.line_matches(text),
+                    None => search.line_matches(&slice.to_string()),
+                };
+                matches.into_iter().map(move |(start_col, end_col)| SearchMatch { line, start_col, end_col })
+            })
+            .collect()
+    }
+
+    /// Select a match, with the cursor at its end
+    pub fn select_match(&mut self, m: SearchMatch) {
+        self.drop_secondary();
+        self.start_motion();
+        self.cursor.anchor = Some(m.start());
+        self.cursor.position = m.end();
+    }
+
+    /// Replace a single match as one undo step. Returns false if the text
+    /// there no longer matches.
+    pub fn replace_match(&mut self, search: &Search, m: SearchMatch, template: &str) -> bool {
+        let replacement = search.line_replacements(&self.line_text(m.line), template)
+            .into_iter()
+            .find(|(cols, _)| *cols == (m.start_col, m.end_col));
+        let Some((_, replacement)) = replacement else {
+            return false;
+        };
+
+        let start = self.position_to_char_idx(m.start());
+        let end = self.position_to_char_idx(m.end());
+        self.history.begin_group();
+        self.edit_remove(start, end, EditKind::Other);
+        self.edit_insert(start, &replacement, EditKind::Other);
+        self.history.end_group();
+        true
+    }
+
+    /// Replace every match as one undo step. Returns the number of replacements.
+    pub fn replace_all(&mut self, search: &Search, template: &str) -> usize {
+        // Work out every replacement first, then apply them back to front so
+        // the char indices of earlier matches stay valid
+        let mut edits = Vec::new();
+        for line in 0..self.rope.len_lines() {
+            let line_start = self.rope.line_to_char(line);
+            for ((start_col, end_col), replacement) in search.line_replacements(&self.line_text(line), template) {
+                edits.push((line_start + start_col, line_start + end_col, replacement));
+            }
+        }
+        if edits.is_empty() {
+            return 0;
+        }
+
+        self.history.begin_group();
+        for (start, end, replacement) in edits.iter().rev() {
+            self.edit_remove(*start, *end, EditKind::Other);
+            self.edit_insert(*start, replacement, EditKind::Other);
+        }
+        self.history.end_group();
+        edits.len()
+    }
+
+    /// Replace the whole text as one undoable edit (e.g. with recovered
+    /// unsaved changes), leaving the cursor at the start
+    pub fn replace_text(&mut self, text: &str) {
+        let text = LineEnding::normalize(text);
+        if self.rope == text.as_ref() {
+            return;
+        }
+        self.history.begin_group();
+        self.edit_remove(0, self.rope.len_chars(), EditKind::Other);
+        self.edit_insert(0, &text, EditKind::Other);
+        self.history.end_group();
+        self.set_cursor_position(0, 0, false);
+    }
+
+    // Convert a (line, col) position into a char index
+    fn position_to_char_idx(&self, pos: Position) -> usize {
+        let line = pos.line.min(self.rope.len_lines().saturating_sub(1));
+        self.rope.line_to_char(line) + pos.col.min(self.line_len(line))
     }
 
     /// Move cursor up one line
     pub fn move_up(&mut self) {
+        self.history.break_group();
         if self.cursor.position.line > 0 {
-            self.cursor.position.line -= 1;
-            // Clamp column to line length
-            let max_col = self.line_len(self.cursor.position.line);
-            self.cursor.position.col = self.cursor.position.col.min(max_col);
+            self.move_to_line(self.cursor.position.line - 1);
         }
     }
 
     /// Move cursor down one line
     pub fn move_down(&mut self) {
+        self.history.break_group();
         if self.cursor.position.line < self.rope.len_lines().saturating_sub(1) {
-            self.cursor.position.line += 1;
-            // Clamp column to line length
-            let max_col = self.line_len(self.cursor.position.line);
-            self.cursor.position.col = self.cursor.position.col.min(max_col);
+            self.move_to_line(self.cursor.position.line + 1);
         }
     }
 
-    /// Move cursor left one character
+    /// Move cursor left one grapheme cluster
     pub fn move_left(&mut self) {
+        self.start_motion();
         if self.cursor.position.col > 0 {
-            self.cursor.position.col -= 1;
+            let text = self.line_text(self.cursor.position.line);
+            self.cursor.position.col = grapheme::prev_boundary(&text, self.cursor.position.col);
         } else if self.cursor.position.line > 0 {
             // Move to end of previous line
             self.cursor.position.line -= 1;
@@ -206,11 +1321,13 @@ impl Buffer {
         }
     }
 
-    /// Move cursor right one character
+    /// Move cursor right one grapheme cluster
     pub fn move_right(&mut self) {
+        self.start_motion();
         let line_len = self.line_len(self.cursor.position.line);
         if self.cursor.position.col < line_len {
-            self.cursor.position.col += 1;
+            let text = self.line_text(self.cursor.position.line);
+            self.cursor.position.col = grapheme::next_boundary(&text, self.cursor.position.col);
         } else if self.cursor.position.line < self.rope.len_lines().saturating_sub(1) {
             // Move to start of next line
             self.cursor.position.line += 1;
@@ -220,36 +1337,210 @@ impl Buffer {
 
     /// Move cursor to start of current line
     pub fn move_to_line_start(&mut self) {
+        self.start_motion();
         self.cursor.position.col = 0;
     }
 
     /// Move cursor to end of current line
     pub fn move_to_line_end(&mut self) {
+        self.start_motion();
         self.cursor.position.col = self.line_len(self.cursor.position.line);
     }
 
-    /// Get an iterator over all lines as strings
+    /// Move to the first non-blank char of the line, or to column 0 if
+    /// already there (smart Home)
+    pub fn move_to_line_start_smart(&mut self) {
+        self.start_motion();
+        let text = self.line_text(self.cursor.position.line);
+        // A combining mark after the indentation belongs to its last char
+        let indent = grapheme::snap(&text, text.chars().take_while(|ch| ch.is_whitespace()).count());
+        self.cursor.position.col = if self.cursor.position.col == indent { 0 } else { indent };
+    }
+
+    /// Move to the end of the next word, crossing line breaks (Ctrl+Right)
+    pub fn move_word_right(&mut self, rules: &WordRules) {
+        self.start_motion();
+        let idx = self.position_to_char_idx(self.cursor.position);
+        let len = rules.motion_len(self.rope.chars_at(idx), true);
+        self.set_cursor_char_idx(self.snap_char_idx(idx + len, true));
+    }
+
+    /// Move to the start of the previous word, crossing line breaks (Ctrl+Left)
+    pub fn move_word_left(&mut self, rules: &WordRules) {
+        self.start_motion();
+        let idx = self.position_to_char_idx(self.cursor.position);
+        let mut chars = self.rope.chars_at(idx);
+        let len = rules.motion_len(std::iter::from_fn(|| chars.prev()), false);
+        self.set_cursor_char_idx(self.snap_char_idx(idx - len, false));
+    }
+
+    /// Move to the blank line after the current paragraph, or the end of
+    /// the document (Ctrl+Down)
+    pub fn move_paragraph_down(&mut self) {
+        self.start_motion();
+        let last = self.rope.len_lines().saturating_sub(1);
+        let mut line = self.cursor.position.line;
+        while line < last && self.is_blank_line(line) {
+            line += 1;
+        }
+        while line < last && !self.is_blank_line(line) {
+            line += 1;
+        }
+        let col = if line == last { self.line_len(line) } else { 0 };
+        self.cursor.position = Position::new(line, col);
+    }
+
+    /// Move to the blank line before the current paragraph, or the start of
+    /// the document (Ctrl+Up)
+    pub fn move_paragraph_up(&mut self) {
+        self.start_motion();
+        let mut line = self.cursor.position.line;
+        while line > 0 && self.is_blank_line(line) {
+            line -= 1;
+        }
+        while line > 0 && !self.is_blank_line(line) {
+            line -= 1;
+        }
+        self.cursor.position = Position::new(line, 0);
+    }
+
+    /// Move to the start of the document (Ctrl+Home)
+    pub fn move_to_doc_start(&mut self) {
+        self.start_motion();
+        self.cursor.position = Position::new(0, 0);
+    }
+
+    /// Move to the end of the document (Ctrl+End)
+    pub fn move_to_doc_end(&mut self) {
+        self.start_motion();
+        self.set_cursor_char_idx(self.rope.len_chars());
+    }
+
+    /// Move up by `lines` lines (a page of the view)
+    pub fn move_page_up(&mut self, lines: usize) {
+        self.history.break_group();
+        let line = self.cursor.position.line.saturating_sub(lines);
+        self.move_to_line(line);
+    }
+
+    /// Move down by `lines` lines (a page of the view)
+    pub fn move_page_down(&mut self, lines: usize) {
+        self.history.break_group();
+        let last = self.rope.len_lines().saturating_sub(1);
+        let line = (self.cursor.position.line + lines).min(last);
+        self.move_to_line(line);
+    }
+
+    // Move to another line, aiming for the preferred visual column (kept
+    // across vertical moves so short lines don't lose it)
+    fn move_to_line(&mut self, line: usize) {
+        let preferred = match self.cursor.preferred_col {
+            Some(col) => col,
+            None => self.visual_col(self.cursor.position),
+        };
+        self.cursor.position.line = line;
+        let text = self.line_text(line);
+        self.cursor.position.col = grapheme::col_at_visual(&text, preferred, self.tab_size);
+        self.cursor.preferred_col = Some(preferred);
+    }
+
+    // Start a motion that isn't vertical, which forgets the preferred column
+    fn start_motion(&mut self) {
+        self.history.break_group();
+        self.cursor.preferred_col = None;
+    }
+
+    // Whether a line is empty or only whitespace
+    fn is_blank_line(&self, line: usize) -> bool {
+        self.rope.line(line).chars().all(char::is_whitespace)
+    }
+
+    /// Get an iterator over all lines as strings (without their line breaks)
     pub fn lines(&self) -> impl Iterator<Item = String> + '_ {
-        self.rope.lines().map(|line| {
-            // Remove trailing newline for display
-            let s = line.to_string();
-            s.trim_end_matches('\n').to_string()
-        })
+        (0..self.rope.len_lines()).map(|idx| self.line_text(idx))
+    }
+
+    /// Get the text of a single line (without its newline)
+    pub fn line_text(&self, line_idx: usize) -> String {
+        if line_idx >= self.rope.len_lines() {
+            return String::new();
+        }
+        let line = self.rope.line(line_idx);
+        line.slice(..self.line_len(line_idx)).to_string()
+    }
+
+    /// Get an iterator over a range of lines as (index, text), only
+    /// converting the lines that are asked for
+    pub fn lines_in(&self, range: Range<usize>) -> impl Iterator<Item = (usize, String)> + '_ {
+        let end = range.end.min(self.rope.len_lines());
+        (range.start.min(end)..end).map(|idx| (idx, self.line_text(idx)))
     }
 
-    /// Get the full text content
+    /// Get the full text content (line breaks as `\n`)
     pub fn text(&self) -> String {
         self.rope.to_string()
     }
 
-    /// Check if buffer has unsaved changes
+    /// Line-ending style used when saving
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    /// Encoding the buffer is saved in
+    pub fn encoding(&self) -> FileEncoding {
+        self.encoding
+    }
+
+    /// Set the encoding of the file the buffer was loaded from
+    pub fn set_encoding(&mut self, encoding: FileEncoding) {
+        self.encoding = encoding;
+    }
+
+    /// Change the line-ending style used when saving; the file changes on
+    /// the next save, so the buffer is dirty until then (or until switched back)
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        if line_ending != self.line_ending {
+            self.line_ending = line_ending;
+            self.mark_changed();
+        }
+    }
+
+    /// Check if buffer has unsaved changes; undoing back to the saved
+    /// state makes it clean again
     pub fn is_dirty(&self) -> bool {
-        self.dirty
+        !self.history.is_at_saved() || self.line_ending != self.saved_line_ending
     }
 
-    /// Mark as saved (clear dirty flag)
+    /// Check if the file had mixed line endings that the next save will
+    /// convert to `line_ending()`
+    pub fn has_mixed_line_endings(&self) -> bool {
+        self.mixed_line_endings
+    }
+
+    /// Mark the current state as saved
     pub fn mark_saved(&mut self) {
-        self.dirty = false;
+        self.history.mark_saved();
+        self.saved_line_ending = self.line_ending;
+        self.mixed_line_endings = false;
+    }
+
+    /// Revision counter, bumped on every change to the text
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Lines changed since `revision`, merged into one change; None if that is
+    /// no longer known and everything should be treated as changed
+    pub fn line_changes_since(&self, revision: u64) -> Option<LineChange> {
+        self.changes.since(revision)
+    }
+
+    /// Mark as saved only if the text hasn't changed since `revision`
+    /// (edits made while an async save was running keep the buffer dirty)
+    pub fn mark_saved_at(&mut self, revision: u64) {
+        if self.revision == revision {
+            self.mark_saved();
+        }
     }
 
     /// Set the file path
@@ -271,7 +1562,11 @@ impl Buffer {
     pub fn clear(&mut self) {
         self.rope = Rope::new();
         self.cursor = Cursor::new();
-        self.dirty = false;
+        self.drop_secondary();
+        self.mixed_line_endings = false;
+        self.history.clear();
+        self.revision += 1;
+        self.changes.reset(self.revision);
     }
 }
 
@@ -279,4 +1574,442 @@ impl Default for Buffer {
     fn default() -> Self {
         Self::new()
     }
+}
+
+// Chars the rope treats as line breaks
+fn is_line_break(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{000B}' | '\u{000C}' | '\u{0085}' | '\u{2028}' | '\u{2029}')
+}
+
+// Character classes used for word selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Word,
+    Whitespace,
+    Punctuation,
+}
+
+fn char_class(ch: char) -> CharClass {
+    if ch.is_alphanumeric() || ch == '_' {
+        CharClass::Word
+    } else if ch.is_whitespace() {
+        CharClass::Whitespace
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use crate::editor::SearchQuery;
+
+    // An edit at a char offset (scaled to the buffer length)
+    #[derive(Debug, Clone)]
+    enum TestEdit {
+        Insert(f64, String),
+        Delete(f64, usize),
+    }
+
+    fn test_edit() -> impl Strategy<Value = TestEdit> {
+        prop_oneof![
+            (0.0..=1.0, "[ab\n]{1,6}").prop_map(|(at, text)| TestEdit::Insert(at, text)),
+            (0.0..=1.0, 1..8usize).prop_map(|(at, len)| TestEdit::Delete(at, len)),
+        ]
+    }
+
+    #[test]
+    fn typing_at_several_cursors_undoes_word_by_word() {
+        let mut buffer = Buffer::new_with_text("\n\n");
+        buffer.add_cursor_below();
+        buffer.add_cursor_below();
+        for ch in "hello world".chars() {
+            buffer.insert_char(ch);
+        }
+        assert_eq!(buffer.text(), "hello world\nhello world\nhello world");
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "hello \nhello \nhello ");
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "\n\n");
+        assert_eq!(buffer.cursor_count(), 3);
+    }
+
+    #[test]
+    fn moving_cursors_starts_a_new_undo_step() {
+        let mut buffer = Buffer::new_with_text("ab\ncd");
+        buffer.add_cursor_below();
+        buffer.insert_char('x');
+        buffer.move_with_selection(false, Buffer::move_right);
+        buffer.insert_char('y');
+        assert_eq!(buffer.text(), "xayb\nxcyd");
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "xab\nxcd");
+    }
+
+    #[test]
+    fn replace_all_is_one_undo_step() {
+        let mut buffer = Buffer::new_with_text("foo bar foo\nfoo\nbaz");
+        let search = Search::new(&SearchQuery { pattern: "foo".to_string(), ..Default::default() }).unwrap();
+        assert_eq!(buffer.replace_all(&search, "quux"), 3);
+        assert_eq!(buffer.text(), "quux bar quux\nquux\nbaz");
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "foo bar foo\nfoo\nbaz");
+    }
+
+    #[test]
+    fn replace_all_expands_groups_in_regex_mode() {
+        let mut buffer = Buffer::new_with_text("é=1, b=22");
+        let query = SearchQuery { pattern: r"(\w)=(\d+)".to_string(), regex: true, ..Default::default() };
+        let search = Search::new(&query).unwrap();
+        assert_eq!(buffer.replace_all(&search, "$2=$1"), 2);
+        assert_eq!(buffer.text(), "1=é, 22=b");
+    }
+
+    #[test]
+    fn replace_all_without_matches_leaves_the_buffer_clean() {
+        let mut buffer = Buffer::new_with_text("abc");
+        let search = Search::new(&SearchQuery { pattern: "x".to_string(), ..Default::default() }).unwrap();
+        assert_eq!(buffer.replace_all(&search, "y"), 0);
+        assert!(!buffer.is_dirty());
+        assert!(!buffer.undo());
+    }
+
+    #[test]
+    fn indent_lines_adds_one_level_to_each_selected_line() {
+        let mut buffer = Buffer::new_with_text("\tone\n  two\n\nthree\nfour");
+        buffer.set_indent(Indent::Spaces(4));
+        buffer.set_cursor_position(0, 2, false);
+        buffer.set_cursor_position(3, 1, true);
+        buffer.indent_lines();
+        // Blank lines stay blank; the line after the selection is untouched
+        assert_eq!(buffer.text(), "    \tone\n      two\n\n    three\nfour");
+        assert_eq!(buffer.selection(), Some((Position::new(0, 6), Position::new(3, 5))));
+
+        buffer.set_indent(Indent::Tabs);
+        buffer.indent_lines();
+        assert_eq!(buffer.text(), "\t    \tone\n\t      two\n\n\t    three\nfour");
+        assert!(buffer.undo());
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "\tone\n  two\n\nthree\nfour");
+    }
+
+    #[test]
+    fn outdent_lines_removes_up_to_one_level_from_each_selected_line() {
+        let mut buffer = Buffer::new_with_text("\t\tone\n  two\n      three\n \tfour\n    five");
+        buffer.set_indent(Indent::Spaces(4));
+        // Ends at the start of the last line, which isn't included
+        buffer.set_cursor_position(0, 0, false);
+        buffer.set_cursor_position(4, 0, true);
+        buffer.outdent_lines();
+        // One tab, or up to four spaces, whichever the line starts with
+        assert_eq!(buffer.text(), "\tone\ntwo\n  three\n\tfour\n    five");
+        buffer.outdent_lines();
+        assert_eq!(buffer.text(), "one\ntwo\nthree\nfour\n    five");
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "\tone\ntwo\n  three\n\tfour\n    five");
+    }
+
+    #[test]
+    fn preferred_column_survives_shorter_lines() {
+        let mut buffer = Buffer::new_with_text("abcdefgh\nab\n\nabcdefgh");
+        buffer.set_cursor_position(0, 6, false);
+        buffer.move_down();
+        assert_eq!(buffer.cursor_positions(), vec![Position::new(1, 2)]);
+        buffer.move_down();
+        assert_eq!(buffer.cursor_positions(), vec![Position::new(2, 0)]);
+        buffer.move_down();
+        assert_eq!(buffer.cursor_positions(), vec![Position::new(3, 6)]);
+        buffer.move_page_up(3);
+        assert_eq!(buffer.cursor_positions(), vec![Position::new(0, 6)]);
+    }
+
+    #[test]
+    fn preferred_column_is_visual_across_tabs() {
+        let mut buffer = Buffer::new_with_text("abcdefgh\n\tx\n\t\ty\nabcdefghij");
+        buffer.set_tab_size(4);
+        buffer.set_cursor_position(0, 5, false);
+        buffer.move_down();
+        // Column 5 is just past the tab and the "x"
+        assert_eq!(buffer.cursor_positions(), vec![Position::new(1, 2)]);
+        buffer.move_down();
+        // Inside the second tab, which starts at column 4
+        assert_eq!(buffer.cursor_positions(), vec![Position::new(2, 1)]);
+        buffer.move_down();
+        assert_eq!(buffer.cursor_positions(), vec![Position::new(3, 5)]);
+
+        // Starting after two tabs aims for column 8
+        buffer.set_cursor_position(2, 2, false);
+        buffer.move_down();
+        assert_eq!(buffer.cursor_positions(), vec![Position::new(3, 8)]);
+    }
+
+    #[test]
+    fn horizontal_moves_and_edits_reset_the_preferred_column() {
+        let mut buffer = Buffer::new_with_text("abcdefgh\nab\nabcdefgh");
+        buffer.set_cursor_position(0, 6, false);
+        buffer.move_down();
+        buffer.move_left();
+        buffer.move_down();
+        assert_eq!(buffer.cursor_positions(), vec![Position::new(2, 1)]);
+
+        buffer.set_cursor_position(0, 6, false);
+        buffer.move_down();
+        buffer.insert_char('x');
+        buffer.move_down();
+        assert_eq!(buffer.cursor_positions(), vec![Position::new(2, 3)]);
+
+        buffer.set_cursor_position(0, 6, false);
+        buffer.move_down();
+        buffer.delete_backward();
+        buffer.move_down();
+        assert_eq!(buffer.cursor_positions(), vec![Position::new(2, 2)]);
+    }
+
+    #[test]
+    fn backward_selections_report_start_before_end() {
+        let mut buffer = Buffer::new_with_text("one two\nthree");
+        buffer.set_cursor_position(1, 2, false);
+        buffer.set_cursor_position(0, 4, true);
+        assert_eq!(buffer.selection(), Some((Position::new(0, 4), Position::new(1, 2))));
+        assert_eq!(buffer.selected_text().as_deref(), Some("two\nth"));
+        // Same line, head left of the anchor
+        buffer.set_cursor_position(0, 3, false);
+        buffer.set_cursor_position(0, 1, true);
+        assert_eq!(buffer.selection(), Some((Position::new(0, 1), Position::new(0, 3))));
+    }
+
+    #[test]
+    fn typing_replaces_the_selection() {
+        let mut buffer = Buffer::new_with_text("one two\nthree");
+        buffer.set_cursor_position(1, 2, false);
+        buffer.set_cursor_position(0, 4, true);
+        buffer.insert_char('X');
+        assert_eq!(buffer.text(), "one Xree");
+        assert_eq!(buffer.cursor_positions(), vec![Position::new(0, 5)]);
+        assert!(!buffer.has_selection());
+        assert!(buffer.undo());
+        assert_eq!(buffer.text(), "one two\nthree");
+    }
+
+    #[test]
+    fn delete_and_backspace_remove_only_the_selection() {
+        for forward in [false, true] {
+            let mut buffer = Buffer::new_with_text("one two three");
+            buffer.set_cursor_position(0, 7, false);
+            buffer.set_cursor_position(0, 4, true);
+            if forward {
+                buffer.delete_forward();
+            } else {
+                buffer.delete_backward();
+            }
+            assert_eq!(buffer.text(), "one  three");
+            assert_eq!(buffer.cursor_positions(), vec![Position::new(0, 4)]);
+            assert!(!buffer.has_selection());
+        }
+    }
+
+    #[test]
+    fn select_word_at_picks_the_run_under_the_position() {
+        let mut buffer = Buffer::new_with_text("let foo_bar = a.b;\n\nx");
+        buffer.select_word_at(0, 6);
+        assert_eq!(buffer.selected_text().as_deref(), Some("foo_bar"));
+        // The char after the position decides, even at the end of a word
+        buffer.select_word_at(0, 3);
+        assert_eq!(buffer.selected_text().as_deref(), Some(" "));
+        buffer.select_word_at(0, 16);
+        assert_eq!(buffer.selected_text().as_deref(), Some("b"));
+        // Past the end of the line selects the last run
+        buffer.select_word_at(0, 99);
+        assert_eq!(buffer.selected_text().as_deref(), Some(";"));
+        // An empty line just places the cursor
+        buffer.select_word_at(1, 0);
+        assert!(!buffer.has_selection());
+        assert_eq!(buffer.cursor_positions(), vec![Position::new(1, 0)]);
+    }
+
+    #[test]
+    fn select_line_includes_the_line_break() {
+        let mut buffer = Buffer::new_with_text("one\ntwo\nthree");
+        buffer.select_line(1);
+        assert_eq!(buffer.selected_text().as_deref(), Some("two\n"));
+        // The last line has no break to include
+        buffer.select_line(5);
+        assert_eq!(buffer.selected_text().as_deref(), Some("three"));
+        buffer.delete_backward();
+        assert_eq!(buffer.text(), "one\ntwo\n");
+    }
+
+    // Lines of words, indentation, punctuation, combining accents, wide
+    // characters and emoji with modifiers
+    fn motion_text() -> impl Strategy<Value = String> {
+        let pieces = prop_oneof![
+            Just("ab"), Just("Cd_e"), Just(" "), Just("\t"), Just("\n"), Just("\n\n"), Just("."),
+            Just("!\u{301}"), Just("e\u{301}"), Just("日本"), Just("👍🏽"), Just("👨\u{200d}👩"),
+        ];
+        prop::collection::vec(pieces, 0..30).prop_map(|pieces| pieces.concat())
+    }
+
+    // A buffer with the cursor on a valid position picked from two fractions
+    fn buffer_at(text: &str, line: f64, col: f64) -> Buffer {
+        let mut buffer = Buffer::new_with_text(text);
+        let line = (line * buffer.line_count() as f64) as usize;
+        let line = line.min(buffer.line_count() - 1);
+        let text = buffer.line_text(line);
+        let col = grapheme::snap(&text, (col * text.chars().count() as f64) as usize);
+        buffer.set_cursor_position(line, col, false);
+        buffer
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum Motion {
+        WordLeft,
+        WordRight,
+        ParagraphUp,
+        ParagraphDown,
+        PageUp,
+        PageDown,
+        SmartHome,
+        DocStart,
+        DocEnd,
+    }
+
+    fn motion() -> impl Strategy<Value = Motion> {
+        prop_oneof![
+            Just(Motion::WordLeft), Just(Motion::WordRight), Just(Motion::ParagraphUp), Just(Motion::ParagraphDown),
+            Just(Motion::PageUp), Just(Motion::PageDown), Just(Motion::SmartHome), Just(Motion::DocStart),
+            Just(Motion::DocEnd),
+        ]
+    }
+
+    fn apply(buffer: &mut Buffer, motion: Motion, rules: &WordRules) {
+        match motion {
+            Motion::WordLeft => buffer.move_word_left(rules),
+            Motion::WordRight => buffer.move_word_right(rules),
+            Motion::ParagraphUp => buffer.move_paragraph_up(),
+            Motion::ParagraphDown => buffer.move_paragraph_down(),
+            Motion::PageUp => buffer.move_page_up(3),
+            Motion::PageDown => buffer.move_page_down(3),
+            Motion::SmartHome => buffer.move_to_line_start_smart(),
+            Motion::DocStart => buffer.move_to_doc_start(),
+            Motion::DocEnd => buffer.move_to_doc_end(),
+        }
+    }
+
+    fn position(buffer: &Buffer) -> Position {
+        Position::new(buffer.cursor_line(), buffer.cursor_col())
+    }
+
+    fn end_position(buffer: &Buffer) -> Position {
+        let last = buffer.line_count() - 1;
+        Position::new(last, buffer.line_len(last))
+    }
+
+    proptest! {
+        #[test]
+        fn motions_land_on_grapheme_boundaries(
+            text in motion_text(),
+            line in 0.0..1.0f64,
+            col in 0.0..=1.0f64,
+            motions in prop::collection::vec(motion(), 1..8),
+            subwords in any::<bool>(),
+        ) {
+            let rules = WordRules { subwords, ..WordRules::default() };
+            let mut buffer = buffer_at(&text, line, col);
+            for motion in motions {
+                apply(&mut buffer, motion, &rules);
+                let Position { line, col } = position(&buffer);
+                prop_assert!(line < buffer.line_count());
+                let boundaries = grapheme::boundaries(&buffer.line_text(line));
+                prop_assert!(boundaries.contains(&col), "{:?} left the cursor at {}:{}", motion, line, col);
+            }
+        }
+
+        #[test]
+        fn motions_are_monotonic(
+            text in motion_text(),
+            line in 0.0..1.0f64,
+            col in 0.0..=1.0f64,
+            motion in motion(),
+            subwords in any::<bool>(),
+        ) {
+            let rules = WordRules { subwords, ..WordRules::default() };
+            let mut buffer = buffer_at(&text, line, col);
+            let before = position(&buffer);
+            apply(&mut buffer, motion, &rules);
+            let after = position(&buffer);
+            let start = Position::new(0, 0);
+            let end = end_position(&buffer);
+            match motion {
+                Motion::WordLeft | Motion::ParagraphUp => {
+                    prop_assert!(after < before || before == start, "{:?}: {:?} -> {:?}", motion, before, after);
+                }
+                Motion::WordRight | Motion::ParagraphDown => {
+                    prop_assert!(after > before || before == end, "{:?}: {:?} -> {:?}", motion, before, after);
+                }
+                Motion::PageUp => prop_assert!(after.line <= before.line),
+                Motion::PageDown => prop_assert!(after.line >= before.line),
+                Motion::SmartHome => prop_assert_eq!(after.line, before.line),
+                Motion::DocStart => prop_assert_eq!(after, start),
+                Motion::DocEnd => prop_assert_eq!(after, end),
+            }
+        }
+
+        #[test]
+        fn word_motions_round_trip_to_the_ends(text in motion_text(), subwords in any::<bool>()) {
+            let rules = WordRules { subwords, ..WordRules::default() };
+            let mut buffer = Buffer::new_with_text(&text);
+            let end = end_position(&buffer);
+            // Every step makes progress, so this many steps always reach the end
+            for _ in 0..=buffer.char_count() {
+                buffer.move_word_right(&rules);
+            }
+            prop_assert_eq!(position(&buffer), end);
+            for _ in 0..=buffer.char_count() {
+                buffer.move_word_left(&rules);
+            }
+            prop_assert_eq!(position(&buffer), Position::new(0, 0));
+        }
+
+        #[test]
+        fn smart_home_toggles_between_indent_and_column_zero(text in motion_text(), line in 0.0..1.0f64, col in 0.0..=1.0f64) {
+            let mut buffer = buffer_at(&text, line, col);
+            buffer.move_to_line_start_smart();
+            let first = buffer.cursor_col();
+            buffer.move_to_line_start_smart();
+            let second = buffer.cursor_col();
+            prop_assert!(first == 0 || second == 0);
+            buffer.move_to_line_start_smart();
+            prop_assert_eq!(buffer.cursor_col(), first);
+        }
+
+        #[test]
+        fn incremental_matches_equal_full_search(
+            text in "[ab\n]{0,60}",
+            edits in prop::collection::vec(test_edit(), 1..6),
+        ) {
+            let query = SearchQuery { pattern: "ab".to_string(), ..SearchQuery::default() };
+            let search = Search::new(&query).unwrap();
+            let mut buffer = Buffer::new_with_text(&text);
+            let mut revision = buffer.revision();
+            let mut matches = buffer.find_all(&search);
+
+            for edit in edits {
+                let len = buffer.char_count();
+                match edit {
+                    TestEdit::Insert(at, text) => {
+                        let idx = (at * len as f64) as usize;
+                        buffer.edit_insert(idx, &text, EditKind::Other);
+                    }
+                    TestEdit::Delete(at, count) => {
+                        let idx = (at * len as f64) as usize;
+                        buffer.edit_remove(idx, (idx + count).min(len), EditKind::Other);
+                    }
+                }
+                matches = buffer.find_all_since(&search, &matches, revision);
+                revision = buffer.revision();
+                prop_assert_eq!(&matches, &buffer.find_all(&search));
+            }
+        }
+    }
 }
\ No newline at end of file
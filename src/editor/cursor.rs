@@ -3,8 +3,9 @@
 // Allow unused - these are API methods for future use
 #![allow(dead_code)]
 
-/// A position in the text buffer (line, column)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// A position in the text buffer (line, column); positions order by line,
+/// then column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
 pub struct Position {
     /// Line index (0-based)
     pub line: usize,
@@ -19,12 +20,15 @@ impl Position {
 }
 
 /// Cursor state including position and optional selection
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Cursor {
     /// Current cursor position
     pub position: Position,
     /// Selection anchor (if selecting)
     pub anchor: Option<Position>,
+    /// Visual column to aim for when moving up or down, kept across
+    /// vertical moves and reset by horizontal moves and edits
+    pub preferred_col: Option<usize>,
 }
 
 impl Cursor {
@@ -32,9 +36,14 @@ impl Cursor {
         Self::default()
     }
 
-    /// Check if there's an active selection
+    /// A caret at a position, without a selection
+    pub fn at(position: Position) -> Self {
+        Self { position, ..Self::default() }
+    }
+
+    /// Check if there's an active, non-empty selection
     pub fn has_selection(&self) -> bool {
-        self.anchor.is_some()
+        self.anchor.is_some_and(|anchor| anchor != self.position)
     }
 
     /// Get the selection range (start, end) if any
@@ -49,8 +58,64 @@ impl Cursor {
         })
     }
 
+    /// The selection range, or the cursor position twice without a selection
+    pub fn range(&self) -> (Position, Position) {
+        self.selection_range().unwrap_or((self.position, self.position))
+    }
+
+    /// Check whether two cursors overlap and should become one: their
+    /// selections share text, or a caret sits inside (or at the edge of) the
+    /// other's selection
+    pub fn overlaps(&self, other: &Cursor) -> bool {
+        let (a_start, a_end) = self.range();
+        let (b_start, b_end) = other.range();
+        if a_start == a_end || b_start == b_end {
+            a_start <= b_end && b_start <= a_end
+        } else {
+            a_start < b_end && b_start < a_end
+        }
+    }
+
+    /// Grow this cursor to also cover `other`, keeping its own direction
+    pub fn merge(&mut self, other: &Cursor) {
+        let (a_start, a_end) = self.range();
+        let (b_start, b_end) = other.range();
+        let (start, end) = (a_start.min(b_start), a_end.max(b_end));
+        if start == end {
+            self.anchor = None;
+            self.position = start;
+        } else if self.anchor.is_some_and(|anchor| anchor > self.position) {
+            self.anchor = Some(end);
+            self.position = start;
+        } else {
+            self.anchor = Some(start);
+            self.position = end;
+        }
+    }
+
     /// Clear any selection
     pub fn clear_selection(&mut self) {
         self.anchor = None;
     }
+}
+
+/// Every cursor of a buffer: the primary one (which the view follows) and
+/// any secondary cursors added for multi-cursor editing
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CursorSet {
+    /// The primary cursor
+    pub primary: Cursor,
+    /// Secondary cursors, in document order
+    pub secondary: Vec<Cursor>,
+}
+
+/// A rectangular selection between two corners. Columns are visual (tabs
+/// expanded) so the block stays straight across lines; each line of it is
+/// selected by its own cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Block {
+    /// Corner where the selection started
+    pub anchor: Position,
+    /// Corner that moves as the selection grows
+    pub head: Position,
 }
\ No newline at end of file
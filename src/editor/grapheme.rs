@@ -0,0 +1,132 @@
+// Grapheme cluster and display-width helpers
+// Columns are char offsets within a line; these helpers keep them on
+// extended grapheme cluster boundaries (emoji ZWJ sequences, combining accents...)
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Char columns of every grapheme boundary in a line, including 0 and the end
+pub fn boundaries(text: &str) -> Vec<usize> {
+    let mut cols = Vec::with_capacity(text.len() + 1);
+    let mut col = 0;
+    cols.push(0);
+    for grapheme in text.graphemes(true) {
+        col += grapheme.chars().count();
+        cols.push(col);
+    }
+    cols
+}
+
+/// Column of the grapheme boundary before `col` (0 at the start)
+pub fn prev_boundary(text: &str, col: usize) -> usize {
+    boundaries(text)
+        .into_iter()
+        .take_while(|&b| b < col)
+        .last()
+        .unwrap_or(0)
+}
+
+/// Column of the grapheme boundary after `col` (line length at the end)
+pub fn next_boundary(text: &str, col: usize) -> usize {
+    let cols = boundaries(text);
+    let end = cols.last().copied().unwrap_or(0);
+    cols.into_iter().find(|&b| b > col).unwrap_or(end)
+}
+
+/// Snap a column back onto the nearest grapheme boundary at or before it
+pub fn snap(text: &str, col: usize) -> usize {
+    boundaries(text)
+        .into_iter()
+        .take_while(|&b| b <= col)
+        .last()
+        .unwrap_or(0)
+}
+
+/// Visual column (in cells) of char column `col`: tabs advance to the next
+/// multiple of `tab_size`, wide CJK characters and most emoji count as 2
+pub fn visual_col(text: &str, col: usize, tab_size: usize) -> usize {
+    let byte_end = text.char_indices().nth(col).map_or(text.len(), |(i, _)| i);
+    text[..byte_end].graphemes(true).fold(0, |visual, g| advance(visual, g, tab_size))
+}
+
+/// Char column of the last grapheme boundary whose visual column is at or
+/// before `visual` (the line end if the line is shorter)
+pub fn col_at_visual(text: &str, visual: usize, tab_size: usize) -> usize {
+    let mut col = 0;
+    let mut current = 0;
+    for grapheme in text.graphemes(true) {
+        current = advance(current, grapheme, tab_size);
+        if current > visual {
+            break;
+        }
+        col += grapheme.chars().count();
+    }
+    col
+}
+
+// Visual column after drawing a grapheme at `visual`
+fn advance(visual: usize, grapheme: &str, tab_size: usize) -> usize {
+    if grapheme == "\t" {
+        let tab_size = tab_size.max(1);
+        (visual / tab_size + 1) * tab_size
+    } else {
+        visual + grapheme.width().max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "e" + combining acute, a thumbs up with a skin tone, and a family
+    // joined with ZWJs: 1 + 2 + 2 + 5 chars
+    const TEXT: &str = "ae\u{301}👍🏽👨\u{200d}👩\u{200d}👧";
+
+    #[test]
+    fn boundaries_keep_clusters_whole() {
+        assert_eq!(boundaries(TEXT), vec![0, 1, 3, 5, 10]);
+        assert_eq!(boundaries(""), vec![0]);
+    }
+
+    #[test]
+    fn prev_boundary_skips_whole_clusters() {
+        assert_eq!(prev_boundary(TEXT, 10), 5);
+        assert_eq!(prev_boundary(TEXT, 5), 3);
+        assert_eq!(prev_boundary(TEXT, 3), 1);
+        assert_eq!(prev_boundary(TEXT, 1), 0);
+        assert_eq!(prev_boundary(TEXT, 0), 0);
+        // From inside a cluster, to its start
+        assert_eq!(prev_boundary(TEXT, 7), 5);
+    }
+
+    #[test]
+    fn next_boundary_skips_whole_clusters() {
+        assert_eq!(next_boundary(TEXT, 0), 1);
+        assert_eq!(next_boundary(TEXT, 1), 3);
+        assert_eq!(next_boundary(TEXT, 3), 5);
+        assert_eq!(next_boundary(TEXT, 5), 10);
+        assert_eq!(next_boundary(TEXT, 10), 10);
+        // From inside a cluster, to its end
+        assert_eq!(next_boundary(TEXT, 2), 3);
+    }
+
+    #[test]
+    fn snap_moves_back_to_a_boundary() {
+        assert_eq!(snap(TEXT, 2), 1);
+        assert_eq!(snap(TEXT, 3), 3);
+        assert_eq!(snap(TEXT, 9), 5);
+        assert_eq!(snap(TEXT, 99), 10);
+        assert_eq!(snap("", 3), 0);
+    }
+
+    #[test]
+    fn visual_columns_expand_tabs_and_wide_chars() {
+        assert_eq!(visual_col("a\tb", 2, 4), 4);
+        assert_eq!(visual_col("日本", 1, 4), 2);
+        assert_eq!(visual_col(TEXT, 3, 4), 2);
+        assert_eq!(col_at_visual("a\tb", 3, 4), 1);
+        assert_eq!(col_at_visual("a\tb", 4, 4), 2);
+        assert_eq!(col_at_visual("日本", 3, 4), 1);
+        assert_eq!(col_at_visual("ab", 9, 4), 2);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,405 @@
+// Undo/redo history for the text buffer
+// Records reversible edits and groups them into transactions
+
+// Allow unused - these are API methods for future use
+#![allow(dead_code)]
+
+use super::cursor::CursorSet;
+
+/// Maximum number of transactions kept on the undo stack
+const MAX_UNDO_DEPTH: usize = 1000;
+
+/// A single reversible change to the rope (char indices)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// `text` was inserted at `idx`
+    Insert { idx: usize, text: String },
+    /// `text` was removed starting at `idx`
+    Delete { idx: usize, text: String },
+}
+
+/// The kind of edit, used to decide whether edits merge into one undo step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditKind {
+    /// Typing single characters
+    Typing,
+    /// Backspace
+    DeleteBackward,
+    /// Delete key
+    DeleteForward,
+    /// Anything else (paste, replace...) - never merges
+    Other,
+}
+
+/// A group of edits that is undone/redone as a single step
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    /// Edits in the order they were applied
+    pub edits: Vec<Edit>,
+    /// Cursors before the first edit
+    pub cursor_before: CursorSet,
+    /// Cursors after the last edit
+    pub cursor_after: CursorSet,
+    // Kind of the edits, for merging
+    kind: EditKind,
+    // Edits made per step (one per cursor for multi-cursor edits), for merging
+    step: usize,
+    // Unique id, identifying the document state after this transaction
+    id: u64,
+}
+
+/// Undo/redo stacks with typing-group merging
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    // Transactions that can be undone (most recent last)
+    undo_stack: Vec<Transaction>,
+    // Transactions that can be redone (most recent last)
+    redo_stack: Vec<Transaction>,
+    // Whether the top of the undo stack may absorb further edits
+    open: bool,
+    // Nesting depth of explicit groups (begin_group/end_group)
+    group_depth: usize,
+    // Transaction being built while inside an explicit group
+    pending: Option<Transaction>,
+    // Id handed to the most recently created transaction
+    last_id: u64,
+    // State id (see `state`) when the document was last saved
+    saved: u64,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an applied edit
+    pub fn record(&mut self, edit: Edit, kind: EditKind, cursor_before: CursorSet, cursor_after: CursorSet) {
+        self.redo_stack.clear();
+
+        // Inside an explicit group everything goes into the pending transaction
+        if self.group_depth > 0 {
+            let pending = self.pending.get_or_insert_with(|| Transaction {
+                edits: Vec::new(),
+                cursor_before,
+                cursor_after: CursorSet::default(),
+                kind: EditKind::Other,
+                step: 0,
+                id: 0,
+            });
+            pending.edits.push(edit);
+            pending.cursor_after = cursor_after;
+            return;
+        }
+
+        if self.open {
+            if let Some(top) = self.undo_stack.last_mut() {
+                if top.kind == kind && can_merge(top, &edit, kind) {
+                    top.edits.push(edit);
+                    top.cursor_after = cursor_after;
+                    return;
+                }
+            }
+        }
+
+        self.push_undo(Transaction {
+            edits: vec![edit],
+            cursor_before,
+            cursor_after,
+            kind,
+            step: 1,
+            id: 0,
+        });
+        self.open = kind != EditKind::Other;
+    }
+
+    /// Stop merging further edits into the current undo step
+    /// (called on cursor movement, save, etc.)
+    pub fn break_group(&mut self) {
+        self.open = false;
+    }
+
+    /// Start an explicit group: all edits until `end_group` form one undo step
+    pub fn begin_group(&mut self) {
+        self.group_depth += 1;
+    }
+
+    /// Finish an explicit group started with `begin_group`
+    pub fn end_group(&mut self) {
+        self.end_group_as(EditKind::Other);
+    }
+
+    /// Finish an explicit group that made one edit of `kind` at each of
+    /// several cursors; like a single edit it merges into the previous step
+    /// while typing or deleting continues
+    pub fn end_group_as(&mut self, kind: EditKind) {
+        if self.group_depth == 0 {
+            return;
+        }
+        self.group_depth -= 1;
+        if self.group_depth > 0 {
+            return;
+        }
+        let Some(mut tx) = self.pending.take() else {
+            self.open = false;
+            return;
+        };
+        tx.kind = kind;
+        tx.step = tx.edits.len();
+
+        match self.undo_stack.last_mut() {
+            Some(top) if self.open && can_merge_step(top, &tx) => {
+                top.edits.extend(tx.edits);
+                top.cursor_after = tx.cursor_after;
+            }
+            _ => self.push_undo(tx),
+        }
+        self.open = kind != EditKind::Other;
+    }
+
+    /// Pop the most recent transaction for undoing; it moves to the redo stack
+    pub fn undo(&mut self) -> Option<Transaction> {
+        self.open = false;
+        let tx = self.undo_stack.pop()?;
+        self.redo_stack.push(tx.clone());
+        Some(tx)
+    }
+
+    /// Pop the most recently undone transaction for redoing
+    pub fn redo(&mut self) -> Option<Transaction> {
+        self.open = false;
+        let tx = self.redo_stack.pop()?;
+        self.undo_stack.push(tx.clone());
+        Some(tx)
+    }
+
+    /// Check if there is anything to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Check if there is anything to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Remember the current state as the saved one and stop merging into it
+    pub fn mark_saved(&mut self) {
+        self.saved = self.state();
+        self.open = false;
+    }
+
+    /// Check if undo/redo has brought the document back to the saved state
+    pub fn is_at_saved(&self) -> bool {
+        self.pending.is_none() && self.state() == self.saved
+    }
+
+    /// Drop all history; the current state counts as saved
+    pub fn clear(&mut self) {
+        let last_id = self.last_id;
+        *self = Self::default();
+        // Keep ids unique so a stale state can never look saved
+        self.last_id = last_id;
+    }
+
+    // Id of the current document state: the top transaction's id, or 0 when
+    // nothing is left to undo
+    fn state(&self) -> u64 {
+        self.undo_stack.last().map_or(0, |tx| tx.id)
+    }
+
+    // Push a finished transaction under a fresh id, trimming the oldest
+    // entries past the limit
+    fn push_undo(&mut self, mut tx: Transaction) {
+        self.last_id += 1;
+        tx.id = self.last_id;
+        self.undo_stack.push(tx);
+        if self.undo_stack.len() > MAX_UNDO_DEPTH {
+            self.undo_stack.remove(0);
+        }
+    }
+}
+
+// Decide whether `edit` continues the typing/deleting run in `top`.
+// Runs are word-sized: a new group starts when a word begins after whitespace,
+// and newlines always stand alone.
+fn can_merge(top: &Transaction, edit: &Edit, kind: EditKind) -> bool {
+    let Some(last) = top.edits.last() else {
+        return false;
+    };
+
+    match (kind, last, edit) {
+        (EditKind::Typing, Edit::Insert { idx: a, text: prev }, Edit::Insert { idx: b, text: next }) => {
+            *b == a + prev.chars().count() && continues_word(prev, next)
+        }
+        (EditKind::DeleteBackward, Edit::Delete { idx: a, text: prev }, Edit::Delete { idx: b, text: next }) => {
+            // Deleting leftwards: new text sits just before the previous one
+            *b + next.chars().count() == *a && continues_word(next, prev)
+        }
+        (EditKind::DeleteForward, Edit::Delete { idx: a, text: prev }, Edit::Delete { idx: b, text: next }) => {
+            *b == *a && continues_word(prev, next)
+        }
+        _ => false,
+    }
+}
+
+// Decide whether a multi-cursor step continues the run in `top`: no cursor
+// moved in between, and each cursor's edit continues its previous one
+fn can_merge_step(top: &Transaction, tx: &Transaction) -> bool {
+    if tx.kind == EditKind::Other || top.kind != tx.kind || top.step != tx.step || top.cursor_after != tx.cursor_before {
+        return false;
+    }
+    let previous = &top.edits[top.edits.len() - top.step..];
+    previous.iter().zip(&tx.edits).all(|(prev, next)| match (tx.kind, prev, next) {
+        (EditKind::Typing, Edit::Insert { text: prev, .. }, Edit::Insert { text: next, .. }) => continues_word(prev, next),
+        (EditKind::DeleteBackward, Edit::Delete { text: prev, .. }, Edit::Delete { text: next, .. }) => continues_word(next, prev),
+        (EditKind::DeleteForward, Edit::Delete { text: prev, .. }, Edit::Delete { text: next, .. }) => continues_word(prev, next),
+        _ => false,
+    })
+}
+
+// True if `next` (text following `prev`) belongs to the same word-sized group
+fn continues_word(prev: &str, next: &str) -> bool {
+    let (Some(p), Some(n)) = (prev.chars().last(), next.chars().next()) else {
+        return false;
+    };
+    if p == '\n' || n == '\n' {
+        return false;
+    }
+    // Whitespace followed by a non-space starts a new word
+    !p.is_whitespace() || n.is_whitespace()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(history: &mut History, idx: usize, text: &str) {
+        let edit = Edit::Insert { idx, text: text.to_string() };
+        history.record(edit, EditKind::Other, CursorSet::default(), CursorSet::default());
+    }
+
+    // Record single-char edits of one kind, as the buffer does for keystrokes
+    fn record(history: &mut History, kind: EditKind, edits: &[(usize, char)]) {
+        for &(idx, ch) in edits {
+            let text = ch.to_string();
+            let edit = if kind == EditKind::Typing { Edit::Insert { idx, text } } else { Edit::Delete { idx, text } };
+            history.record(edit, kind, CursorSet::default(), CursorSet::default());
+        }
+    }
+
+    fn type_text(history: &mut History, at: usize, text: &str) {
+        let edits: Vec<(usize, char)> = text.chars().enumerate().map(|(i, ch)| (at + i, ch)).collect();
+        record(history, EditKind::Typing, &edits);
+    }
+
+    // Text of each undo step, most recent first
+    fn undo_steps(history: &mut History) -> Vec<String> {
+        std::iter::from_fn(|| history.undo())
+            .map(|tx| {
+                tx.edits
+                    .iter()
+                    .map(|edit| match edit {
+                        Edit::Insert { text, .. } | Edit::Delete { text, .. } => text.as_str(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn typing_splits_where_a_word_starts_after_whitespace() {
+        let mut history = History::new();
+        type_text(&mut history, 0, "let  x = 1;");
+        assert_eq!(undo_steps(&mut history), vec!["1;", "= ", "x ", "let  "]);
+    }
+
+    #[test]
+    fn newlines_are_undone_on_their_own() {
+        let mut history = History::new();
+        type_text(&mut history, 0, "ab\n\ncd");
+        assert_eq!(undo_steps(&mut history), vec!["cd", "\n", "\n", "ab"]);
+    }
+
+    #[test]
+    fn deleting_runs_group_by_word() {
+        let mut history = History::new();
+        // Backspacing "ab cd" from the end
+        record(&mut history, EditKind::DeleteBackward, &[(4, 'd'), (3, 'c'), (2, ' '), (1, 'b'), (0, 'a')]);
+        assert_eq!(undo_steps(&mut history), vec![" ba", "dc"]);
+
+        // Deleting "ab cd" forwards from the start
+        record(&mut history, EditKind::DeleteForward, &[(0, 'a'), (0, 'b'), (0, ' '), (0, 'c'), (0, 'd')]);
+        assert_eq!(undo_steps(&mut history), vec!["cd", "ab "]);
+    }
+
+    #[test]
+    fn runs_break_on_jumps_and_other_kinds() {
+        let mut history = History::new();
+        type_text(&mut history, 0, "ab");
+        // Typing somewhere else
+        type_text(&mut history, 0, "c");
+        record(&mut history, EditKind::DeleteBackward, &[(0, 'c')]);
+        // A backspace that isn't next to the previous one
+        record(&mut history, EditKind::DeleteBackward, &[(1, 'b')]);
+        assert_eq!(undo_steps(&mut history), vec!["b", "c", "c", "ab"]);
+    }
+
+    #[test]
+    fn break_group_ends_the_run() {
+        let mut history = History::new();
+        type_text(&mut history, 0, "ab");
+        history.break_group();
+        type_text(&mut history, 2, "cd");
+        assert_eq!(undo_steps(&mut history), vec!["cd", "ab"]);
+    }
+
+    #[test]
+    fn oldest_steps_are_dropped_past_the_limit() {
+        let mut history = History::new();
+        for i in 0..MAX_UNDO_DEPTH + 5 {
+            insert(&mut history, i, &i.to_string());
+        }
+        let steps = undo_steps(&mut history);
+        assert_eq!(steps.len(), MAX_UNDO_DEPTH);
+        assert_eq!(steps.last().map(String::as_str), Some("5"));
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn undo_back_to_saved_state_is_clean() {
+        let mut history = History::new();
+        assert!(history.is_at_saved());
+        insert(&mut history, 0, "a");
+        history.mark_saved();
+        insert(&mut history, 1, "b");
+        assert!(!history.is_at_saved());
+        history.undo();
+        assert!(history.is_at_saved());
+        history.undo();
+        assert!(!history.is_at_saved());
+        history.redo();
+        assert!(history.is_at_saved());
+    }
+
+    #[test]
+    fn saved_state_lost_after_diverging_edit() {
+        let mut history = History::new();
+        insert(&mut history, 0, "a");
+        history.mark_saved();
+        history.undo();
+        insert(&mut history, 0, "b");
+        assert!(!history.is_at_saved());
+        history.undo();
+        assert!(!history.is_at_saved());
+    }
+
+    #[test]
+    fn clear_counts_as_saved() {
+        let mut history = History::new();
+        insert(&mut history, 0, "a");
+        history.clear();
+        assert!(history.is_at_saved());
+        insert(&mut history, 0, "b");
+        assert!(!history.is_at_saved());
+    }
+}
@@ -0,0 +1,140 @@
+// Indentation style (tabs or spaces, and the indent width), detected from
+// the leading whitespace of a file's lines
+
+use crate::theme::editor::TAB_SIZE;
+
+/// Widths tried when detecting space indentation
+const MAX_DETECTED_WIDTH: usize = 8;
+
+/// How a buffer is indented
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    /// One tab per level
+    Tabs,
+    /// This many spaces per level (soft tabs)
+    Spaces(usize),
+}
+
+impl Default for Indent {
+    fn default() -> Self {
+        Indent::Spaces(TAB_SIZE as usize)
+    }
+}
+
+impl Indent {
+    /// Indentation used by most indented lines of `text`: tabs if more lines
+    /// start with a tab than with spaces, otherwise spaces with the most common
+    /// step between the indents of consecutive lines. `None` if no line is
+    /// indented.
+    pub fn detect(text: &str) -> Option<Self> {
+        let (mut tab_lines, mut space_lines) = (0usize, 0usize);
+        // Votes for each width, indexed by width
+        let mut steps = [0usize; MAX_DETECTED_WIDTH + 1];
+        let mut previous = 0;
+
+        for line in text.lines() {
+            let trimmed = line.trim_start();
+            // Skip blank lines and block comment continuations (` * ...`)
+            if trimmed.is_empty() || trimmed.starts_with('*') {
+                continue;
+            }
+            if line.starts_with('\t') {
+                tab_lines += 1;
+                continue;
+            }
+            let spaces = line.len() - line.trim_start_matches(' ').len();
+            if spaces > 0 {
+                space_lines += 1;
+            }
+            let step = spaces.abs_diff(previous);
+            if (1..=MAX_DETECTED_WIDTH).contains(&step) {
+                steps[step] += 1;
+            }
+            previous = spaces;
+        }
+
+        if tab_lines == 0 && space_lines == 0 {
+            None
+        } else if tab_lines > space_lines {
+            Some(Indent::Tabs)
+        } else {
+            // Most votes wins; ties go to the smaller width
+            let width = (1..=MAX_DETECTED_WIDTH)
+                .filter(|&width| steps[width] > 0)
+                .min_by_key(|&width| (std::cmp::Reverse(steps[width]), width))
+                .unwrap_or(TAB_SIZE as usize);
+            Some(Indent::Spaces(width))
+        }
+    }
+
+    /// Text for one level of indentation
+    pub fn unit(self) -> String {
+        match self {
+            Indent::Tabs => "\t".to_string(),
+            Indent::Spaces(width) => " ".repeat(width),
+        }
+    }
+
+    /// Columns in one level of indentation (`tab_size` for tabs)
+    pub fn width(self, tab_size: usize) -> usize {
+        match self {
+            Indent::Tabs => tab_size,
+            Indent::Spaces(width) => width,
+        }
+    }
+
+    /// The next style, for cycling from the status bar:
+    /// 2 spaces, 4 spaces, 8 spaces, tabs
+    pub fn next(self) -> Self {
+        match self {
+            Indent::Spaces(width) if width < 2 => Indent::Spaces(2),
+            Indent::Spaces(width) if width < 4 => Indent::Spaces(4),
+            Indent::Spaces(width) if width < 8 => Indent::Spaces(8),
+            Indent::Spaces(_) => Indent::Tabs,
+            Indent::Tabs => Indent::Spaces(2),
+        }
+    }
+
+    /// Short label for the status bar
+    pub fn label(self) -> String {
+        match self {
+            Indent::Tabs => "Tabs".to_string(),
+            Indent::Spaces(width) => format!("Spaces: {width}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_follows_the_majority_of_tabs_or_spaces() {
+        assert_eq!(Indent::detect("a\n\tb\n\tc\n    d"), Some(Indent::Tabs));
+        assert_eq!(Indent::detect("a\n\tb\n    c\n    d"), Some(Indent::Spaces(4)));
+        // A tie goes to spaces
+        assert_eq!(Indent::detect("a\n\tb\n  c"), Some(Indent::Spaces(2)));
+    }
+
+    #[test]
+    fn detect_finds_the_most_common_step() {
+        assert_eq!(Indent::detect("a {\n  b {\n    c\n  }\n}"), Some(Indent::Spaces(2)));
+        assert_eq!(Indent::detect("a {\n    b {\n        c\n    }\n}"), Some(Indent::Spaces(4)));
+        // Deep indentation counts by step, not by absolute width
+        assert_eq!(Indent::detect("        a\n          b\n            c"), Some(Indent::Spaces(2)));
+    }
+
+    #[test]
+    fn detect_skips_comment_continuations() {
+        let text = "/**\n * Doc comment\n * more\n */\nfn f() {\n    body\n}";
+        assert_eq!(Indent::detect(text), Some(Indent::Spaces(4)));
+        assert_eq!(Indent::detect("/*\n * only a comment\n */"), None);
+    }
+
+    #[test]
+    fn detect_gives_up_without_indented_lines() {
+        assert_eq!(Indent::detect(""), None);
+        assert_eq!(Indent::detect("a\nb\n\n   \nc"), None);
+        assert_eq!(Indent::detect("a\nb").unwrap_or_default(), Indent::Spaces(TAB_SIZE as usize));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,69 @@
+// Line-level change tracking
+// Lets consumers that cache per-line data (like syntax highlighting) find out
+// which lines an edit touched instead of redoing the whole buffer.
+
+use std::collections::VecDeque;
+
+/// Max changes kept; older revisions fall back to "everything changed"
+const MAX_CHANGES: usize = 1024;
+
+/// Lines `start..old_end` were replaced by lines `start..new_end`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LineChange {
+    pub start: usize,
+    pub old_end: usize,
+    pub new_end: usize,
+}
+
+impl LineChange {
+    /// Combine with a change made after this one into a single change
+    pub fn then(self, next: LineChange) -> LineChange {
+        // End of the union of both changes, in the lines between the two
+        let mid_end = self.new_end.max(next.old_end);
+        LineChange {
+            start: self.start.min(next.start),
+            old_end: mid_end - self.new_end + self.old_end,
+            new_end: mid_end - next.old_end + next.new_end,
+        }
+    }
+}
+
+/// Recent line changes, each stamped with the revision it was made on top of
+#[derive(Debug, Clone, Default)]
+pub struct ChangeLog {
+    entries: VecDeque<(u64, LineChange)>,
+    // Oldest revision whose changes are all still in the log
+    complete_from: u64,
+}
+
+impl ChangeLog {
+    pub fn record(&mut self, revision: u64, change: LineChange) {
+        if self.entries.len() == MAX_CHANGES {
+            if let Some((dropped, _)) = self.entries.pop_front() {
+                self.complete_from = dropped + 1;
+            }
+        }
+        self.entries.push_back((revision, change));
+    }
+
+    /// Forget everything before `revision` (e.g. after replacing the whole text)
+    pub fn reset(&mut self, revision: u64) {
+        self.entries.clear();
+        self.complete_from = revision;
+    }
+
+    /// All changes made since `revision` as one change, or None if they are
+    /// no longer known
+    pub fn since(&self, revision: u64) -> Option<LineChange> {
+        if revision < self.complete_from {
+            return None;
+        }
+        let change = self.entries
+            .iter()
+            .filter(|(stamp, _)| *stamp >= revision)
+            .map(|(_, change)| *change)
+            .reduce(LineChange::then)
+            .unwrap_or_default();
+        Some(change)
+    }
+}
\ No newline at end of file
@@ -1,5 +1,18 @@
 // Editor module - text buffer & cursor management
 mod buffer;
 mod cursor;
+mod grapheme;
+mod history;
+mod indent;
+mod line_change;
+mod search;
+mod viewport;
+mod word;
 
-pub use buffer::Buffer;
\ No newline at end of file
+pub use buffer::Buffer;
+pub use cursor::Position;
+pub use indent::Indent;
+pub use line_change::LineChange;
+pub use search::{next_match, prev_match, Search, SearchMatch, SearchQuery};
+pub use viewport::Viewport;
+pub use word::{WordRules, DEFAULT_WORD_SEPARATORS};
\ No newline at end of file
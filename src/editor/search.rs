@@ -0,0 +1,214 @@
+// Text search over buffer lines
+// Each line is searched on its own straight from the rope, so matches never
+// span a line break and the whole text is never copied out.
+
+use regex::Regex;
+use super::cursor::Position;
+
+/// What to search for and how
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SearchQuery {
+    /// Text (or regular expression) to find
+    pub pattern: String,
+    /// Treat the pattern as a regular expression
+    pub regex: bool,
+    /// Match case exactly
+    pub case_sensitive: bool,
+    /// Only match whole words
+    pub whole_word: bool,
+}
+
+/// A compiled search query
+#[derive(Debug, Clone)]
+pub struct Search {
+    regex: Regex,
+    // Expand $1 / ${name} in replacements (regex mode only)
+    expand: bool,
+}
+
+/// A match on a single line, as a char column range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub line: usize,
+    pub start_col: usize,
+    pub end_col: usize,
+}
+
+impl SearchMatch {
+    pub fn start(&self) -> Position {
+        Position::new(self.line, self.start_col)
+    }
+
+    pub fn end(&self) -> Position {
+        Position::new(self.line, self.end_col)
+    }
+}
+
+impl Search {
+    /// Compile a query; fails on an invalid regular expression
+    pub fn new(query: &SearchQuery) -> Result<Self, regex::Error> {
+        let pattern = if query.regex {
+            query.pattern.clone()
+        } else {
+            regex::escape(&query.pattern)
+        };
+        let pattern = if query.whole_word {
+            format!(r"\b(?:{pattern})\b")
+        } else {
+            pattern
+        };
+        // Inline flag rather than a builder option, so it shows up in as_str()
+        let pattern = if query.case_sensitive {
+            pattern
+        } else {
+            format!("(?i){pattern}")
+        };
+        Ok(Self { regex: Regex::new(&pattern)?, expand: query.regex })
+    }
+
+    /// Char column ranges of the matches in a line (empty matches are skipped)
+    pub fn line_matches(&self, line: &str) -> Vec<(usize, usize)> {
+        self.replacements(line, None)
+            .into_iter()
+            .map(|(cols, _)| cols)
+            .collect()
+    }
+
+    /// Matches in a line with the replacement text for each
+    pub fn line_replacements(&self, line: &str, template: &str) -> Vec<((usize, usize), String)> {
+        self.replacements(line, Some(template))
+            .into_iter()
+            .map(|(cols, replacement)| (cols, replacement.unwrap_or_default()))
+            .collect()
+    }
+
+    fn replacements(&self, line: &str, template: Option<&str>) -> Vec<((usize, usize), Option<String>)> {
+        let mut results = Vec::new();
+        // Convert byte offsets to char columns incrementally
+        let mut byte_pos = 0;
+        let mut col = 0;
+        for caps in self.regex.captures_iter(line) {
+            let m = caps.get(0).expect("group 0 always matches");
+            if m.is_empty() {
+                continue;
+            }
+            col += line[byte_pos..m.start()].chars().count();
+            let start_col = col;
+            col += m.as_str().chars().count();
+            byte_pos = m.end();
+
+            let replacement = template.map(|template| {
+                if self.expand {
+                    let mut out = String::new();
+                    caps.expand(template, &mut out);
+                    out
+                } else {
+                    template.to_string()
+                }
+            });
+            results.push(((start_col, col), replacement));
+        }
+        results
+    }
+}
+
+impl PartialEq for Search {
+    fn eq(&self, other: &Self) -> bool {
+        self.regex.as_str() == other.regex.as_str() && self.expand == other.expand
+    }
+}
+
+/// Index of the first match starting at or after `from`, wrapping to the first
+pub fn next_match(matches: &[SearchMatch], from: Position) -> Option<usize> {
+    if matches.is_empty() {
+        return None;
+    }
+    let from = (from.line, from.col);
+    Some(matches.iter().position(|m| (m.line, m.start_col) >= from).unwrap_or(0))
+}
+
+/// Index of the last match starting before `before`, wrapping to the last
+pub fn prev_match(matches: &[SearchMatch], before: Position) -> Option<usize> {
+    if matches.is_empty() {
+        return None;
+    }
+    let before = (before.line, before.col);
+    Some(matches.iter().rposition(|m| (m.line, m.start_col) < before).unwrap_or(matches.len() - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(pattern: &str, regex: bool, case_sensitive: bool, whole_word: bool) -> Search {
+        Search::new(&SearchQuery { pattern: pattern.to_string(), regex, case_sensitive, whole_word }).unwrap()
+    }
+
+    #[test]
+    fn plain_text_is_matched_literally() {
+        let search = search("a.b", false, true, false);
+        assert_eq!(search.line_matches("a.b axb a.b"), vec![(0, 3), (8, 11)]);
+    }
+
+    #[test]
+    fn case_insensitive_by_default() {
+        assert_eq!(search("foo", false, false, false).line_matches("Foo FOO foo"), vec![(0, 3), (4, 7), (8, 11)]);
+        assert_eq!(search("foo", false, true, false).line_matches("Foo FOO foo"), vec![(8, 11)]);
+    }
+
+    #[test]
+    fn whole_word_skips_matches_inside_words() {
+        let search = search("cat", false, false, true);
+        assert_eq!(search.line_matches("cat concat cats cat."), vec![(0, 3), (16, 19)]);
+    }
+
+    #[test]
+    fn columns_count_chars_not_bytes() {
+        let search = search("ß", false, true, false);
+        assert_eq!(search.line_matches("éé ß 日本ß"), vec![(3, 4), (7, 8)]);
+    }
+
+    #[test]
+    fn empty_matches_are_skipped() {
+        assert_eq!(search("x*", true, true, false).line_matches("axxb"), vec![(1, 3)]);
+        assert!(search("^", true, true, false).line_matches("abc").is_empty());
+    }
+
+    #[test]
+    fn invalid_regex_is_an_error() {
+        let query = SearchQuery { pattern: "(".to_string(), regex: true, ..Default::default() };
+        assert!(Search::new(&query).is_err());
+        // The same pattern is fine as plain text
+        let query = SearchQuery { pattern: "(".to_string(), ..Default::default() };
+        assert!(Search::new(&query).is_ok());
+    }
+
+    #[test]
+    fn regex_replacements_expand_groups() {
+        let search = search(r"(\w+)=(?P<value>\d+)", true, true, false);
+        assert_eq!(
+            search.line_replacements("a=1 bé=22", "${value}:$1"),
+            vec![((0, 3), "1:a".to_string()), ((4, 9), "22:bé".to_string())],
+        );
+    }
+
+    #[test]
+    fn plain_replacements_are_taken_literally() {
+        let search = search("a", false, true, false);
+        assert_eq!(search.line_replacements("ba", "$1"), vec![((1, 2), "$1".to_string())]);
+    }
+
+    #[test]
+    fn next_and_prev_match_wrap_around() {
+        let matches = [
+            SearchMatch { line: 0, start_col: 2, end_col: 3 },
+            SearchMatch { line: 2, start_col: 0, end_col: 1 },
+        ];
+        assert_eq!(next_match(&matches, Position::new(0, 2)), Some(0));
+        assert_eq!(next_match(&matches, Position::new(1, 0)), Some(1));
+        assert_eq!(next_match(&matches, Position::new(3, 0)), Some(0));
+        assert_eq!(prev_match(&matches, Position::new(2, 0)), Some(0));
+        assert_eq!(prev_match(&matches, Position::new(0, 2)), Some(1));
+        assert_eq!(next_match(&[], Position::new(0, 0)), None);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,286 @@
+// Viewport state for virtualized rendering
+// Tracks the scroll position and works out which lines need DOM nodes.
+// Every line is assumed to be one row tall until it has been rendered and
+// measured; lines that wrap onto more rows keep their measured height, so the
+// spacers standing in for unrendered lines stay the right size.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use crate::theme::editor::{FONT_SIZE_DEFAULT, LINE_HEIGHT, SCROLL_PADDING};
+use super::{Buffer, LineChange};
+
+/// Extra lines rendered above and below the visible area
+pub const OVERSCAN_LINES: usize = 20;
+
+// Measured heights this close to one row are rounding, not wrapping
+const MIN_EXTRA_HEIGHT: f64 = 1.0;
+
+/// Scroll position and size of the editor view, in pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct Viewport {
+    /// Current vertical scroll offset
+    pub scroll_top: f64,
+    /// Visible height of the editor view
+    pub height: f64,
+    /// Height of a single row
+    pub line_height: f64,
+    /// Lines kept between the cursor and the top/bottom edge
+    pub scroll_padding: usize,
+    // Width the lines wrap at; wrapped heights are only valid for this width
+    width: f64,
+    // Wrapped line -> height beyond its first row
+    wrapped: BTreeMap<usize, f64>,
+    // Buffer revision the line numbers in `wrapped` refer to
+    revision: u64,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            scroll_top: 0.0,
+            // Assume a typical window until the real size is measured
+            height: 800.0,
+            line_height: FONT_SIZE_DEFAULT as f64 * LINE_HEIGHT as f64,
+            scroll_padding: SCROLL_PADDING as usize,
+            width: 0.0,
+            wrapped: BTreeMap::new(),
+            revision: 0,
+        }
+    }
+}
+
+impl Viewport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take over another document's scroll position and wrapped lines,
+    /// keeping this view's size
+    pub fn show(&mut self, other: Viewport) {
+        let same_layout = other.width == self.width && other.line_height == self.line_height;
+        self.scroll_top = other.scroll_top;
+        self.revision = other.revision;
+        self.wrapped = if same_layout { other.wrapped } else { BTreeMap::new() };
+    }
+
+    /// Set the row height; wrapped heights measured with the old one are dropped
+    pub fn set_line_height(&mut self, line_height: f64) {
+        if line_height != self.line_height {
+            self.line_height = line_height;
+            self.wrapped.clear();
+        }
+    }
+
+    /// Set the width lines wrap at; a new width drops the wrapped heights
+    pub fn set_width(&mut self, width: f64) {
+        if (width - self.width).abs() >= MIN_EXTRA_HEIGHT {
+            self.width = width;
+            self.wrapped.clear();
+        }
+    }
+
+    /// Width lines wrap at, as last set
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    /// Forget every measured height (e.g. when the font or tab size changes)
+    pub fn clear_heights(&mut self) {
+        self.wrapped.clear();
+    }
+
+    /// Check whether measured `(line, height)` pairs differ from what is known
+    pub fn heights_changed(&self, rows: &[(usize, f64)]) -> bool {
+        rows.iter().any(|&(line, height)| {
+            let known = self.wrapped.get(&line).copied().unwrap_or(0.0);
+            (self.extra_height(height) - known).abs() >= MIN_EXTRA_HEIGHT
+        })
+    }
+
+    /// Record the measured height of rendered lines
+    pub fn set_heights(&mut self, rows: &[(usize, f64)]) {
+        for &(line, height) in rows {
+            let extra = self.extra_height(height);
+            if extra > 0.0 {
+                self.wrapped.insert(line, extra);
+            } else {
+                self.wrapped.remove(&line);
+            }
+        }
+    }
+
+    /// Move the wrapped heights along with the lines edited since they were
+    /// measured. Edited lines count as one row until measured again.
+    pub fn sync(&mut self, buffer: &Buffer) {
+        if self.revision == buffer.revision() {
+            return;
+        }
+        match buffer.line_changes_since(self.revision) {
+            Some(change) => self.apply_change(change),
+            None => self.wrapped.clear(),
+        }
+        self.revision = buffer.revision();
+    }
+
+    /// Buffer revision the wrapped heights are up to date with
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn apply_change(&mut self, change: LineChange) {
+        let moved = self.wrapped.split_off(&change.start);
+        for (line, extra) in moved {
+            if line >= change.old_end {
+                self.wrapped.insert(line - change.old_end + change.new_end, extra);
+            }
+        }
+    }
+
+    // Height of a line beyond its first row, ignoring rounding
+    fn extra_height(&self, height: f64) -> f64 {
+        let extra = height - self.line_height;
+        if extra < MIN_EXTRA_HEIGHT { 0.0 } else { extra }
+    }
+
+    /// Index of the first line at least partly visible
+    pub fn first_visible_line(&self) -> usize {
+        self.line_at(self.scroll_top)
+    }
+
+    /// Line at a vertical offset
+    pub fn line_at(&self, y: f64) -> usize {
+        // Extra height of the wrapped lines above the one being looked at
+        let mut above = 0.0;
+        for (&line, &extra) in &self.wrapped {
+            let top = line as f64 * self.line_height + above;
+            if y < top {
+                break;
+            }
+            if y < top + self.line_height + extra {
+                return line;
+            }
+            above += extra;
+        }
+        ((y - above) / self.line_height).floor().max(0.0) as usize
+    }
+
+    /// Number of rows that fit in the view
+    pub fn visible_line_count(&self) -> usize {
+        ((self.height / self.line_height).ceil() as usize).max(1)
+    }
+
+    /// Lines to render: the visible ones plus overscan, clamped to the buffer
+    pub fn render_range(&self, line_count: usize) -> Range<usize> {
+        let first = self.first_visible_line().min(line_count.saturating_sub(1));
+        let start = first.saturating_sub(OVERSCAN_LINES);
+        let end = (first + self.visible_line_count() + OVERSCAN_LINES).min(line_count);
+        start..end
+    }
+
+    /// Pixel offset of the top of a line
+    pub fn line_top(&self, line: usize) -> f64 {
+        line as f64 * self.line_height + self.wrapped.range(..line).map(|(_, extra)| extra).sum::<f64>()
+    }
+
+    /// New scroll offset that keeps `line` at least `scroll_padding` rows away
+    /// from the edges of the view, or None if it already is
+    pub fn scroll_to_reveal(&self, line: usize, line_count: usize) -> Option<f64> {
+        let visible = self.visible_line_count();
+        // Don't let the padding swallow the whole view on tiny windows
+        let padding = self.scroll_padding.min(visible.saturating_sub(1) / 2);
+
+        let top = self.line_top(line.saturating_sub(padding));
+        let bottom = self.line_top((line + padding + 1).min(line_count));
+
+        let target = if top < self.scroll_top {
+            top
+        } else if bottom > self.scroll_top + self.height {
+            // A line taller than the view shows its start
+            (bottom - self.height).min(self.line_top(line))
+        } else {
+            return None;
+        };
+
+        let max_top = (self.line_top(line_count) - self.height).max(0.0);
+        let target = target.min(max_top).max(0.0);
+        (target != self.scroll_top).then_some(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 10px-row, 100px-high view where line 2 wraps onto three rows
+    fn wrapped_view() -> Viewport {
+        let mut viewport = Viewport { line_height: 10.0, height: 100.0, scroll_padding: 0, ..Viewport::new() };
+        viewport.set_heights(&[(1, 10.0), (2, 30.0), (3, 10.4)]);
+        viewport
+    }
+
+    #[test]
+    fn wrapped_lines_push_later_lines_down() {
+        let viewport = wrapped_view();
+        assert_eq!(viewport.line_top(2), 20.0);
+        assert_eq!(viewport.line_top(3), 50.0);
+        assert_eq!(viewport.line_top(10), 120.0);
+    }
+
+    #[test]
+    fn line_at_finds_the_line_under_an_offset() {
+        let viewport = wrapped_view();
+        assert_eq!(viewport.line_at(0.0), 0);
+        assert_eq!(viewport.line_at(19.9), 1);
+        assert_eq!(viewport.line_at(20.0), 2);
+        assert_eq!(viewport.line_at(49.0), 2);
+        assert_eq!(viewport.line_at(50.0), 3);
+        assert_eq!(viewport.line_at(125.0), 10);
+    }
+
+    #[test]
+    fn heights_follow_edited_lines() {
+        let mut viewport = wrapped_view();
+        viewport.set_heights(&[(5, 20.0)]);
+        // Line 3 replaced by two lines: line 2 stays, line 5 moves to 6
+        viewport.apply_change(LineChange { start: 3, old_end: 4, new_end: 5 });
+        assert_eq!(viewport.line_top(3), 50.0);
+        assert_eq!(viewport.line_top(7), 100.0);
+        // Editing the wrapped line itself forgets its height
+        viewport.apply_change(LineChange { start: 2, old_end: 3, new_end: 3 });
+        assert_eq!(viewport.line_top(3), 30.0);
+    }
+
+    #[test]
+    fn only_real_changes_need_a_rerender() {
+        let viewport = wrapped_view();
+        assert!(!viewport.heights_changed(&[(0, 10.0), (2, 30.4), (3, 10.0)]));
+        assert!(viewport.heights_changed(&[(2, 10.0)]));
+        assert!(viewport.heights_changed(&[(4, 20.0)]));
+    }
+
+    #[test]
+    fn new_width_forgets_wrapped_heights() {
+        let mut viewport = wrapped_view();
+        viewport.set_width(500.0);
+        assert_eq!(viewport.line_top(3), 30.0);
+    }
+
+    #[test]
+    fn reveal_accounts_for_wrapped_lines() {
+        let mut viewport = wrapped_view();
+        viewport.set_heights(&[(12, 40.0)]);
+        // Line 2 ends at 50px, inside the view
+        assert_eq!(viewport.scroll_to_reveal(2, 50), None);
+        // Line 12 spans 140..180px
+        assert_eq!(viewport.scroll_to_reveal(12, 50), Some(80.0));
+        viewport.scroll_top = 100.0;
+        assert_eq!(viewport.scroll_to_reveal(2, 50), Some(20.0));
+    }
+
+    #[test]
+    fn reveal_shows_the_start_of_a_line_taller_than_the_view() {
+        let mut viewport = wrapped_view();
+        viewport.set_heights(&[(4, 250.0)]);
+        assert_eq!(viewport.scroll_to_reveal(4, 50), Some(60.0));
+    }
+}
\ No newline at end of file
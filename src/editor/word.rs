@@ -0,0 +1,142 @@
+// Word boundaries for word-wise cursor motion (Ctrl+Left/Right)
+// A word is a run of word characters or a run of separator punctuation;
+// whitespace (including line breaks) between words is skipped.
+
+/// Punctuation that separates words unless configured otherwise
+pub const DEFAULT_WORD_SEPARATORS: &str = "`~!@#$%^&*()-=+[{]}\\|;:'\",.<>/?";
+
+/// How words are delimited
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordRules {
+    /// Characters that end a word (whitespace always does)
+    pub separators: String,
+    /// Also stop inside identifiers: at camelCase humps and before `_`
+    pub subwords: bool,
+}
+
+impl Default for WordRules {
+    fn default() -> Self {
+        Self {
+            separators: DEFAULT_WORD_SEPARATORS.to_string(),
+            subwords: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Space,
+    Separator,
+    Word,
+}
+
+impl WordRules {
+    fn class(&self, ch: char) -> Class {
+        if ch.is_whitespace() {
+            Class::Space
+        } else if self.separators.contains(ch) {
+            Class::Separator
+        } else {
+            Class::Word
+        }
+    }
+
+    // Whether a subword boundary lies between two adjacent word chars
+    fn splits(&self, before: char, after: char) -> bool {
+        self.subwords
+            && ((before.is_lowercase() || before.is_ascii_digit()) && after.is_uppercase()
+                || after == '_' && before != '_')
+    }
+
+    /// How many chars a word motion moves over, reading `chars` in the
+    /// direction of travel: any whitespace, then one word or separator run.
+    /// `forward` says whether `chars` runs forwards through the text.
+    pub fn motion_len(&self, chars: impl Iterator<Item = char>, forward: bool) -> usize {
+        let mut chars = chars.peekable();
+        let mut len = 0;
+        while chars.next_if(|ch| ch.is_whitespace()).is_some() {
+            len += 1;
+        }
+
+        let Some(mut last) = chars.next() else {
+            return len;
+        };
+        len += 1;
+        let class = self.class(last);
+        while let Some(&ch) = chars.peek() {
+            let (before, after) = if forward { (last, ch) } else { (ch, last) };
+            if self.class(ch) != class || class == Class::Word && self.splits(before, after) {
+                break;
+            }
+            chars.next();
+            len += 1;
+            last = ch;
+        }
+        len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // Text mixing words, camelCase, separators, whitespace and line breaks
+    const TEXT: &str = "[a-cA-C0-9_ \t\n.,()!-]{0,40}";
+
+    fn rules() -> impl Strategy<Value = WordRules> {
+        any::<bool>().prop_map(|subwords| WordRules { subwords, ..WordRules::default() })
+    }
+
+    #[test]
+    fn stops_at_word_and_separator_runs() {
+        let rules = WordRules::default();
+        assert_eq!(rules.motion_len("foo.bar".chars(), true), 3);
+        assert_eq!(rules.motion_len("  ...bar".chars(), true), 5);
+        assert_eq!(rules.motion_len("rab oof".chars(), false), 3);
+        assert_eq!(rules.motion_len(" \n ".chars(), true), 3);
+        assert_eq!(rules.motion_len("".chars(), true), 0);
+    }
+
+    #[test]
+    fn subwords_stop_at_humps_and_underscores() {
+        let rules = WordRules { subwords: true, ..WordRules::default() };
+        assert_eq!(rules.motion_len("camelCase".chars(), true), 5);
+        assert_eq!(rules.motion_len("esaClemac".chars(), false), 4);
+        assert_eq!(rules.motion_len("snake_case".chars(), true), 5);
+        assert_eq!(rules.motion_len("HTTP2Server".chars(), true), 5);
+    }
+
+    proptest! {
+        #[test]
+        fn moves_over_whitespace_then_one_run(text in TEXT, rules in rules(), forward in any::<bool>()) {
+            let chars: Vec<char> = if forward { text.chars().collect() } else { text.chars().rev().collect() };
+            let len = rules.motion_len(chars.iter().copied(), forward);
+
+            prop_assert!(len <= chars.len());
+            prop_assert_eq!(len == 0, chars.is_empty());
+            let spaces = chars.iter().take_while(|ch| ch.is_whitespace()).count();
+            if spaces < chars.len() {
+                // Past the whitespace it takes a non-empty run of one class
+                prop_assert!(len > spaces);
+                let class = rules.class(chars[spaces]);
+                prop_assert!(chars[spaces..len].iter().all(|&ch| rules.class(ch) == class));
+                // ...and only stops early at a subword boundary
+                if let Some(&next) = chars.get(len) {
+                    let (before, after) = if forward { (chars[len - 1], next) } else { (next, chars[len - 1]) };
+                    prop_assert!(rules.class(next) != class || rules.splits(before, after));
+                }
+            } else {
+                prop_assert_eq!(len, chars.len());
+            }
+        }
+
+        #[test]
+        fn subwords_never_move_further(text in TEXT, forward in any::<bool>()) {
+            let chars: Vec<char> = if forward { text.chars().collect() } else { text.chars().rev().collect() };
+            let whole = WordRules::default().motion_len(chars.iter().copied(), forward);
+            let sub = WordRules { subwords: true, ..WordRules::default() }.motion_len(chars.iter().copied(), forward);
+            prop_assert!(sub <= whole);
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,293 @@
+// Crash-safe file replacement
+// New contents go to a temporary file next to the target, which is fsynced
+// and then renamed over it, so the target is always either the old or the
+// new version, never a truncated mix.
+//
+// Renaming gives the path a new inode, which would detach a hard-linked file
+// from its other names, so those are overwritten in place instead. The same
+// happens when the temporary file can't be created or renamed (a directory
+// we can't write to, a file bind-mounted on its own); that write isn't
+// crash-safe, but it is what saving did before.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Max symlinks followed before giving up (same limit as Linux)
+const MAX_SYMLINKS: usize = 40;
+
+/// Follow symlinks to the file that should actually be replaced, so saving
+/// through a link updates its target instead of replacing the link. The
+/// target doesn't have to exist yet.
+pub fn resolve_symlinks(path: &Path) -> io::Result<PathBuf> {
+    let mut path = path.to_path_buf();
+    for _ in 0..MAX_SYMLINKS {
+        match fs::symlink_metadata(&path) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                let link = fs::read_link(&path)?;
+                path = match path.parent() {
+                    Some(parent) if link.is_relative() => parent.join(link),
+                    _ => link,
+                };
+            }
+            _ => return Ok(path),
+        }
+    }
+    Err(io::Error::other(format!("too many levels of symbolic links: {}", path.display())))
+}
+
+/// Replace the file at `target` (not a symlink) with `bytes`, keeping its
+/// permissions and, where possible, its owner and extended attributes
+pub fn replace_file(target: &Path, bytes: &[u8]) -> io::Result<()> {
+    let original = fs::metadata(target).ok();
+    if original.as_ref().is_some_and(is_hard_linked) {
+        return write_in_place(target, bytes);
+    }
+    match replace_via_temp(target, bytes, original.as_ref()) {
+        Err(e) if original.is_some() && can_write_in_place(&e) => {
+            tracing::debug!("Saving {} in place: {}", target.display(), e);
+            write_in_place(target, bytes)
+        }
+        result => result,
+    }
+}
+
+// Write a temporary copy and rename it over the target
+fn replace_via_temp(target: &Path, bytes: &[u8], original: Option<&fs::Metadata>) -> io::Result<()> {
+    let dir = match target.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let temp = temp_path(dir, target);
+
+    let result = write_temp(&temp, bytes, original, target)
+        .and_then(|()| fs::rename(&temp, target));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+        return result;
+    }
+
+    // Make the rename itself durable
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+// Write and fsync the temporary copy, carrying over the original's metadata.
+// Permissions are set before anything is written, so contents of a private
+// file are never readable by others, not even briefly.
+fn write_temp(temp: &Path, bytes: &[u8], original: Option<&fs::Metadata>, target: &Path) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    if let Some(original) = original {
+        create_with_mode(&mut options, original);
+    }
+    let mut file = options.open(temp)?;
+
+    if let Some(original) = original {
+        file.set_permissions(original.permissions())?;
+        copy_owner(&file, original);
+        copy_xattrs(target, temp);
+    }
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+// Overwrite the target's contents, keeping its inode (and so its hard links
+// and metadata)
+fn write_in_place(target: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).truncate(true).open(target)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+// Errors from the temporary file that an in-place write may get around
+fn can_write_in_place(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::PermissionDenied | io::ErrorKind::CrossesDevices | io::ErrorKind::ResourceBusy
+    )
+}
+
+#[cfg(unix)]
+fn is_hard_linked(meta: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    meta.nlink() > 1
+}
+
+#[cfg(not(unix))]
+fn is_hard_linked(_meta: &fs::Metadata) -> bool {
+    false
+}
+
+// Create the temporary file with the original's mode (less the umask) rather
+// than the default, which may be readable by everyone
+#[cfg(unix)]
+fn create_with_mode(options: &mut OpenOptions, original: &fs::Metadata) {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    options.mode(original.permissions().mode() & 0o777);
+}
+
+#[cfg(not(unix))]
+fn create_with_mode(_options: &mut OpenOptions, _original: &fs::Metadata) {}
+
+// Hidden sibling name that won't clash with other saves
+fn temp_path(dir: &Path, target: &Path) -> PathBuf {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    let name = target.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    let unique = COUNTER.fetch_add(1, Ordering::Relaxed);
+    dir.join(format!(".{name}.stringr-{}-{unique}.tmp", std::process::id()))
+}
+
+// Keep the owner and group; only root can give files away, so failures are expected
+#[cfg(unix)]
+fn copy_owner(file: &File, original: &fs::Metadata) {
+    use std::os::unix::fs::MetadataExt;
+    if let Err(e) = std::os::unix::fs::fchown(file, Some(original.uid()), Some(original.gid())) {
+        tracing::debug!("Could not keep file owner: {}", e);
+    }
+}
+
+#[cfg(not(unix))]
+fn copy_owner(_file: &File, _original: &fs::Metadata) {}
+
+// Copy extended attributes (SELinux labels, user metadata...) where allowed
+#[cfg(target_os = "linux")]
+fn copy_xattrs(from: &Path, to: &Path) {
+    let Ok(names) = xattr::list(from) else {
+        return;
+    };
+    for name in names {
+        if let Ok(Some(value)) = xattr::get(from, &name) {
+            if let Err(e) = xattr::set(to, &name, &value) {
+                tracing::debug!("Could not keep xattr {:?}: {}", name, e);
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn copy_xattrs(_from: &Path, _to: &Path) {}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::{symlink, MetadataExt, PermissionsExt};
+
+    fn mode(path: &Path) -> u32 {
+        fs::metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    // Names in `dir`, to check no temporary file is left behind
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn resolve_follows_chains_of_relative_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        fs::write(&file, "x").unwrap();
+        symlink("file.txt", dir.path().join("one")).unwrap();
+        symlink(dir.path().join("one"), dir.path().join("two")).unwrap();
+        assert_eq!(resolve_symlinks(&dir.path().join("two")).unwrap(), file);
+    }
+
+    #[test]
+    fn resolve_keeps_paths_that_are_not_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("new.txt");
+        assert_eq!(resolve_symlinks(&missing).unwrap(), missing);
+        // A dangling link resolves to where the new file should go
+        symlink("target.txt", dir.path().join("link")).unwrap();
+        assert_eq!(resolve_symlinks(&dir.path().join("link")).unwrap(), dir.path().join("target.txt"));
+    }
+
+    #[test]
+    fn resolve_gives_up_on_link_loops() {
+        let dir = tempfile::tempdir().unwrap();
+        symlink("b", dir.path().join("a")).unwrap();
+        symlink("a", dir.path().join("b")).unwrap();
+        assert!(resolve_symlinks(&dir.path().join("a")).is_err());
+    }
+
+    #[test]
+    fn replace_keeps_permissions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secret.txt");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+        replace_file(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(mode(&path), 0o600);
+        assert_eq!(entries(dir.path()), vec!["secret.txt"]);
+    }
+
+    #[test]
+    fn replace_creates_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("new.txt");
+        replace_file(&path, b"hello").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hello");
+        assert_eq!(entries(dir.path()), vec!["new.txt"]);
+    }
+
+    #[test]
+    fn saving_through_a_link_replaces_its_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        let link = dir.path().join("link.txt");
+        fs::write(&file, "old").unwrap();
+        symlink("file.txt", &link).unwrap();
+
+        replace_file(&resolve_symlinks(&link).unwrap(), b"new").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&link).unwrap(), "new");
+    }
+
+    #[test]
+    fn hard_links_stay_linked() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        let other = dir.path().join("other.txt");
+        fs::write(&file, "old").unwrap();
+        fs::hard_link(&file, &other).unwrap();
+
+        replace_file(&file, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&other).unwrap(), "new");
+        assert_eq!(fs::metadata(&file).unwrap().nlink(), 2);
+    }
+
+    #[test]
+    fn read_only_directory_falls_back_to_writing_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("locked");
+        fs::create_dir(&sub).unwrap();
+        let path = sub.join("file.txt");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&sub, fs::Permissions::from_mode(0o555)).unwrap();
+
+        let result = replace_file(&path, b"new");
+        fs::set_permissions(&sub, fs::Permissions::from_mode(0o755)).unwrap();
+        result.unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(entries(&sub), vec!["file.txt"]);
+    }
+
+    #[test]
+    fn only_permission_and_device_errors_fall_back() {
+        assert!(can_write_in_place(&io::Error::from(io::ErrorKind::PermissionDenied)));
+        assert!(can_write_in_place(&io::Error::from(io::ErrorKind::CrossesDevices)));
+        assert!(!can_write_in_place(&io::Error::from(io::ErrorKind::ReadOnlyFilesystem)));
+        assert!(!can_write_in_place(&io::Error::from(io::ErrorKind::StorageFull)));
+    }
+}
\ No newline at end of file
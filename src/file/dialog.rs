@@ -0,0 +1,30 @@
+// Native file dialogs for Stringr
+// Thin async wrappers around rfd running on the tokio runtime
+
+use std::path::{Path, PathBuf};
+use rfd::AsyncFileDialog;
+
+/// Ask the user for a file to open
+pub async fn pick_open_path(current: Option<&Path>) -> Option<PathBuf> {
+    let mut dialog = AsyncFileDialog::new().set_title("Open File");
+    if let Some(dir) = current.and_then(|p| p.parent()) {
+        dialog = dialog.set_directory(dir);
+    }
+    dialog.pick_file().await.map(|handle| handle.path().to_path_buf())
+}
+
+/// Ask the user where to save, starting from the current path if any
+pub async fn pick_save_path(current: Option<&Path>) -> Option<PathBuf> {
+    let mut dialog = AsyncFileDialog::new()
+        .set_title("Save As")
+        .set_can_create_directories(true);
+    if let Some(path) = current {
+        if let Some(dir) = path.parent() {
+            dialog = dialog.set_directory(dir);
+        }
+        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+            dialog = dialog.set_file_name(name);
+        }
+    }
+    dialog.save_file().await.map(|handle| handle.path().to_path_buf())
+}
\ No newline at end of file
@@ -0,0 +1,234 @@
+// Text encodings
+// Files are decoded to UTF-8 on load and encoded back on save, keeping their
+// byte order mark if they had one.
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Encodings offered when reopening a file that isn't valid UTF-8
+pub const REOPEN_ENCODINGS: &[FileEncoding] = &[
+    FileEncoding::UTF8,
+    FileEncoding::new(UTF_16LE, false),
+    FileEncoding::new(UTF_16BE, false),
+    FileEncoding::new(encoding_rs::WINDOWS_1252, false),
+    FileEncoding::new(encoding_rs::ISO_8859_15, false),
+    FileEncoding::new(encoding_rs::WINDOWS_1250, false),
+    FileEncoding::new(encoding_rs::WINDOWS_1251, false),
+    FileEncoding::new(encoding_rs::KOI8_R, false),
+    FileEncoding::new(encoding_rs::WINDOWS_1253, false),
+    FileEncoding::new(encoding_rs::WINDOWS_1254, false),
+];
+
+// Bytes sniffed for BOM-less UTF-16
+const SNIFF_LEN: usize = 1024;
+
+/// Encoding of a file and whether it starts with a byte order mark
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileEncoding {
+    encoding: &'static Encoding,
+    bom: bool,
+}
+
+impl Default for FileEncoding {
+    fn default() -> Self {
+        Self::UTF8
+    }
+}
+
+impl FileEncoding {
+    /// UTF-8 without a BOM
+    pub const UTF8: FileEncoding = FileEncoding::new(UTF_8, false);
+
+    pub const fn new(encoding: &'static Encoding, bom: bool) -> Self {
+        Self { encoding, bom }
+    }
+
+    /// Whether the file starts with a byte order mark
+    pub fn has_bom(&self) -> bool {
+        self.bom
+    }
+
+    /// Display name, e.g. "UTF-8", "UTF-16 LE BOM", "windows-1252"
+    pub fn label(&self) -> String {
+        let name = match self.encoding.name() {
+            "UTF-16LE" => "UTF-16 LE",
+            "UTF-16BE" => "UTF-16 BE",
+            name => name,
+        };
+        if self.bom { format!("{name} BOM") } else { name.to_string() }
+    }
+
+    /// Detect the encoding of raw file bytes and decode them. Files without a
+    /// BOM must be valid UTF-8 or recognizably UTF-16; anything else is None
+    /// (single-byte encodings can't be told apart, so the user has to pick one).
+    pub fn detect(bytes: &[u8]) -> Option<(String, FileEncoding)> {
+        if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+            let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+            return Some((text.into_owned(), FileEncoding::new(encoding, true)));
+        }
+        // A guess that doesn't decode cleanly is more likely binary data, which
+        // saving the lossy text would corrupt
+        if let Some(encoding) = sniff_utf16(bytes) {
+            let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+            if had_errors {
+                return None;
+            }
+            return Some((text.into_owned(), FileEncoding::new(encoding, false)));
+        }
+        std::str::from_utf8(bytes)
+            .ok()
+            .map(|text| (text.to_string(), FileEncoding::UTF8))
+    }
+
+    /// Decode bytes with this encoding, replacing invalid sequences. A BOM for
+    /// this encoding is stripped (and kept for saving) if present.
+    pub fn decode(self, bytes: &[u8]) -> (String, FileEncoding) {
+        match Encoding::for_bom(bytes) {
+            Some((encoding, bom_len)) if encoding == self.encoding => {
+                let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+                (text.into_owned(), FileEncoding::new(encoding, true))
+            }
+            _ => {
+                let (text, _) = self.encoding.decode_without_bom_handling(bytes);
+                (text.into_owned(), FileEncoding::new(self.encoding, false))
+            }
+        }
+    }
+
+    /// Encode text, with a BOM if the file had one. Returns None if the text
+    /// has characters this encoding can't represent.
+    pub fn encode(self, text: &str) -> Option<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(text.len() + 3);
+        // encoding_rs only encodes to UTF-8 for the UTF-16 encodings, so
+        // handle those here
+        if self.encoding == UTF_16LE || self.encoding == UTF_16BE {
+            let little_endian = self.encoding == UTF_16LE;
+            let bom = self.bom.then_some(0xFEFF_u16);
+            for unit in bom.into_iter().chain(text.encode_utf16()) {
+                let pair = if little_endian { unit.to_le_bytes() } else { unit.to_be_bytes() };
+                bytes.extend_from_slice(&pair);
+            }
+            return Some(bytes);
+        }
+
+        if self.bom && self.encoding == UTF_8 {
+            bytes.extend_from_slice(b"\xEF\xBB\xBF");
+        }
+        let (encoded, _, unmappable) = self.encoding.encode(text);
+        if unmappable {
+            return None;
+        }
+        bytes.extend_from_slice(&encoded);
+        Some(bytes)
+    }
+}
+
+// Guess BOM-less UTF-16 from NUL bytes: mostly-ASCII UTF-16 text has a NUL in
+// every other byte
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN) & !1];
+    let pairs = sample.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let even_nuls = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_nuls = sample.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+    let mostly = |count: usize| count * 10 >= pairs * 4;
+    let rarely = |count: usize| count * 20 <= pairs;
+    if mostly(odd_nuls) && rarely(even_nuls) {
+        Some(UTF_16LE)
+    } else if mostly(even_nuls) && rarely(odd_nuls) {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "héllo, wörld\n日本語 👍\n";
+
+    #[test]
+    fn plain_utf8_is_detected_without_a_bom() {
+        let (text, encoding) = FileEncoding::detect(TEXT.as_bytes()).unwrap();
+        assert_eq!(text, TEXT);
+        assert_eq!(encoding, FileEncoding::UTF8);
+        assert_eq!(encoding.label(), "UTF-8");
+    }
+
+    #[test]
+    fn utf8_bom_is_stripped_and_kept() {
+        let bytes = FileEncoding::new(UTF_8, true).encode(TEXT).unwrap();
+        assert!(bytes.starts_with(b"\xEF\xBB\xBF"));
+        let (text, encoding) = FileEncoding::detect(&bytes).unwrap();
+        assert_eq!(text, TEXT);
+        assert_eq!(encoding.label(), "UTF-8 BOM");
+        assert_eq!(encoding.encode(&text).unwrap(), bytes);
+    }
+
+    #[test]
+    fn utf16_round_trips_with_a_bom() {
+        let cases = [
+            (UTF_16LE, b"\xFF\xFE", "UTF-16 LE BOM"),
+            (UTF_16BE, b"\xFE\xFF", "UTF-16 BE BOM"),
+        ];
+        for (encoding, bom, label) in cases {
+            let bytes = FileEncoding::new(encoding, true).encode(TEXT).unwrap();
+            assert!(bytes.starts_with(bom));
+            let (text, detected) = FileEncoding::detect(&bytes).unwrap();
+            assert_eq!(text, TEXT);
+            assert_eq!(detected, FileEncoding::new(encoding, true));
+            assert_eq!(detected.label(), label);
+            assert_eq!(detected.encode(&text).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn utf16_is_sniffed_without_a_bom() {
+        for encoding in [UTF_16LE, UTF_16BE] {
+            let bytes = FileEncoding::new(encoding, false).encode("plain ASCII text\n").unwrap();
+            let (text, detected) = FileEncoding::detect(&bytes).unwrap();
+            assert_eq!(text, "plain ASCII text\n");
+            assert_eq!(detected, FileEncoding::new(encoding, false));
+            assert_eq!(detected.encode(&text).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn sniffed_utf16_that_does_not_decode_is_rejected() {
+        let mut bytes = FileEncoding::new(UTF_16LE, false).encode("binary-looking data").unwrap();
+        // An unpaired surrogate
+        bytes.extend_from_slice(&[0x00, 0xD8]);
+        bytes.extend_from_slice(&FileEncoding::new(UTF_16LE, false).encode("more").unwrap());
+        assert_eq!(FileEncoding::detect(&bytes), None);
+
+        // An odd trailing byte
+        let mut bytes = FileEncoding::new(UTF_16BE, false).encode("binary-looking data").unwrap();
+        bytes.push(0x41);
+        assert_eq!(FileEncoding::detect(&bytes), None);
+    }
+
+    #[test]
+    fn invalid_utf8_without_a_bom_is_not_guessed() {
+        assert_eq!(FileEncoding::detect(b"caf\xE9"), None);
+        let (text, encoding) = FileEncoding::new(encoding_rs::WINDOWS_1252, false).decode(b"caf\xE9");
+        assert_eq!(text, "café");
+        assert_eq!(encoding.encode(&text).unwrap(), b"caf\xE9");
+    }
+
+    #[test]
+    fn decode_keeps_only_a_matching_bom() {
+        let utf16 = FileEncoding::new(UTF_16LE, false);
+        let (text, encoding) = utf16.decode(b"\xFF\xFEa\0");
+        assert_eq!(text, "a");
+        assert!(encoding.has_bom());
+        let (_, encoding) = FileEncoding::UTF8.decode(b"\xFF\xFEa\0");
+        assert!(!encoding.has_bom());
+    }
+
+    #[test]
+    fn unmappable_characters_fail_to_encode() {
+        assert_eq!(FileEncoding::new(encoding_rs::WINDOWS_1252, false).encode("日本"), None);
+    }
+}
\ No newline at end of file
@@ -1,9 +1,12 @@
 // File I/O operations for Stringr
 // Provides async file reading and writing using tokio
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 use std::io;
+use super::atomic;
+use super::encoding::FileEncoding;
+use super::line_ending::LineEnding;
 
 // Error types for file operations
 #[derive(Debug)]
@@ -14,8 +17,12 @@ pub enum FileError {
     PermissionDenied(PathBuf),
     // Generic I/O error
     IoError(io::Error),
-    // File is not valid UTF-8
+    // File is not valid UTF-8 (and has no BOM); it can be reopened with an explicit encoding
     InvalidUtf8(PathBuf),
+    // Text has characters the file's encoding can't represent
+    Unencodable(PathBuf, String),
+    // File can't be replaced, e.g. on a read-only file system
+    ReplaceFailed(PathBuf, io::Error),
 }
 
 impl std::fmt::Display for FileError {
@@ -25,6 +32,10 @@ impl std::fmt::Display for FileError {
             FileError::PermissionDenied(path) => write!(f, "Permission denied: {}", path.display()),
             FileError::IoError(e) => write!(f, "I/O error: {}", e),
             FileError::InvalidUtf8(path) => write!(f, "File is not valid UTF-8: {}", path.display()),
+            FileError::Unencodable(path, encoding) => {
+                write!(f, "Text can't be saved as {}: {}", encoding, path.display())
+            }
+            FileError::ReplaceFailed(path, e) => write!(f, "Can't replace {}: {}", path.display(), e),
         }
     }
 }
@@ -37,10 +48,17 @@ impl From<io::Error> for FileError {
     }
 }
 
-// Read a file's contents as a UTF-8 string
-pub async fn read_file(path: &PathBuf) -> Result<String, FileError> {
-    match fs::read_to_string(path).await {
-        Ok(content) => Ok(content),
+/// Decoded text of a file and the encoding it was stored in
+#[derive(Debug, Clone)]
+pub struct FileContents {
+    pub text: String,
+    pub encoding: FileEncoding,
+}
+
+// Read a file's raw bytes
+async fn read_bytes(path: &PathBuf) -> Result<Vec<u8>, FileError> {
+    match fs::read(path).await {
+        Ok(bytes) => Ok(bytes),
         Err(e) => {
             match e.kind() {
                 io::ErrorKind::NotFound => Err(FileError::NotFound(path.clone())),
@@ -51,13 +69,57 @@ pub async fn read_file(path: &PathBuf) -> Result<String, FileError> {
     }
 }
 
-/// Write content to a file, creating it if it doesn't exist
-pub async fn write_file(path: &PathBuf, content: &str) -> Result<(), FileError> {
-    match fs::write(path, content).await {
+/// Read a file, detecting its encoding from a BOM (UTF-8/UTF-16), BOM-less
+/// UTF-16 or valid UTF-8. Anything else fails with `InvalidUtf8`.
+pub async fn read_file(path: &PathBuf) -> Result<FileContents, FileError> {
+    let bytes = read_bytes(path).await?;
+    match FileEncoding::detect(&bytes) {
+        Some((text, encoding)) => Ok(FileContents { text, encoding }),
+        None => Err(FileError::InvalidUtf8(path.clone())),
+    }
+}
+
+/// Read a file with an explicit encoding (e.g. after `InvalidUtf8`)
+pub async fn read_file_with_encoding(path: &PathBuf, encoding: FileEncoding) -> Result<FileContents, FileError> {
+    let bytes = read_bytes(path).await?;
+    let (text, encoding) = encoding.decode(&bytes);
+    Ok(FileContents { text, encoding })
+}
+
+/// Write content to a file, creating it if it doesn't exist.
+/// `content` uses bare `\n`; line breaks are written in `line_ending` style
+/// and the text is stored in `encoding` (with its BOM, if any).
+///
+/// The file is replaced atomically: a crash mid-save leaves the old version
+/// intact. Saving through a symlink writes to the file it points at.
+pub async fn write_file(
+    path: &Path,
+    content: &str,
+    line_ending: LineEnding,
+    encoding: FileEncoding,
+) -> Result<(), FileError> {
+    let content = line_ending.apply(content);
+    let Some(bytes) = encoding.encode(&content) else {
+        return Err(FileError::Unencodable(path.to_path_buf(), encoding.label()));
+    };
+
+    let target = path.to_path_buf();
+    let result = tokio::task::spawn_blocking(move || {
+        let target = atomic::resolve_symlinks(&target)?;
+        atomic::replace_file(&target, &bytes)
+    })
+    .await
+    .map_err(io::Error::other)
+    .and_then(|result| result);
+
+    match result {
         Ok(()) => Ok(()),
         Err(e) => {
             match e.kind() {
-                io::ErrorKind::PermissionDenied => Err(FileError::PermissionDenied(path.clone())),
+                io::ErrorKind::PermissionDenied => Err(FileError::PermissionDenied(path.to_path_buf())),
+                io::ErrorKind::ReadOnlyFilesystem | io::ErrorKind::CrossesDevices => {
+                    Err(FileError::ReplaceFailed(path.to_path_buf(), e))
+                }
                 _ => Err(FileError::IoError(e)),
             }
         }
@@ -70,7 +132,7 @@ pub async fn file_exists(path: &PathBuf) -> bool {
 }
 
 /// Get the filename from a path (for display purposes)
-pub fn get_filename(path: &PathBuf) -> String {
+pub fn get_filename(path: &Path) -> String {
     path.file_name()
         .and_then(|name| name.to_str())
         .unwrap_or("Unknown")
@@ -0,0 +1,145 @@
+// Line-ending styles
+// Buffers always hold bare `\n`; files are converted on load and save.
+
+use std::borrow::Cow;
+
+/// Line-ending style of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// `\n` (Unix, macOS)
+    #[default]
+    Lf,
+    /// `\r\n` (Windows)
+    CrLf,
+    /// `\r` (classic Mac OS)
+    Cr,
+}
+
+impl LineEnding {
+    /// Most common line ending in `text`; LF when there are no line breaks
+    pub fn detect(text: &str) -> Self {
+        let (lf, crlf, cr) = count(text);
+        if crlf > lf && crlf >= cr {
+            LineEnding::CrLf
+        } else if cr > lf && cr > crlf {
+            LineEnding::Cr
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    /// Check if `text` uses more than one line-ending style (saving it
+    /// converts them all to one)
+    pub fn is_mixed(text: &str) -> bool {
+        let (lf, crlf, cr) = count(text);
+        [lf, crlf, cr].iter().filter(|&&n| n > 0).count() > 1
+    }
+
+    /// The line break itself
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+
+    /// Short label for the status bar
+    pub fn label(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+            LineEnding::Cr => "CR",
+        }
+    }
+
+    /// Next style, for cycling through them
+    pub fn next(self) -> Self {
+        match self {
+            LineEnding::Lf => LineEnding::CrLf,
+            LineEnding::CrLf => LineEnding::Cr,
+            LineEnding::Cr => LineEnding::Lf,
+        }
+    }
+
+    /// Convert every line break (of any style) in `text` to `\n`
+    pub fn normalize(text: &str) -> Cow<'_, str> {
+        if !text.contains('\r') {
+            return Cow::Borrowed(text);
+        }
+        Cow::Owned(text.replace("\r\n", "\n").replace('\r', "\n"))
+    }
+
+    /// Convert `\n`-only text to this style
+    pub fn apply(self, text: &str) -> Cow<'_, str> {
+        match self {
+            LineEnding::Lf => Cow::Borrowed(text),
+            _ => Cow::Owned(text.replace('\n', self.as_str())),
+        }
+    }
+}
+
+// Number of LF, CRLF and CR line breaks in `text`
+fn count(text: &str) -> (usize, usize, usize) {
+    let (mut lf, mut crlf, mut cr) = (0usize, 0usize, 0usize);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' if chars.peek() == Some(&'\n') => {
+                chars.next();
+                crlf += 1;
+            }
+            '\r' => cr += 1,
+            '\n' => lf += 1,
+            _ => {}
+        }
+    }
+    (lf, crlf, cr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_picks_the_most_common_style() {
+        assert_eq!(LineEnding::detect("a\nb\nc"), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("a\r\nb\r\nc"), LineEnding::CrLf);
+        assert_eq!(LineEnding::detect("a\rb\rc"), LineEnding::Cr);
+        assert_eq!(LineEnding::detect("a\r\nb\r\nc\nd"), LineEnding::CrLf);
+        assert_eq!(LineEnding::detect("a\nb\nc\r\nd"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn detect_defaults_to_lf() {
+        assert_eq!(LineEnding::detect(""), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("no breaks"), LineEnding::Lf);
+        // Ties go to LF
+        assert_eq!(LineEnding::detect("a\nb\r\nc"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn crlf_is_not_counted_as_cr_and_lf() {
+        assert!(!LineEnding::is_mixed("a\r\nb\r\n"));
+        assert!(LineEnding::is_mixed("a\r\nb\n"));
+        assert!(LineEnding::is_mixed("a\rb\n"));
+        assert!(!LineEnding::is_mixed("a\nb\n"));
+        assert!(!LineEnding::is_mixed(""));
+    }
+
+    #[test]
+    fn normalize_converts_every_style_to_lf() {
+        assert_eq!(LineEnding::normalize("a\r\nb\rc\nd\r\r\n"), "a\nb\nc\nd\n\n");
+        assert!(matches!(LineEnding::normalize("a\nb"), Cow::Borrowed("a\nb")));
+    }
+
+    #[test]
+    fn apply_round_trips_through_normalize() {
+        for ending in [LineEnding::Lf, LineEnding::CrLf, LineEnding::Cr] {
+            let text = ending.apply("a\n\nb\n");
+            assert_eq!(LineEnding::detect(&text), ending);
+            assert_eq!(LineEnding::normalize(&text), "a\n\nb\n");
+        }
+        assert_eq!(LineEnding::CrLf.apply("a\nb"), "a\r\nb");
+    }
+}
\ No newline at end of file
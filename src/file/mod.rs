@@ -1,5 +1,16 @@
 // File operations module
 
+mod atomic;
+mod dialog;
+mod encoding;
 mod io;
+mod line_ending;
+mod recovery;
+mod watcher;
 
-pub use io::{read_file, write_file, file_exists, get_filename, FileError};
\ No newline at end of file
+pub use dialog::{pick_open_path, pick_save_path};
+pub use encoding::{FileEncoding, REOPEN_ENCODINGS};
+pub use io::{read_file, read_file_with_encoding, write_file, get_filename, FileContents, FileError};
+pub use line_ending::LineEnding;
+pub use recovery::{recovery_diff, RecoveredBuffer, RecoveryJournal};
+pub use watcher::FileWatcher;
\ No newline at end of file
@@ -0,0 +1,296 @@
+// Crash recovery journal
+// While Stringr runs, the contents of every buffer with unsaved changes are
+// periodically written to a journal under the user data directory. Each
+// session holds a lock on its own journal; a journal nobody holds a lock on
+// was left behind by a crashed (or killed) session and can be restored.
+
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use similar::TextDiff;
+use super::atomic;
+use super::io::FileError;
+
+/// Unsaved text of one buffer as recorded in a journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecoveredBuffer {
+    /// File the buffer belongs to, `None` if it was never saved
+    pub path: Option<PathBuf>,
+    /// Buffer text (`\n` line breaks)
+    pub text: String,
+}
+
+// On-disk journal format
+#[derive(Debug, Serialize, Deserialize)]
+struct Journal {
+    // Seconds since the Unix epoch when the journal was written
+    saved_at: u64,
+    buffers: Vec<RecoveredBuffer>,
+}
+
+/// Journal left behind by a session that didn't exit cleanly
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrphanedJournal {
+    journal: PathBuf,
+    lock: PathBuf,
+    /// When the journal was last written
+    pub saved_at: SystemTime,
+    /// Buffers with unsaved changes at that time
+    pub buffers: Vec<RecoveredBuffer>,
+}
+
+impl OrphanedJournal {
+    /// Delete the journal once it has been restored or dismissed
+    pub fn discard(&self) {
+        for path in [&self.journal, &self.lock] {
+            if let Err(e) = fs::remove_file(path) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    tracing::warn!("Failed to remove {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+}
+
+/// Recovery journal of the running session
+#[derive(Debug, Clone)]
+pub struct RecoveryJournal {
+    dir: PathBuf,
+    journal: PathBuf,
+    // Held (locked) for as long as the session runs
+    _lock: Arc<File>,
+}
+
+impl RecoveryJournal {
+    /// Create this session's journal in the user data directory
+    pub fn start() -> Result<Self, FileError> {
+        let dir = dirs::data_dir()
+            .ok_or_else(|| io::Error::other("no user data directory"))?
+            .join("stringr")
+            .join("recovery");
+        Self::start_in(dir)
+    }
+
+    // Create this session's journal in `dir`
+    fn start_in(dir: PathBuf) -> Result<Self, FileError> {
+        fs::create_dir_all(&dir)?;
+
+        let millis = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        // Unique even for journals started in the same process and millisecond
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let unique = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let session = format!("{}-{}-{}", std::process::id(), millis, unique);
+        let lock = File::create(dir.join(format!("{session}.lock")))?;
+        lock.try_lock().map_err(io::Error::from)?;
+
+        Ok(RecoveryJournal {
+            journal: dir.join(format!("{session}.json")),
+            dir,
+            _lock: Arc::new(lock),
+        })
+    }
+
+    /// Journals of earlier sessions that are no longer running
+    pub fn orphans(&self) -> Vec<OrphanedJournal> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut orphans: Vec<OrphanedJournal> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "lock"))
+            .filter(|lock| !is_locked(lock))
+            .filter_map(|lock| {
+                let journal = lock.with_extension("json");
+                match read_journal(&journal) {
+                    Ok(Some(orphan)) => Some(orphan),
+                    Ok(None) => {
+                        // Clean exit, or nothing was ever unsaved
+                        let _ = fs::remove_file(&lock);
+                        None
+                    }
+                    Err(e) => {
+                        tracing::warn!("Unreadable recovery journal {}: {}", journal.display(), e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        orphans.sort_by_key(|orphan| orphan.saved_at);
+        orphans
+    }
+
+    /// Remove the journal when the session exits cleanly; changes still
+    /// unsaved then were discarded on purpose and aren't offered next time
+    pub fn finish(&self) {
+        if let Err(e) = fs::remove_file(&self.journal) {
+            if e.kind() != io::ErrorKind::NotFound {
+                tracing::warn!("Failed to remove {}: {}", self.journal.display(), e);
+            }
+        }
+    }
+
+    /// Record the unsaved buffers, replacing the previous record.
+    /// With nothing unsaved the journal is removed.
+    pub async fn record(&self, buffers: Vec<RecoveredBuffer>) -> Result<(), FileError> {
+        let path = self.journal.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            if buffers.is_empty() {
+                return match fs::remove_file(&path) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                    _ => Ok(()),
+                };
+            }
+            let saved_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            let json = serde_json::to_vec(&Journal { saved_at, buffers }).map_err(io::Error::other)?;
+            atomic::replace_file(&path, &json)
+        })
+        .await
+        .map_err(io::Error::other)?;
+        Ok(result?)
+    }
+}
+
+/// Unified diff from the file on disk to the recovered text
+pub fn recovery_diff(name: &str, on_disk: &str, recovered: &str) -> String {
+    TextDiff::from_lines(on_disk, recovered)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("{name} (on disk)"), &format!("{name} (recovered)"))
+        .to_string()
+}
+
+// Whether another running session holds this lock file
+fn is_locked(path: &Path) -> bool {
+    match File::open(path) {
+        Ok(file) => file.try_lock_shared().is_err(),
+        Err(_) => false,
+    }
+}
+
+// Read a journal; `None` if there is none
+fn read_journal(path: &Path) -> io::Result<Option<OrphanedJournal>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let journal: Journal = serde_json::from_slice(&bytes).map_err(io::Error::other)?;
+    if journal.buffers.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(OrphanedJournal {
+        journal: path.to_path_buf(),
+        lock: path.with_extension("lock"),
+        saved_at: UNIX_EPOCH + Duration::from_secs(journal.saved_at),
+        buffers: journal.buffers,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unsaved(path: Option<&str>, text: &str) -> RecoveredBuffer {
+        RecoveredBuffer { path: path.map(PathBuf::from), text: text.to_string() }
+    }
+
+    // Files in the journal directory with the given extension
+    fn count(dir: &Path, extension: &str) -> usize {
+        fs::read_dir(dir)
+            .unwrap()
+            .flatten()
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == extension))
+            .count()
+    }
+
+    #[tokio::test]
+    async fn record_round_trips_through_the_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = RecoveryJournal::start_in(dir.path().to_path_buf()).unwrap();
+        let buffers = vec![unsaved(Some("/tmp/a.txt"), "one\ntwo"), unsaved(None, "scratch")];
+        session.record(buffers.clone()).await.unwrap();
+
+        let journal = read_journal(&session.journal).unwrap().unwrap();
+        assert_eq!(journal.buffers, buffers);
+        assert!(journal.saved_at.elapsed().unwrap() < Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn recording_nothing_removes_the_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = RecoveryJournal::start_in(dir.path().to_path_buf()).unwrap();
+        session.record(vec![unsaved(None, "x")]).await.unwrap();
+        session.record(Vec::new()).await.unwrap();
+        assert_eq!(read_journal(&session.journal).unwrap(), None);
+        // Nothing recorded yet is fine too
+        session.record(Vec::new()).await.unwrap();
+    }
+
+    #[test]
+    fn empty_or_missing_journals_read_as_none() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("x.json");
+        assert_eq!(read_journal(&path).unwrap(), None);
+        fs::write(&path, r#"{ "saved_at": 0, "buffers": [] }"#).unwrap();
+        assert_eq!(read_journal(&path).unwrap(), None);
+        fs::write(&path, "not json").unwrap();
+        assert!(read_journal(&path).is_err());
+    }
+
+    #[tokio::test]
+    async fn journals_of_running_sessions_are_not_orphans() {
+        let dir = tempfile::tempdir().unwrap();
+        let running = RecoveryJournal::start_in(dir.path().to_path_buf()).unwrap();
+        running.record(vec![unsaved(None, "still open")]).await.unwrap();
+
+        let session = RecoveryJournal::start_in(dir.path().to_path_buf()).unwrap();
+        assert!(session.orphans().is_empty());
+        assert_eq!(count(dir.path(), "json"), 1);
+    }
+
+    #[tokio::test]
+    async fn journals_of_crashed_sessions_are_orphans() {
+        let dir = tempfile::tempdir().unwrap();
+        let crashed = RecoveryJournal::start_in(dir.path().to_path_buf()).unwrap();
+        crashed.record(vec![unsaved(Some("/tmp/a.txt"), "lost work")]).await.unwrap();
+        // Dropping the session without finishing releases its lock, like a crash
+        drop(crashed);
+
+        let session = RecoveryJournal::start_in(dir.path().to_path_buf()).unwrap();
+        let orphans = session.orphans();
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].buffers, vec![unsaved(Some("/tmp/a.txt"), "lost work")]);
+
+        orphans[0].discard();
+        assert!(session.orphans().is_empty());
+        // Only this session's lock is left
+        assert_eq!((count(dir.path(), "json"), count(dir.path(), "lock")), (0, 1));
+    }
+
+    #[tokio::test]
+    async fn clean_exits_leave_nothing_to_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let finished = RecoveryJournal::start_in(dir.path().to_path_buf()).unwrap();
+        finished.record(vec![unsaved(None, "discarded on purpose")]).await.unwrap();
+        finished.finish();
+        drop(finished);
+
+        let session = RecoveryJournal::start_in(dir.path().to_path_buf()).unwrap();
+        assert!(session.orphans().is_empty());
+        // The stale lock file is cleaned up
+        assert_eq!(count(dir.path(), "lock"), 1);
+    }
+
+    #[test]
+    fn diff_shows_the_recovered_changes() {
+        let diff = recovery_diff("a.txt", "one\ntwo\n", "one\n2\n");
+        assert!(diff.contains("--- a.txt (on disk)"));
+        assert!(diff.contains("+++ a.txt (recovered)"));
+        assert!(diff.contains("-two\n+2\n"));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,314 @@
+// External file change detection
+// Watches the files open in the editor and reports when another program
+// (git checkout, a formatter...) rewrites them. Parent directories are
+// watched rather than the files themselves so changes made by renaming a
+// new file over the old one are seen too.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, SystemTime};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use super::io::FileError;
+
+/// How long to wait for a burst of events (e.g. truncate then write) to settle
+const SETTLE_DELAY: Duration = Duration::from_millis(100);
+
+// Size and modification time; a change in either means new contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileStamp {
+    // Current stamp of a file, `None` if it doesn't exist (or can't be read)
+    fn read(path: &Path) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+        Some(FileStamp { len: meta.len(), modified: meta.modified().ok() })
+    }
+}
+
+// A watched file, keyed by its canonical path
+#[derive(Debug)]
+struct WatchedFile {
+    // Path as the editor knows it, reported back on changes
+    path: PathBuf,
+    // Last contents seen; events that leave it unchanged are ignored
+    stamp: Option<FileStamp>,
+    // Set while the editor itself writes the file
+    suspended: bool,
+}
+
+type WatchedFiles = Arc<Mutex<HashMap<PathBuf, WatchedFile>>>;
+
+/// Watches a set of files for changes made outside the editor.
+/// Created together with a [`FileChanges`] stream that reports them.
+pub struct FileWatcher {
+    watcher: RecommendedWatcher,
+    // Editor path -> canonical path
+    paths: HashMap<PathBuf, PathBuf>,
+    // Watched directories and how many files each is watched for
+    dirs: HashMap<PathBuf, usize>,
+    files: WatchedFiles,
+}
+
+/// Stream of files changed on disk by other programs
+pub struct FileChanges {
+    events: mpsc::UnboundedReceiver<PathBuf>,
+}
+
+impl FileWatcher {
+    /// Start an (initially empty) watcher
+    pub fn new() -> Result<(Self, FileChanges), FileError> {
+        let files = WatchedFiles::default();
+        let (sender, events) = mpsc::unbounded_channel();
+
+        let handler_files = files.clone();
+        let watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+            let event = match result {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::warn!("File watcher error: {}", e);
+                    return;
+                }
+            };
+            let mut files = lock(&handler_files);
+            for key in &event.paths {
+                let Some(file) = files.get_mut(key) else {
+                    continue;
+                };
+                if file.suspended {
+                    continue;
+                }
+                let stamp = FileStamp::read(key);
+                if stamp == file.stamp {
+                    continue;
+                }
+                file.stamp = stamp;
+                // A deleted file is reported once it comes back
+                if stamp.is_some() {
+                    let _ = sender.send(file.path.clone());
+                }
+            }
+        })
+        .map_err(|e| FileError::IoError(io::Error::other(e)))?;
+
+        let watcher = FileWatcher {
+            watcher,
+            paths: HashMap::new(),
+            dirs: HashMap::new(),
+            files,
+        };
+        Ok((watcher, FileChanges { events }))
+    }
+
+    /// Watch exactly `paths`, adding new ones and dropping the rest
+    pub fn sync<'a>(&mut self, paths: impl IntoIterator<Item = &'a PathBuf>) {
+        let wanted: Vec<&PathBuf> = paths.into_iter().collect();
+        let stale: Vec<PathBuf> = self.paths
+            .keys()
+            .filter(|path| !wanted.contains(path))
+            .cloned()
+            .collect();
+        for path in stale {
+            self.unwatch(&path);
+        }
+        for path in wanted {
+            if let Err(e) = self.watch(path) {
+                tracing::warn!("Can't watch {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Start watching a file; it doesn't have to exist yet
+    pub fn watch(&mut self, path: &Path) -> Result<(), FileError> {
+        if self.paths.contains_key(path) {
+            return Ok(());
+        }
+        let key = watch_key(path).ok_or_else(|| FileError::NotFound(path.to_path_buf()))?;
+        let dir = key.parent().unwrap_or(&key).to_path_buf();
+
+        if !self.dirs.contains_key(&dir) {
+            self.watcher
+                .watch(&dir, RecursiveMode::NonRecursive)
+                .map_err(|e| FileError::IoError(io::Error::other(e)))?;
+        }
+        *self.dirs.entry(dir).or_default() += 1;
+
+        lock(&self.files).insert(key.clone(), WatchedFile {
+            path: path.to_path_buf(),
+            stamp: FileStamp::read(&key),
+            suspended: false,
+        });
+        self.paths.insert(path.to_path_buf(), key);
+        Ok(())
+    }
+
+    /// Stop watching a file
+    pub fn unwatch(&mut self, path: &Path) {
+        let Some(key) = self.paths.remove(path) else {
+            return;
+        };
+        lock(&self.files).remove(&key);
+
+        let dir = key.parent().unwrap_or(&key).to_path_buf();
+        if let Some(count) = self.dirs.get_mut(&dir) {
+            *count -= 1;
+            if *count == 0 {
+                self.dirs.remove(&dir);
+                let _ = self.watcher.unwatch(&dir);
+            }
+        }
+    }
+
+    /// Ignore changes to a file while the editor writes it itself
+    pub fn suspend(&mut self, path: &Path) {
+        self.update(path, |file| file.suspended = true);
+    }
+
+    /// Resume watching after a write, taking the file as it is now as current
+    pub fn resume(&mut self, path: &Path) {
+        self.update(path, |file| file.suspended = false);
+    }
+
+    // Apply `f` to a watched file and refresh its stamp
+    fn update(&mut self, path: &Path, f: impl FnOnce(&mut WatchedFile)) {
+        let Some(key) = self.paths.get(path) else {
+            return;
+        };
+        if let Some(file) = lock(&self.files).get_mut(key) {
+            f(file);
+            file.stamp = FileStamp::read(key);
+        }
+    }
+}
+
+impl FileChanges {
+    /// Wait for the next batch of changed files (editor paths, no duplicates).
+    /// Returns `None` once the watcher has been dropped.
+    pub async fn next(&mut self) -> Option<Vec<PathBuf>> {
+        let mut changed = vec![self.events.recv().await?];
+        while let Ok(Some(path)) = tokio::time::timeout(SETTLE_DELAY, self.events.recv()).await {
+            if !changed.contains(&path) {
+                changed.push(path);
+            }
+        }
+        Some(changed)
+    }
+}
+
+// Lock the watched files. A panic while holding the lock can't leave the map
+// half-updated in a way that matters, so a poisoned lock is used as is.
+fn lock(files: &WatchedFiles) -> MutexGuard<'_, HashMap<PathBuf, WatchedFile>> {
+    files.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+// Canonical path used to match watcher events, resolving symlinks so the
+// file that actually gets rewritten is watched. For a file that doesn't
+// exist yet only its directory is resolved.
+fn watch_key(path: &Path) -> Option<PathBuf> {
+    if let Ok(key) = fs::canonicalize(path) {
+        return Some(key);
+    }
+    let name = path.file_name()?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    Some(fs::canonicalize(dir).ok()?.join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Long enough for inotify & co. to deliver events and the batch to settle
+    const WAIT: Duration = Duration::from_secs(2);
+
+    // Next batch of changes, or None if nothing arrives in time
+    async fn next_batch(changes: &mut FileChanges) -> Option<Vec<PathBuf>> {
+        tokio::time::timeout(WAIT, changes.next()).await.ok().flatten()
+    }
+
+    #[tokio::test]
+    async fn reports_external_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "one").unwrap();
+        let (mut watcher, mut changes) = FileWatcher::new().unwrap();
+        watcher.watch(&path).unwrap();
+
+        fs::write(&path, "one two").unwrap();
+        assert_eq!(next_batch(&mut changes).await, Some(vec![path]));
+    }
+
+    #[tokio::test]
+    async fn batches_a_burst_of_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        fs::write(&a, "a").unwrap();
+        fs::write(&b, "b").unwrap();
+        let (mut watcher, mut changes) = FileWatcher::new().unwrap();
+        watcher.sync([&a, &b]);
+
+        fs::write(&a, "aa").unwrap();
+        fs::write(&b, "bb").unwrap();
+        fs::write(&a, "aaa").unwrap();
+        let mut batch = next_batch(&mut changes).await.unwrap();
+        batch.sort();
+        assert_eq!(batch, vec![a, b]);
+    }
+
+    #[tokio::test]
+    async fn ignores_own_writes_while_suspended() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "one").unwrap();
+        let (mut watcher, mut changes) = FileWatcher::new().unwrap();
+        watcher.watch(&path).unwrap();
+
+        watcher.suspend(&path);
+        fs::write(&path, "saved by the editor").unwrap();
+        // Let the events for the write arrive while still suspended
+        tokio::time::sleep(SETTLE_DELAY * 3).await;
+        watcher.resume(&path);
+        assert_eq!(next_batch(&mut changes).await, None);
+
+        // Watching carries on after resuming
+        fs::write(&path, "changed by someone else").unwrap();
+        assert_eq!(next_batch(&mut changes).await, Some(vec![path]));
+    }
+
+    #[tokio::test]
+    async fn ignores_events_that_leave_the_file_unchanged() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "one").unwrap();
+        let (mut watcher, mut changes) = FileWatcher::new().unwrap();
+        watcher.watch(&path).unwrap();
+
+        // Changing permissions fires an event but keeps size and mtime
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions).unwrap();
+        assert_eq!(next_batch(&mut changes).await, None);
+    }
+
+    #[tokio::test]
+    async fn stops_reporting_unwatched_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "one").unwrap();
+        let (mut watcher, mut changes) = FileWatcher::new().unwrap();
+        watcher.watch(&path).unwrap();
+        watcher.sync([]);
+
+        fs::write(&path, "one two").unwrap();
+        assert_eq!(next_batch(&mut changes).await, None);
+    }
+}
\ No newline at end of file
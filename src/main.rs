@@ -5,15 +5,51 @@
 #![allow(dead_code)]
 
 mod app;
+mod cli;
+mod clipboard;
+mod command;
+mod settings;
 mod theme;
 mod editor;
 mod document;
 mod file;
+mod syntax;
 mod ui;
 
+use std::io::Read;
+use std::process;
+
 fn main() {
     // Init logging
     tracing_subscriber::fmt::init();
+
+    // Parse command-line arguments
+    let mut args = match cli::parse(std::env::args().skip(1)) {
+        Ok(cli::Command::Launch(args)) => args,
+        Ok(cli::Command::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Ok(cli::Command::Version) => {
+            println!("stringr {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Err(e) => {
+            eprintln!("stringr: {}\n\n{}", e, cli::USAGE);
+            process::exit(2);
+        }
+    };
+
+    // Read the buffer from stdin when asked to with `-`
+    if args.reads_stdin() {
+        let mut text = String::new();
+        if let Err(e) = std::io::stdin().read_to_string(&mut text) {
+            eprintln!("stringr: failed to read stdin: {}", e);
+            process::exit(1);
+        }
+        args.stdin_text = Some(text);
+    }
+
     tracing::info!("Starting Stringr...");
 
     // Launch Dioxus desktop app with window configuration
@@ -25,6 +61,10 @@ fn main() {
                         .with_title("Stringr")
                         .with_resizable(true)
                 )
+                // Closing the window only hides it; the app asks about unsaved
+                // changes first and exits itself (see `app::app`)
+                .with_close_behaviour(dioxus::desktop::WindowCloseBehaviour::LastWindowHides)
         )
+        .with_context(args)
         .launch(app::app);
-}
\ No newline at end of file
+}
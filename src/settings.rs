@@ -0,0 +1,239 @@
+// User settings
+// Loaded from `<config dir>/stringr/settings.json`. Every field is optional;
+// missing ones fall back to the defaults in `theme::editor`.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::editor::{WordRules, DEFAULT_WORD_SEPARATORS};
+use crate::theme::editor;
+
+/// Allowed line heights (multiples of the font size)
+const LINE_HEIGHT_RANGE: RangeInclusive<f32> = 1.0..=3.0;
+
+/// Allowed tab sizes (in columns)
+const TAB_SIZE_RANGE: RangeInclusive<u32> = 1..=16;
+
+/// Allowed cursor blink rates (milliseconds); 0 turns blinking off
+const BLINK_RATE_RANGE: RangeInclusive<u32> = 100..=5000;
+
+/// Max scroll padding (in lines)
+const SCROLL_PADDING_MAX: u32 = 50;
+
+/// Editor settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Editor font size (px)
+    pub font_size: u32,
+    /// Line height as a multiple of the font size
+    pub line_height: f32,
+    /// Columns a tab character spans
+    pub tab_size: u32,
+    /// Cursor blink rate (ms), 0 for a steady cursor
+    pub cursor_blink_rate: u32,
+    /// Lines kept between the cursor and the edge of the view when scrolling
+    pub scroll_padding: u32,
+    /// Punctuation that ends a word for Ctrl+Left/Right (whitespace always does)
+    pub word_separators: String,
+    /// Make Ctrl+Left/Right stop at camelCase humps and underscores
+    pub subword_navigation: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            font_size: editor::FONT_SIZE_DEFAULT,
+            line_height: editor::LINE_HEIGHT,
+            tab_size: editor::TAB_SIZE,
+            cursor_blink_rate: editor::CURSOR_BLINK_RATE,
+            scroll_padding: editor::SCROLL_PADDING,
+            word_separators: DEFAULT_WORD_SEPARATORS.to_string(),
+            subword_navigation: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Load settings from `path`; a missing file means all defaults
+    pub fn load(path: &Path) -> Result<Self, SettingsError> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(SettingsError::Io(path.to_path_buf(), e)),
+        };
+        let settings: Settings = serde_json::from_str(&text)
+            .map_err(|e| SettingsError::Parse(path.to_path_buf(), e))?;
+
+        let problems = settings.validate();
+        if !problems.is_empty() {
+            return Err(SettingsError::Invalid(path.to_path_buf(), problems));
+        }
+        Ok(settings)
+    }
+
+    /// Describe every out-of-range value
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let font_sizes = editor::FONT_SIZE_MIN..=editor::FONT_SIZE_MAX;
+        if !font_sizes.contains(&self.font_size) {
+            problems.push(out_of_range("font_size", &font_sizes, self.font_size));
+        }
+        if !LINE_HEIGHT_RANGE.contains(&self.line_height) {
+            problems.push(out_of_range("line_height", &LINE_HEIGHT_RANGE, self.line_height));
+        }
+        if !TAB_SIZE_RANGE.contains(&self.tab_size) {
+            problems.push(out_of_range("tab_size", &TAB_SIZE_RANGE, self.tab_size));
+        }
+        if self.cursor_blink_rate != 0 && !BLINK_RATE_RANGE.contains(&self.cursor_blink_rate) {
+            problems.push(format!(
+                "{} (or 0 to turn blinking off)",
+                out_of_range("cursor_blink_rate", &BLINK_RATE_RANGE, self.cursor_blink_rate)
+            ));
+        }
+        if self.scroll_padding > SCROLL_PADDING_MAX {
+            problems.push(out_of_range("scroll_padding", &(0..=SCROLL_PADDING_MAX), self.scroll_padding));
+        }
+        problems
+    }
+
+    /// Word boundaries for word motions
+    pub fn word_rules(&self) -> WordRules {
+        WordRules {
+            separators: self.word_separators.clone(),
+            subwords: self.subword_navigation,
+        }
+    }
+
+    /// Line height in pixels
+    pub fn line_height_px(&self) -> f64 {
+        self.font_size as f64 * self.line_height as f64
+    }
+}
+
+/// Stringr's directory under the platform config directory, if there is one
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("stringr"))
+}
+
+/// Where the settings file lives
+pub fn settings_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("settings.json"))
+}
+
+/// Where custom key bindings live
+pub fn keymap_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("keybindings.json"))
+}
+
+// "field must be between min and max (got value)"
+fn out_of_range<T: fmt::Display>(field: &str, range: &RangeInclusive<T>, value: T) -> String {
+    format!("{} must be between {} and {} (got {})", field, range.start(), range.end(), value)
+}
+
+/// Why a settings file was rejected
+#[derive(Debug)]
+pub enum SettingsError {
+    /// The file couldn't be read
+    Io(PathBuf, io::Error),
+    /// Not valid JSON, a value of the wrong type or an unknown field
+    Parse(PathBuf, serde_json::Error),
+    /// Well-formed, but with out-of-range values
+    Invalid(PathBuf, Vec<String>),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(path, e) => write!(f, "Can't read {}: {}", path.display(), e),
+            // serde_json errors already end with "at line L column C"
+            SettingsError::Parse(path, e) => write!(f, "Error in {}: {}", path.display(), e),
+            SettingsError::Invalid(path, problems) => {
+                write!(f, "Invalid settings in {}: {}", path.display(), problems.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(Settings::default().validate().is_empty());
+    }
+
+    #[test]
+    fn range_bounds_are_inclusive() {
+        let settings = Settings {
+            font_size: editor::FONT_SIZE_MAX,
+            line_height: 1.0,
+            tab_size: 16,
+            cursor_blink_rate: 100,
+            scroll_padding: SCROLL_PADDING_MAX,
+            ..Settings::default()
+        };
+        assert!(settings.validate().is_empty());
+    }
+
+    #[test]
+    fn reports_every_out_of_range_value() {
+        let settings = Settings {
+            font_size: editor::FONT_SIZE_MIN - 1,
+            line_height: 3.5,
+            tab_size: 0,
+            cursor_blink_rate: 50,
+            scroll_padding: SCROLL_PADDING_MAX + 1,
+            ..Settings::default()
+        };
+        assert_eq!(settings.validate(), vec![
+            "font_size must be between 10 and 32 (got 9)",
+            "line_height must be between 1 and 3 (got 3.5)",
+            "tab_size must be between 1 and 16 (got 0)",
+            "cursor_blink_rate must be between 100 and 5000 (got 50) (or 0 to turn blinking off)",
+            "scroll_padding must be between 0 and 50 (got 51)",
+        ]);
+    }
+
+    #[test]
+    fn zero_blink_rate_turns_blinking_off() {
+        let settings = Settings { cursor_blink_rate: 0, ..Settings::default() };
+        assert!(settings.validate().is_empty());
+    }
+
+    #[test]
+    fn missing_file_means_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = Settings::load(&dir.path().join("settings.json")).unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn load_fills_in_missing_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(&path, r#"{ "tab_size": 2 }"#).unwrap();
+        let settings = Settings::load(&path).unwrap();
+        assert_eq!(settings, Settings { tab_size: 2, ..Settings::default() });
+    }
+
+    #[test]
+    fn load_rejects_unknown_fields_and_invalid_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(&path, r#"{ "tab_sise": 2 }"#).unwrap();
+        assert!(matches!(Settings::load(&path), Err(SettingsError::Parse(..))));
+
+        fs::write(&path, r#"{ "tab_size": 40 }"#).unwrap();
+        match Settings::load(&path) {
+            Err(SettingsError::Invalid(_, problems)) => assert_eq!(problems.len(), 1),
+            other => panic!("expected invalid settings, got {other:?}"),
+        }
+    }
+}
\ No newline at end of file
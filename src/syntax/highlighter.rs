@@ -0,0 +1,287 @@
+// Incremental highlighter
+// Caches spans per line from the top of the buffer down to the last line that
+// has been rendered. After an edit only the changed lines are redone, plus the
+// lines below them until the state carried into a line is the same as before.
+
+use std::ops::Range;
+use crate::editor::{Buffer, LineChange};
+use super::{Language, LineState, Span};
+
+// Cached result for one line
+#[derive(Debug, Clone)]
+struct CachedLine {
+    start_state: LineState,
+    end_state: LineState,
+    spans: Vec<Span>,
+}
+
+/// Syntax highlight cache for one buffer
+#[derive(Debug, Clone, Default)]
+pub struct Highlighter {
+    language: Language,
+    // Highlighted lines, from the first line down
+    lines: Vec<CachedLine>,
+    // Buffer revision the cache is up to date with
+    revision: u64,
+}
+
+impl Highlighter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget everything, e.g. when a different buffer is shown
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Language in use
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Spans for each line in `range`, catching up with the buffer first
+    pub fn highlight(&mut self, buffer: &Buffer, range: Range<usize>) -> Vec<Vec<Span>> {
+        self.sync(buffer);
+        let end = range.end.min(buffer.line_count());
+        while self.lines.len() < end {
+            self.push_line(buffer);
+        }
+        let start = range.start.min(end);
+        self.lines[start..end].iter().map(|line| line.spans.clone()).collect()
+    }
+
+    // Bring the cache up to date with the buffer's text and path
+    fn sync(&mut self, buffer: &Buffer) {
+        let language = Language::from_path(buffer.path().map(|p| p.as_path()));
+        if language != self.language {
+            self.language = language;
+            self.lines.clear();
+        } else if self.revision != buffer.revision() {
+            match buffer.line_changes_since(self.revision) {
+                Some(change) => self.apply_change(buffer, change),
+                None => self.lines.clear(),
+            }
+        }
+        self.revision = buffer.revision();
+    }
+
+    // Redo the changed lines and the ones below them whose start state changed
+    fn apply_change(&mut self, buffer: &Buffer, change: LineChange) {
+        if change.start >= self.lines.len() {
+            return;
+        }
+        if change.old_end >= self.lines.len() {
+            self.lines.truncate(change.start);
+            return;
+        }
+
+        let mut tail = self.lines.split_off(change.old_end).into_iter();
+        self.lines.truncate(change.start);
+        let line_count = buffer.line_count();
+        while self.lines.len() < change.new_end.min(line_count) {
+            self.push_line(buffer);
+        }
+
+        // Lines below the change kept their text; reuse them from the first one
+        // that starts in the same state as before
+        while let Some(cached) = tail.next() {
+            if self.lines.len() >= line_count {
+                break;
+            }
+            if cached.start_state == self.next_state() {
+                self.lines.push(cached);
+                self.lines.extend(tail.by_ref());
+                break;
+            }
+            self.push_line(buffer);
+        }
+        self.lines.truncate(line_count);
+    }
+
+    // State carried into the next uncached line
+    fn next_state(&self) -> LineState {
+        self.lines.last().map_or(LineState::Normal, |line| line.end_state)
+    }
+
+    // Highlight the first uncached line
+    fn push_line(&mut self, buffer: &Buffer) {
+        let start_state = self.next_state();
+        let text = buffer.line_text(self.lines.len());
+        let (spans, end_state) = self.language.tokenize_line(&text, start_state);
+        self.lines.push(CachedLine { start_state, end_state, spans });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use proptest::prelude::*;
+    use crate::syntax::TokenKind;
+
+    const RUST: &str = "fn main() {\n    let s = \"text\";\n    // note\n    let n = 42;\n}\n";
+    const MARKDOWN: &str = "# Title\n\nSome *text*.\n\nfn code() {}\n\nMore text.\n";
+    const TOML: &str = "[package]\nname = \"demo\"\nversion = 1\n\n[deps]\nx = true\n";
+
+    fn buffer(name: &str, text: &str) -> Buffer {
+        Buffer::from_file(PathBuf::from(name), text.to_string())
+    }
+
+    // Spans for the whole buffer from a fresh highlighter
+    fn full(buffer: &Buffer) -> Vec<Vec<Span>> {
+        Highlighter::new().highlight(buffer, 0..buffer.line_count())
+    }
+
+    fn insert(buffer: &mut Buffer, line: usize, col: usize, text: &str) {
+        buffer.set_cursor_position(line, col, false);
+        buffer.insert_str(text);
+    }
+
+    fn kinds(spans: &[Span]) -> Vec<TokenKind> {
+        spans.iter().map(|span| span.kind).collect()
+    }
+
+    // Highlight everything, edit with `edit`, and check that catching up
+    // with the edit matches starting over; returns the spans after the edit
+    fn check_edit(buffer: &mut Buffer, edit: impl FnOnce(&mut Buffer)) -> Vec<Vec<Span>> {
+        let mut highlighter = Highlighter::new();
+        highlighter.highlight(buffer, 0..buffer.line_count());
+        edit(buffer);
+        let spans = highlighter.highlight(buffer, 0..buffer.line_count());
+        assert_eq!(spans, full(buffer), "after edit:\n{}", buffer.text());
+        spans
+    }
+
+    #[test]
+    fn opening_a_block_comment_reaches_the_lines_below() {
+        let mut buffer = buffer("main.rs", RUST);
+        let spans = check_edit(&mut buffer, |buffer| insert(buffer, 1, 4, "/* "));
+        assert_eq!(kinds(&spans[3]), vec![TokenKind::Comment]);
+
+        let spans = check_edit(&mut buffer, |buffer| insert(buffer, 2, 11, " */"));
+        assert!(kinds(&spans[3]).contains(&TokenKind::Number));
+    }
+
+    #[test]
+    fn undoing_a_block_comment_restores_the_lines_below() {
+        let mut buffer = buffer("main.rs", RUST);
+        let before = full(&buffer);
+        check_edit(&mut buffer, |buffer| insert(buffer, 0, 0, "/*"));
+        let spans = check_edit(&mut buffer, |buffer| {
+            buffer.undo();
+        });
+        assert_eq!(spans, before);
+    }
+
+    #[test]
+    fn raw_strings_span_lines() {
+        let mut buffer = buffer("main.rs", RUST);
+        let spans = check_edit(&mut buffer, |buffer| insert(buffer, 1, 12, "r#\"\n"));
+        assert_eq!(kinds(&spans[4]), vec![TokenKind::String]);
+        check_edit(&mut buffer, |buffer| insert(buffer, 4, 0, "\"#"));
+    }
+
+    #[test]
+    fn code_fences_change_the_lines_inside_them() {
+        let mut buffer = buffer("README.md", MARKDOWN);
+        let before = full(&buffer);
+        let spans = check_edit(&mut buffer, |buffer| insert(buffer, 3, 0, "```\n"));
+        assert_ne!(spans[5], before[4]);
+        // Closing the fence puts the text after it back to normal
+        let spans = check_edit(&mut buffer, |buffer| insert(buffer, 6, 0, "```\n"));
+        assert_eq!(spans[8], before[6]);
+        check_edit(&mut buffer, |buffer| {
+            buffer.set_cursor_position(3, 0, false);
+            buffer.set_cursor_position(4, 0, true);
+            buffer.delete_selection();
+        });
+    }
+
+    #[test]
+    fn triple_quoted_strings_span_lines() {
+        let mut buffer = buffer("Cargo.toml", TOML);
+        let spans = check_edit(&mut buffer, |buffer| insert(buffer, 1, 7, "'''"));
+        assert_eq!(kinds(&spans[2]), vec![TokenKind::String]);
+        check_edit(&mut buffer, |buffer| insert(buffer, 3, 0, "'''"));
+    }
+
+    #[test]
+    fn edits_above_a_partly_highlighted_buffer() {
+        let text = RUST.repeat(20);
+        let mut buffer = buffer("main.rs", &text);
+        let mut highlighter = Highlighter::new();
+        highlighter.highlight(&buffer, 30..40);
+        insert(&mut buffer, 2, 0, "/*");
+        highlighter.highlight(&buffer, 30..40);
+        insert(&mut buffer, 60, 0, "*/");
+        let line_count = buffer.line_count();
+        assert_eq!(highlighter.highlight(&buffer, 0..line_count), full(&buffer));
+    }
+
+    // Snippets that open or close multi-line state in one of the languages
+    fn piece() -> impl Strategy<Value = &'static str> {
+        prop::sample::select(vec![
+            "/*", "*/", "\"", "r#\"", "\"#", "'''", "\"\"\"", "```", "~~~", "\n", "\n```\n", "x", " ", "#", "//",
+        ])
+    }
+
+    #[derive(Debug, Clone)]
+    enum TestEdit {
+        Insert(f64, f64, &'static str),
+        Delete(f64, f64, usize),
+        Undo,
+    }
+
+    fn test_edit() -> impl Strategy<Value = TestEdit> {
+        prop_oneof![
+            4 => (0.0..1.0, 0.0..1.0, piece()).prop_map(|(line, col, text)| TestEdit::Insert(line, col, text)),
+            2 => (0.0..1.0, 0.0..1.0, 1..6usize).prop_map(|(line, col, len)| TestEdit::Delete(line, col, len)),
+            1 => Just(TestEdit::Undo),
+        ]
+    }
+
+    // Line and column at fractions of the buffer's length
+    fn position(buffer: &Buffer, line: f64, col: f64) -> (usize, usize) {
+        let line = (line * buffer.line_count() as f64) as usize;
+        let len = buffer.line_text(line).chars().count();
+        (line, (col * (len + 1) as f64) as usize)
+    }
+
+    proptest! {
+        #[test]
+        fn incremental_highlighting_equals_a_full_pass(
+            name in prop::sample::select(vec!["main.rs", "README.md", "Cargo.toml", "run.sh", "data.json"]),
+            edits in prop::collection::vec((test_edit(), 0.0..1.0f64), 1..12),
+        ) {
+            let text = [RUST, MARKDOWN, TOML].concat();
+            let mut buffer = buffer(name, &text);
+            let mut highlighter = Highlighter::new();
+            for (edit, view) in edits {
+                match edit {
+                    TestEdit::Insert(line, col, text) => {
+                        let (line, col) = position(&buffer, line, col);
+                        insert(&mut buffer, line, col, text);
+                    }
+                    TestEdit::Delete(line, col, len) => {
+                        let (line, col) = position(&buffer, line, col);
+                        buffer.set_cursor_position(line, col, false);
+                        for _ in 0..len {
+                            buffer.delete_forward();
+                        }
+                    }
+                    TestEdit::Undo => {
+                        buffer.undo();
+                    }
+                }
+                // Only part of the buffer is highlighted, like a scrolled view
+                let start = (view * buffer.line_count() as f64) as usize;
+                let range = start..(start + 5).min(buffer.line_count());
+                let expected = full(&buffer)[range.clone()].to_vec();
+                prop_assert_eq!(highlighter.highlight(&buffer, range), expected);
+            }
+            let line_count = buffer.line_count();
+            prop_assert_eq!(highlighter.highlight(&buffer, 0..line_count), full(&buffer));
+        }
+    }
+}
\ No newline at end of file
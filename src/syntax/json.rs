@@ -0,0 +1,42 @@
+// JSON tokenizer (also accepts JSONC line comments)
+
+use super::scanner::{peek_non_space, scan_ident, scan_quoted, scan_while, starts_with, Tokens};
+use super::{LineState, Span, TokenKind};
+
+pub fn tokenize(chars: &[char], _state: LineState) -> (Vec<Span>, LineState) {
+    let mut tokens = Tokens::default();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '/' if starts_with(chars, i, "//") => {
+                tokens.push(i, chars.len(), TokenKind::Comment);
+                i = chars.len();
+            }
+            '"' => {
+                let (end, _) = scan_quoted(chars, i + 1, '"', true);
+                // Object keys are followed by a colon
+                let kind = if peek_non_space(chars, end) == Some(':') { TokenKind::Function } else { TokenKind::String };
+                tokens.push(i, end, kind);
+                i = end;
+            }
+            c if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) => {
+                let end = scan_while(chars, i + 1, |c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'));
+                tokens.push(i, end, TokenKind::Number);
+                i = end;
+            }
+            c if c.is_ascii_alphabetic() => {
+                let end = scan_ident(chars, i);
+                let word: String = chars[i..end].iter().collect();
+                if matches!(word.as_str(), "true" | "false" | "null") {
+                    tokens.push(i, end, TokenKind::Constant);
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    // JSON strings can't span lines, so there is never state to carry
+    (tokens.into_spans(), LineState::Normal)
+}
\ No newline at end of file
@@ -0,0 +1,67 @@
+// Language detection from file names
+
+use std::path::Path;
+use super::{json, markdown, rust, shell, toml, LineState, Span};
+
+/// Languages with syntax highlighting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    PlainText,
+    Rust,
+    Toml,
+    Json,
+    Markdown,
+    Shell,
+}
+
+impl Language {
+    /// Pick a language from a file's name or extension
+    pub fn from_path(path: Option<&Path>) -> Self {
+        let Some(path) = path else {
+            return Language::PlainText;
+        };
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        match name {
+            "Cargo.lock" => return Language::Toml,
+            ".bashrc" | ".bash_profile" | ".bash_aliases" | ".profile" | ".zshrc" | ".zprofile" => {
+                return Language::Shell
+            }
+            _ => {}
+        }
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+        match extension.to_ascii_lowercase().as_str() {
+            "rs" => Language::Rust,
+            "toml" => Language::Toml,
+            "json" | "jsonc" => Language::Json,
+            "md" | "markdown" => Language::Markdown,
+            "sh" | "bash" | "zsh" => Language::Shell,
+            _ => Language::PlainText,
+        }
+    }
+
+    /// Display name
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::PlainText => "Plain Text",
+            Language::Rust => "Rust",
+            Language::Toml => "TOML",
+            Language::Json => "JSON",
+            Language::Markdown => "Markdown",
+            Language::Shell => "Shell",
+        }
+    }
+
+    /// Highlight one line, given the state left by the line before it
+    pub fn tokenize_line(self, line: &str, state: LineState) -> (Vec<Span>, LineState) {
+        let chars: Vec<char> = line.chars().collect();
+        match self {
+            Language::PlainText => (Vec::new(), LineState::Normal),
+            Language::Rust => rust::tokenize(&chars, state),
+            Language::Toml => toml::tokenize(&chars, state),
+            Language::Json => json::tokenize(&chars, state),
+            Language::Markdown => markdown::tokenize(&chars, state),
+            Language::Shell => shell::tokenize(&chars, state),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,123 @@
+// Markdown tokenizer
+
+use super::scanner::{scan_while, starts_with, Tokens};
+use super::{LineState, Span, TokenKind};
+
+pub fn tokenize(chars: &[char], state: LineState) -> (Vec<Span>, LineState) {
+    let mut tokens = Tokens::default();
+    let indent = scan_while(chars, 0, |c| c == ' ');
+    let fence = fence_char(chars, indent);
+
+    // Fenced code blocks are shown as one string, fences included
+    if let LineState::Fence(open) = state {
+        tokens.push(0, chars.len(), TokenKind::String);
+        let state = if fence == Some(open) { LineState::Normal } else { state };
+        return (tokens.into_spans(), state);
+    }
+    if let Some(fence) = fence {
+        tokens.push(0, chars.len(), TokenKind::String);
+        return (tokens.into_spans(), LineState::Fence(fence));
+    }
+
+    // Headings and block quotes take the whole line
+    let hashes = scan_while(chars, indent, |c| c == '#') - indent;
+    if (1..=6).contains(&hashes) && chars.get(indent + hashes).is_none_or(|c| c.is_whitespace()) {
+        tokens.push(indent, chars.len(), TokenKind::Keyword);
+        return (tokens.into_spans(), LineState::Normal);
+    }
+    if chars.get(indent) == Some(&'>') {
+        tokens.push(indent, chars.len(), TokenKind::Comment);
+        return (tokens.into_spans(), LineState::Normal);
+    }
+
+    // List markers: -, *, + or 1. / 1)
+    let mut i = indent;
+    let digits = scan_while(chars, i, |c| c.is_ascii_digit());
+    let marker_end = match chars.get(i) {
+        Some('-' | '*' | '+') => Some(i + 1),
+        Some(c) if c.is_ascii_digit() && matches!(chars.get(digits), Some('.' | ')')) => Some(digits + 1),
+        _ => None,
+    };
+    if let Some(end) = marker_end.filter(|&end| chars.get(end).is_none_or(|c| c.is_whitespace())) {
+        tokens.push(i, end, TokenKind::Constant);
+        i = end;
+    }
+
+    inline(chars, i, &mut tokens);
+    (tokens.into_spans(), LineState::Normal)
+}
+
+// Code spans, emphasis, links and autolinks within a line
+fn inline(chars: &[char], mut i: usize, tokens: &mut Tokens) {
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' => i += 2,
+            '`' => {
+                // A code span closes with a backtick run of the same length
+                let run = scan_while(chars, i, |c| c == '`') - i;
+                let closing = "`".repeat(run);
+                let close = (i + run..chars.len()).find(|&j| {
+                    starts_with(chars, j, &closing) && chars.get(j + run) != Some(&'`')
+                });
+                match close {
+                    Some(close) => {
+                        tokens.push(i, close + run, TokenKind::String);
+                        i = close + run;
+                    }
+                    None => i += run,
+                }
+            }
+            '*' | '_' => {
+                let run = (scan_while(chars, i, |ch| ch == c) - i).min(2);
+                let delimiter = c.to_string().repeat(run);
+                // `_` inside words (snake_case) is not emphasis
+                let intraword = c == '_' && i > 0 && chars[i - 1].is_alphanumeric();
+                let close = (i + run + 1..chars.len()).find(|&j| starts_with(chars, j, &delimiter));
+                match close {
+                    Some(close) if !intraword && !chars[i + run].is_whitespace() => {
+                        tokens.push(i, close + run, TokenKind::Type);
+                        i = close + run;
+                    }
+                    _ => i += run,
+                }
+            }
+            // [text](url) and ![alt](url)
+            '[' | '!' if c == '[' || chars.get(i + 1) == Some(&'[') => {
+                match link_end(chars, if c == '!' { i + 1 } else { i }) {
+                    Some(end) => {
+                        tokens.push(i, end, TokenKind::Function);
+                        i = end;
+                    }
+                    None => i += 1,
+                }
+            }
+            // <https://...> autolinks
+            '<' if starts_with(chars, i + 1, "http") => {
+                let end = chars[i..].iter().position(|&c| c == '>').map_or(chars.len(), |n| i + n + 1);
+                tokens.push(i, end, TokenKind::Function);
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+// End of a `[text](url)` link starting at the `[` at `i`
+fn link_end(chars: &[char], i: usize) -> Option<usize> {
+    let close = i + chars[i..].iter().position(|&c| c == ']')?;
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+    let end = close + chars[close..].iter().position(|&c| c == ')')?;
+    Some(end + 1)
+}
+
+// Fence char if the line opens or closes a fenced code block
+fn fence_char(chars: &[char], indent: usize) -> Option<char> {
+    let c = *chars.get(indent)?;
+    if indent > 3 || !matches!(c, '`' | '~') {
+        return None;
+    }
+    (scan_while(chars, indent, |ch| ch == c) - indent >= 3).then_some(c)
+}
\ No newline at end of file
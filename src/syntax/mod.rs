@@ -0,0 +1,72 @@
+// Syntax highlighting module
+// The language is picked from the file extension. Each language is a small
+// hand-written line tokenizer that carries state (open comments, strings,
+// code fences...) from the end of one line to the start of the next.
+
+mod highlighter;
+mod json;
+mod language;
+mod markdown;
+mod rust;
+mod scanner;
+mod shell;
+mod toml;
+
+pub use highlighter::Highlighter;
+pub use language::Language;
+
+use crate::theme::colors;
+
+/// Kind of a highlighted token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Keyword,
+    String,
+    Number,
+    Comment,
+    Function,
+    Type,
+    Variable,
+    Constant,
+}
+
+impl TokenKind {
+    /// Theme color for this kind of token
+    pub fn color(self) -> &'static str {
+        match self {
+            TokenKind::Keyword => colors::SYNTAX_KEYWORD,
+            TokenKind::String => colors::SYNTAX_STRING,
+            TokenKind::Number => colors::SYNTAX_NUMBER,
+            TokenKind::Comment => colors::SYNTAX_COMMENT,
+            TokenKind::Function => colors::SYNTAX_FUNCTION,
+            TokenKind::Type => colors::SYNTAX_TYPE,
+            TokenKind::Variable => colors::SYNTAX_VARIABLE,
+            TokenKind::Constant => colors::SYNTAX_CONSTANT,
+        }
+    }
+}
+
+/// A highlighted char column range on one line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub kind: TokenKind,
+}
+
+/// Tokenizer state carried from the end of one line into the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineState {
+    #[default]
+    Normal,
+    /// Inside a block comment, nested this deep
+    BlockComment(u8),
+    /// Inside a string closed by this quote
+    Quoted(char),
+    /// Inside a Rust raw string closed by a quote and this many `#`s
+    RawString(u8),
+    /// Inside a triple-quoted string (TOML)
+    TripleQuoted(char),
+    /// Inside a fenced code block opened with this fence char (Markdown)
+    Fence(char),
+}
\ No newline at end of file
@@ -0,0 +1,229 @@
+// Rust tokenizer
+
+use super::scanner::{
+    is_ident_char, is_ident_start, peek_non_space, scan_ident, scan_number, scan_quoted, starts_with, Tokens,
+};
+use super::{LineState, Span, TokenKind};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self",
+    "static", "struct", "super", "trait", "type", "unsafe", "use", "where", "while", "yield",
+];
+
+const PRIMITIVES: &[&str] = &[
+    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128",
+    "isize", "f32", "f64",
+];
+
+pub fn tokenize(chars: &[char], state: LineState) -> (Vec<Span>, LineState) {
+    let mut tokens = Tokens::default();
+    let (mut i, mut state) = resume(chars, state, &mut tokens);
+
+    while i < chars.len() && state == LineState::Normal {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '/' if next == Some('/') => {
+                tokens.push(i, chars.len(), TokenKind::Comment);
+                i = chars.len();
+            }
+            '/' if next == Some('*') => {
+                let (end, depth) = block_comment(chars, i + 2, 1);
+                tokens.push(i, end, TokenKind::Comment);
+                state = if depth > 0 { LineState::BlockComment(depth) } else { LineState::Normal };
+                i = end;
+            }
+            '"' => {
+                let (end, closed) = scan_quoted(chars, i + 1, '"', true);
+                tokens.push(i, end, TokenKind::String);
+                if !closed {
+                    state = LineState::Quoted('"');
+                }
+                i = end;
+            }
+            // Raw strings: r"..", r#".."#, br#".."#
+            'r' | 'b' if raw_string_start(chars, i).is_some() => {
+                let (open_end, hashes) = raw_string_start(chars, i).unwrap_or((i + 1, 0));
+                let (end, closed) = raw_string_end(chars, open_end, hashes);
+                tokens.push(i, end, TokenKind::String);
+                if !closed {
+                    state = LineState::RawString(hashes);
+                }
+                i = end;
+            }
+            // Byte strings and byte chars
+            'b' if matches!(next, Some('"' | '\'')) => {
+                let quote = next.unwrap_or('"');
+                let (end, closed) = scan_quoted(chars, i + 2, quote, true);
+                tokens.push(i, end, TokenKind::String);
+                if !closed && quote == '"' {
+                    state = LineState::Quoted('"');
+                }
+                i = end;
+            }
+            // Char literal or lifetime
+            '\'' => {
+                if next == Some('\\') {
+                    let (end, _) = scan_quoted(chars, i + 1, '\'', true);
+                    tokens.push(i, end, TokenKind::String);
+                    i = end;
+                } else if chars.get(i + 2) == Some(&'\'') {
+                    tokens.push(i, i + 3, TokenKind::String);
+                    i += 3;
+                } else {
+                    let end = scan_ident(chars, i + 1);
+                    tokens.push(i, end, TokenKind::Type);
+                    i = end.max(i + 1);
+                }
+            }
+            // Attributes
+            '#' if matches!(next, Some('[' | '!')) => {
+                let end = attribute_end(chars, i);
+                tokens.push(i, end, TokenKind::Constant);
+                i = end;
+            }
+            c if c.is_ascii_digit() => {
+                let end = scan_number(chars, i);
+                tokens.push(i, end, TokenKind::Number);
+                i = end;
+            }
+            c if is_ident_start(c) => {
+                let end = scan_ident(chars, i);
+                let word: String = chars[i..end].iter().collect();
+                // Macros include their `!`
+                let is_macro = chars.get(end) == Some(&'!') && chars.get(end + 1) != Some(&'=');
+                if is_macro {
+                    tokens.push(i, end + 1, TokenKind::Function);
+                    i = end + 1;
+                } else {
+                    if let Some(kind) = classify(&word, peek_non_space(chars, end)) {
+                        tokens.push(i, end, kind);
+                    }
+                    i = end;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    (tokens.into_spans(), state)
+}
+
+// Continue a comment or string left open on the previous line
+fn resume(chars: &[char], state: LineState, tokens: &mut Tokens) -> (usize, LineState) {
+    let (end, state) = match state {
+        LineState::BlockComment(depth) => {
+            let (end, depth) = block_comment(chars, 0, depth);
+            tokens.push(0, end, TokenKind::Comment);
+            (end, if depth > 0 { LineState::BlockComment(depth) } else { LineState::Normal })
+        }
+        LineState::Quoted(quote) => {
+            let (end, closed) = scan_quoted(chars, 0, quote, true);
+            tokens.push(0, end, TokenKind::String);
+            (end, if closed { LineState::Normal } else { state })
+        }
+        LineState::RawString(hashes) => {
+            let (end, closed) = raw_string_end(chars, 0, hashes);
+            tokens.push(0, end, TokenKind::String);
+            (end, if closed { LineState::Normal } else { state })
+        }
+        _ => (0, LineState::Normal),
+    };
+    (end, state)
+}
+
+// Kind of an identifier, given the next non-space char after it
+fn classify(word: &str, next: Option<char>) -> Option<TokenKind> {
+    if KEYWORDS.contains(&word) {
+        return Some(TokenKind::Keyword);
+    }
+    if word == "true" || word == "false" {
+        return Some(TokenKind::Constant);
+    }
+    if PRIMITIVES.contains(&word) {
+        return Some(TokenKind::Type);
+    }
+    if next == Some('(') {
+        return Some(TokenKind::Function);
+    }
+    if word.starts_with(char::is_uppercase) {
+        let all_caps = word.len() > 1 && word.chars().all(|c| c.is_uppercase() || c.is_ascii_digit() || c == '_');
+        return Some(if all_caps { TokenKind::Constant } else { TokenKind::Type });
+    }
+    None
+}
+
+// Scan a (nested) block comment from `i`; returns the end and the remaining depth
+fn block_comment(chars: &[char], mut i: usize, mut depth: u8) -> (usize, u8) {
+    while i < chars.len() {
+        if starts_with(chars, i, "/*") {
+            depth = depth.saturating_add(1);
+            i += 2;
+        } else if starts_with(chars, i, "*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return (i, 0);
+            }
+        } else {
+            i += 1;
+        }
+    }
+    (chars.len(), depth)
+}
+
+// If a raw string starts at `i`, the index after its opening quote and its `#` count
+fn raw_string_start(chars: &[char], i: usize) -> Option<(usize, u8)> {
+    let mut j = i;
+    if chars.get(j) == Some(&'b') {
+        j += 1;
+    }
+    if chars.get(j) != Some(&'r') {
+        return None;
+    }
+    // Not the end of an identifier like `bar`
+    if i > 0 && is_ident_char(chars[i - 1]) {
+        return None;
+    }
+    j += 1;
+    let mut hashes = 0u8;
+    while chars.get(j) == Some(&'#') {
+        hashes = hashes.saturating_add(1);
+        j += 1;
+    }
+    (chars.get(j) == Some(&'"')).then_some((j + 1, hashes))
+}
+
+// Find the closing quote + hashes of a raw string
+fn raw_string_end(chars: &[char], mut i: usize, hashes: u8) -> (usize, bool) {
+    while i < chars.len() {
+        if chars[i] == '"' {
+            let closing = (1..=hashes as usize).all(|n| chars.get(i + n) == Some(&'#'));
+            if closing {
+                return (i + 1 + hashes as usize, true);
+            }
+        }
+        i += 1;
+    }
+    (chars.len(), false)
+}
+
+// End of an attribute (`#[...]` / `#![...]`), matching nested brackets on this line
+fn attribute_end(chars: &[char], i: usize) -> usize {
+    let mut depth = 0;
+    let mut j = i;
+    while j < chars.len() {
+        match chars[j] {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return j + 1;
+                }
+            }
+            _ => {}
+        }
+        j += 1;
+    }
+    chars.len()
+}
\ No newline at end of file
@@ -0,0 +1,82 @@
+// Helpers shared by the language tokenizers
+// Lines are scanned as char slices so indices are char columns.
+
+use super::{Span, TokenKind};
+
+/// Spans collected while scanning a line
+#[derive(Debug, Default)]
+pub struct Tokens {
+    spans: Vec<Span>,
+}
+
+impl Tokens {
+    pub fn push(&mut self, start: usize, end: usize, kind: TokenKind) {
+        if start < end {
+            self.spans.push(Span { start, end, kind });
+        }
+    }
+
+    pub fn into_spans(self) -> Vec<Span> {
+        self.spans
+    }
+}
+
+pub fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+pub fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// End of the identifier starting at `i`
+pub fn scan_ident(chars: &[char], i: usize) -> usize {
+    scan_while(chars, i, is_ident_char)
+}
+
+/// End of the run of chars matching `pred` starting at `i`
+pub fn scan_while(chars: &[char], mut i: usize, pred: impl Fn(char) -> bool) -> usize {
+    while i < chars.len() && pred(chars[i]) {
+        i += 1;
+    }
+    i
+}
+
+/// End of the number starting at `i` (digits, radix prefixes, separators,
+/// fractions, exponents and type suffixes)
+pub fn scan_number(chars: &[char], mut i: usize) -> usize {
+    while i < chars.len() {
+        let c = chars[i];
+        let next_is_digit = chars.get(i + 1).is_some_and(|n| n.is_ascii_digit());
+        let is_exponent_sign = (c == '+' || c == '-') && i > 0 && matches!(chars[i - 1], 'e' | 'E');
+        if c.is_alphanumeric() || c == '_' || (c == '.' && next_is_digit) || (is_exponent_sign && next_is_digit) {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+    i
+}
+
+/// Scan a quoted string whose opening quote ends before `i`. Returns the end
+/// (after the closing quote) and whether the string was closed on this line.
+pub fn scan_quoted(chars: &[char], mut i: usize, quote: char, escapes: bool) -> (usize, bool) {
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if escapes => i += 2,
+            c if c == quote => return (i + 1, true),
+            _ => i += 1,
+        }
+    }
+    (chars.len(), false)
+}
+
+/// Next non-whitespace char at or after `i`
+pub fn peek_non_space(chars: &[char], i: usize) -> Option<char> {
+    chars[i.min(chars.len())..].iter().copied().find(|c| !c.is_whitespace())
+}
+
+/// Check if `pattern` occurs at `i`
+pub fn starts_with(chars: &[char], i: usize, pattern: &str) -> bool {
+    pattern.chars().enumerate().all(|(n, p)| chars.get(i + n) == Some(&p))
+}
\ No newline at end of file
@@ -0,0 +1,85 @@
+// Shell (sh/bash/zsh) tokenizer
+
+use super::scanner::{is_ident_char, is_ident_start, scan_ident, scan_quoted, scan_while, Tokens};
+use super::{LineState, Span, TokenKind};
+
+const KEYWORDS: &[&str] = &[
+    "if", "then", "else", "elif", "fi", "for", "while", "until", "do", "done", "case", "esac", "function", "in",
+    "select", "return", "local", "export", "readonly", "declare", "break", "continue", "exit", "source", "alias",
+    "unset", "shift",
+];
+
+pub fn tokenize(chars: &[char], state: LineState) -> (Vec<Span>, LineState) {
+    let mut tokens = Tokens::default();
+    let mut i = 0;
+    let mut state = state;
+
+    // Continue a quoted string from the previous line
+    if let LineState::Quoted(quote) = state {
+        let (end, closed) = scan_quoted(chars, 0, quote, quote == '"');
+        tokens.push(0, end, TokenKind::String);
+        if !closed {
+            return (tokens.into_spans(), state);
+        }
+        state = LineState::Normal;
+        i = end;
+    }
+
+    while i < chars.len() {
+        let c = chars[i];
+        // Only the start of a word can begin a comment or a number
+        let word_start = i == 0 || chars[i - 1].is_whitespace() || matches!(chars[i - 1], ';' | '|' | '&' | '(');
+        match c {
+            '#' if word_start => {
+                tokens.push(i, chars.len(), TokenKind::Comment);
+                i = chars.len();
+            }
+            '\\' => i += 2,
+            '"' | '\'' => {
+                let (end, closed) = scan_quoted(chars, i + 1, c, c == '"');
+                tokens.push(i, end, TokenKind::String);
+                if !closed {
+                    state = LineState::Quoted(c);
+                }
+                i = end;
+            }
+            // $NAME, ${NAME...}, $1, $@, $?...
+            '$' => {
+                let end = match chars.get(i + 1) {
+                    Some('{') => chars[i..].iter().position(|&c| c == '}').map_or(chars.len(), |n| i + n + 1),
+                    Some(&n) if is_ident_start(n) => scan_ident(chars, i + 1),
+                    Some(&n) if n.is_ascii_digit() || matches!(n, '@' | '*' | '#' | '?' | '$' | '!' | '-') => i + 2,
+                    _ => i + 1,
+                };
+                if end > i + 1 {
+                    tokens.push(i, end, TokenKind::Variable);
+                }
+                i = end;
+            }
+            c if c.is_ascii_digit() && word_start => {
+                let end = scan_while(chars, i, |c| c.is_ascii_digit());
+                // Only whole numbers, not words like `2to3`
+                if chars.get(end).is_none_or(|&n| !is_ident_char(n)) {
+                    tokens.push(i, end, TokenKind::Number);
+                }
+                i = end;
+            }
+            c if is_ident_start(c) => {
+                let end = scan_while(chars, i, |c| is_ident_char(c) || c == '-');
+                let word: String = chars[i..end].iter().collect();
+                if word_start && KEYWORDS.contains(&word.as_str()) {
+                    tokens.push(i, end, TokenKind::Keyword);
+                } else if chars.get(end) == Some(&'=') {
+                    // Assignment
+                    tokens.push(i, end, TokenKind::Variable);
+                } else if chars[end..].starts_with(&['(', ')']) {
+                    // Function definition
+                    tokens.push(i, end, TokenKind::Function);
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    (tokens.into_spans(), state)
+}
\ No newline at end of file
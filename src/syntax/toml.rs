@@ -0,0 +1,108 @@
+// TOML tokenizer
+
+use super::scanner::{peek_non_space, scan_quoted, scan_while, starts_with, Tokens};
+use super::{LineState, Span, TokenKind};
+
+pub fn tokenize(chars: &[char], state: LineState) -> (Vec<Span>, LineState) {
+    let mut tokens = Tokens::default();
+    let mut i = 0;
+    let mut state = state;
+
+    // Continue a multi-line string from the previous line
+    if let LineState::TripleQuoted(quote) = state {
+        let (end, closed) = triple_quoted_end(chars, 0, quote);
+        tokens.push(0, end, TokenKind::String);
+        if !closed {
+            return (tokens.into_spans(), state);
+        }
+        state = LineState::Normal;
+        i = end;
+    }
+
+    // Table headers: [table] / [[array.of.tables]]
+    let first = scan_while(chars, i, char::is_whitespace);
+    if i == 0 && chars.get(first) == Some(&'[') {
+        let end = chars.iter().rposition(|&c| c == ']').map_or(chars.len(), |end| end + 1);
+        tokens.push(first, end, TokenKind::Type);
+        i = end;
+    }
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '#' => {
+                tokens.push(i, chars.len(), TokenKind::Comment);
+                i = chars.len();
+            }
+            '"' | '\'' if starts_with(chars, i, &c.to_string().repeat(3)) => {
+                let (end, closed) = triple_quoted_end(chars, i + 3, c);
+                tokens.push(i, end, TokenKind::String);
+                if !closed {
+                    state = LineState::TripleQuoted(c);
+                }
+                i = end;
+            }
+            '"' | '\'' => {
+                let (end, _) = scan_quoted(chars, i + 1, c, c == '"');
+                tokens.push(i, end, key_or(chars, end, TokenKind::String));
+                i = end;
+            }
+            // Numbers, dates and times
+            c if c.is_ascii_digit() || ((c == '+' || c == '-') && chars.get(i + 1).is_some_and(char::is_ascii_digit)) => {
+                let end = scan_while(chars, i + 1, |c| c.is_alphanumeric() || matches!(c, '_' | '.' | ':' | '-' | '+'));
+                tokens.push(i, end, key_or(chars, end, TokenKind::Number));
+                i = end;
+            }
+            // Bare keys and literals
+            c if is_bare_key_char(c) => {
+                let end = scan_while(chars, i, is_bare_key_char);
+                let word: String = chars[i..end].iter().collect();
+                let kind = if is_key(chars, end) {
+                    Some(TokenKind::Function)
+                } else if matches!(word.as_str(), "true" | "false" | "inf" | "nan") {
+                    Some(TokenKind::Constant)
+                } else {
+                    None
+                };
+                if let Some(kind) = kind {
+                    tokens.push(i, end, kind);
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+    (tokens.into_spans(), state)
+}
+
+fn is_bare_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+// Keys are followed by `=` or a `.` of a dotted key
+fn is_key(chars: &[char], end: usize) -> bool {
+    matches!(peek_non_space(chars, end), Some('=' | '.'))
+}
+
+// Keys are highlighted as keys, whatever they look like
+fn key_or(chars: &[char], end: usize, kind: TokenKind) -> TokenKind {
+    if is_key(chars, end) { TokenKind::Function } else { kind }
+}
+
+// Find the closing triple quote of a multi-line string
+fn triple_quoted_end(chars: &[char], mut i: usize, quote: char) -> (usize, bool) {
+    let closing = quote.to_string().repeat(3);
+    while i < chars.len() {
+        if quote == '"' && chars[i] == '\\' {
+            i += 2;
+            continue;
+        }
+        if starts_with(chars, i, &closing) {
+            // Up to two extra quotes belong to the string: """a"""""
+            let end = scan_while(chars, i + 3, |c| c == quote).min(i + 5);
+            return (end, true);
+        }
+        i += 1;
+    }
+    (chars.len(), false)
+}
\ No newline at end of file
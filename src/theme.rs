@@ -205,8 +205,11 @@ pub mod colors {
     /// Line highlight (current line)
     pub const LINE_HIGHLIGHT: &str = "#ffffff08";
 
+    /// Search match background
+    pub const SEARCH_MATCH_BG: &str = "#00e69a40";
+
     // -------------------------------------------------------------------------
-    // Syntax Highlighting Colors (for future code support)
+    // Syntax Highlighting Colors
     // -------------------------------------------------------------------------
 
     /// Keywords
@@ -666,7 +669,7 @@ pub mod transitions {
 // EDITOR SPECIFIC
 // ============================================================================
 
-/// Editor-specific configuration
+/// Editor-specific configuration (defaults for the user settings in `settings.json`)
 pub mod editor {
     /// Default font size for editor (in pixels)
     pub const FONT_SIZE_DEFAULT: u32 = 14;
@@ -0,0 +1,130 @@
+// Command palette
+// Fuzzy-searchable list of every command with its key bindings
+
+use dioxus::prelude::*;
+use crate::command::{fuzzy_score, Command};
+
+/// A command as listed in the palette
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteCommand {
+    pub command: Command,
+    /// Key sequences bound to it, e.g. "Ctrl+S"
+    pub keys: Vec<String>,
+    /// Whether it was run recently
+    pub recent: bool,
+}
+
+/// Modal command palette
+#[component]
+pub fn CommandPalette(
+    /// Commands to offer, recently used ones first
+    commands: Vec<PaletteCommand>,
+    /// Called with the chosen command
+    on_run: EventHandler<Command>,
+    /// Called when the palette is dismissed
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut query = use_signal(String::new);
+    let mut selected = use_signal(|| 0usize);
+
+    let matches = filter_commands(&commands, &query.read());
+    let count = matches.len();
+    let selected_idx = selected().min(count.saturating_sub(1));
+    let chosen = matches.get(selected_idx).map(|item| item.command);
+
+    // Move the selection and keep it in view
+    let mut select = move |idx: usize| {
+        selected.set(idx);
+        document::eval(&format!(
+            "document.getElementById('palette-item-{idx}')?.scrollIntoView({{ block: 'nearest' }});"
+        ));
+    };
+
+    rsx! {
+        div {
+            class: "dialog-overlay",
+            onclick: move |_| on_close.call(()),
+
+            div {
+                class: "dialog-content command-palette",
+                onclick: move |evt| evt.stop_propagation(),
+
+                input {
+                    class: "input-brutal w-full mb-2",
+                    r#type: "text",
+                    placeholder: "Type a command...",
+                    value: "{query}",
+                    onmounted: move |evt| async move {
+                        let _ = evt.set_focus(true).await;
+                    },
+                    oninput: move |evt| {
+                        query.set(evt.value());
+                        selected.set(0);
+                    },
+                    onkeydown: move |evt| {
+                        evt.stop_propagation();
+                        match evt.key() {
+                            Key::ArrowDown if count > 0 => {
+                                evt.prevent_default();
+                                select((selected_idx + 1) % count);
+                            }
+                            Key::ArrowUp if count > 0 => {
+                                evt.prevent_default();
+                                select((selected_idx + count - 1) % count);
+                            }
+                            Key::Enter => {
+                                if let Some(command) = chosen {
+                                    on_run.call(command);
+                                }
+                            }
+                            Key::Escape => on_close.call(()),
+                            _ => {}
+                        }
+                    },
+                }
+
+                div {
+                    class: "palette-list",
+                    for (idx, item) in matches.into_iter().enumerate() {
+                        div {
+                            id: "palette-item-{idx}",
+                            class: if idx == selected_idx { "menu-item palette-item active" } else { "menu-item palette-item" },
+                            onclick: move |_| on_run.call(item.command),
+                            onmouseenter: move |_| selected.set(idx),
+
+                            div {
+                                class: "flex flex-col",
+                                span { "{item.command.title()}" }
+                                span {
+                                    class: "palette-name",
+                                    "{item.command.name()}"
+                                    if item.recent {
+                                        " · recently used"
+                                    }
+                                }
+                            }
+                            span { class: "palette-keys", "{item.keys.join(\", \")}" }
+                        }
+                    }
+                    if count == 0 {
+                        div { class: "text-secondary p-2", "No matching commands" }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Commands matching the query, best first; ties keep the given order
+fn filter_commands(commands: &[PaletteCommand], query: &str) -> Vec<PaletteCommand> {
+    let mut scored: Vec<(u32, &PaletteCommand)> = commands
+        .iter()
+        .filter_map(|item| {
+            let title = fuzzy_score(query, item.command.title());
+            let name = fuzzy_score(query, item.command.name());
+            title.max(name).map(|score| (score, item))
+        })
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, item)| item.clone()).collect()
+}
\ No newline at end of file
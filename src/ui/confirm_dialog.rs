@@ -0,0 +1,59 @@
+// Modal confirmation dialog
+
+use dioxus::prelude::*;
+
+/// Modal dialog asking the user to confirm or cancel an action. The actions
+/// throw away unsaved work, so Cancel starts out focused and Enter picks it.
+#[component]
+pub fn ConfirmDialog(
+    /// Dialog heading
+    title: String,
+    /// Explanation of what will happen
+    message: String,
+    /// Label of the confirm button
+    confirm_label: String,
+    /// Called when the action is confirmed
+    on_confirm: EventHandler<()>,
+    /// Called when the dialog is dismissed (Cancel, Enter, Escape or a click outside)
+    on_cancel: EventHandler<()>,
+) -> Element {
+    rsx! {
+        div {
+            class: "dialog-overlay",
+            onkeydown: move |evt| {
+                evt.stop_propagation();
+                if evt.key() == Key::Escape {
+                    on_cancel.call(());
+                }
+            },
+            onclick: move |_| on_cancel.call(()),
+
+            div {
+                class: "dialog-content",
+                onclick: move |evt| evt.stop_propagation(),
+
+                h2 { class: "text-lg font-bold mb-2", "{title}" }
+                p { class: "text-secondary mb-6", "{message}" }
+
+                div {
+                    class: "flex justify-end gap-4",
+                    button {
+                        class: "btn-brutal",
+                        onmounted: move |evt| async move {
+                            // Focused buttons answer Enter, and Escape works
+                            // without clicking first
+                            let _ = evt.set_focus(true).await;
+                        },
+                        onclick: move |_| on_cancel.call(()),
+                        "Cancel"
+                    }
+                    button {
+                        class: "btn-brutal",
+                        onclick: move |_| on_confirm.call(()),
+                        "{confirm_label}"
+                    }
+                }
+            }
+        }
+    }
+}
\ No newline at end of file
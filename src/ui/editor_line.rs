@@ -0,0 +1,140 @@
+// Editor line component
+// Renders one line of text with syntax colors, the cursors, and selection and
+// search match highlights
+
+use dioxus::prelude::*;
+use crate::syntax::{Span, TokenKind};
+use crate::theme::colors;
+
+/// A single rendered line in the editor view
+#[component]
+pub fn EditorLine(
+    /// Line text (without the trailing newline)
+    text: String,
+    /// Columns of the cursors on this line, in order
+    #[props(default)]
+    cursor_cols: Vec<usize>,
+    /// Selected column ranges on this line (start, end), in order
+    #[props(default)]
+    selections: Vec<(usize, usize)>,
+    /// Whether the selection continues past the end of this line
+    selection_eol: bool,
+    /// Search match column ranges on this line, in order
+    #[props(default)]
+    matches: Vec<(usize, usize)>,
+    /// Syntax highlight spans on this line, in order
+    #[props(default)]
+    spans: Vec<Span>,
+    /// Whether the cursor blinks (editor focused and blinking not turned off)
+    cursor_blinks: bool,
+) -> Element {
+    let segments = split_segments(&text, &selections, &matches, &spans, &cursor_cols);
+    let cursor_class = if cursor_blinks { "cursor-blink" } else { "cursor-static" };
+    let selection_style = format!("background-color: {};", colors::SELECTION_BG);
+
+    rsx! {
+        for segment in segments {
+            match segment {
+                Segment::Text { text, highlight, kind } => rsx! {
+                    span { style: run_style(highlight, kind), "{text}" }
+                },
+                Segment::Cursor => rsx! { span { class: cursor_class } },
+            }
+        }
+        // Show the selected line break as a trailing blank
+        if selection_eol {
+            span { style: "{selection_style}", " " }
+        }
+    }
+}
+
+// A piece of a rendered line
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text {
+        text: String,
+        highlight: Highlight,
+        kind: Option<TokenKind>,
+    },
+    Cursor,
+}
+
+// Background of a run of text; the selection wins over search matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Highlight {
+    None,
+    Match,
+    Selected,
+}
+
+// Split a line into runs of the same color and highlight, with cursor
+// markers in between
+fn split_segments(
+    text: &str,
+    selections: &[(usize, usize)],
+    matches: &[(usize, usize)],
+    spans: &[Span],
+    cursor_cols: &[usize],
+) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut current_style = (Highlight::None, None);
+    // Matches and spans are sorted, so walk them alongside the columns
+    let mut next_match = 0;
+    let mut next_span = 0;
+
+    let chars: Vec<char> = text.chars().collect();
+    for col in 0..=chars.len() {
+        while matches.get(next_match).is_some_and(|&(_, end)| end <= col) {
+            next_match += 1;
+        }
+        while spans.get(next_span).is_some_and(|span| span.end <= col) {
+            next_span += 1;
+        }
+        let selected = selections.iter().any(|&(start, end)| col >= start && col < end);
+        let highlight = if selected {
+            Highlight::Selected
+        } else if matches.get(next_match).is_some_and(|&(start, _)| start <= col) {
+            Highlight::Match
+        } else {
+            Highlight::None
+        };
+        let kind = spans.get(next_span).filter(|span| span.start <= col).map(|span| span.kind);
+        let style = (highlight, kind);
+
+        // Flush the current run when a cursor sits here or the style changes
+        let cursor_here = cursor_cols.contains(&col);
+        if cursor_here || style != current_style {
+            if !current.is_empty() {
+                let (highlight, kind) = current_style;
+                segments.push(Segment::Text { text: std::mem::take(&mut current), highlight, kind });
+            }
+            current_style = style;
+        }
+        if cursor_here {
+            segments.push(Segment::Cursor);
+        }
+        if let Some(&ch) = chars.get(col) {
+            current.push(ch);
+        }
+    }
+    if !current.is_empty() {
+        let (highlight, kind) = current_style;
+        segments.push(Segment::Text { text: current, highlight, kind });
+    }
+    segments
+}
+
+// Inline style for a run of text
+fn run_style(highlight: Highlight, kind: Option<TokenKind>) -> String {
+    let mut style = String::new();
+    if let Some(kind) = kind {
+        style.push_str(&format!("color: {};", kind.color()));
+    }
+    match highlight {
+        Highlight::None => {}
+        Highlight::Match => style.push_str(&format!("background-color: {};", colors::SEARCH_MATCH_BG)),
+        Highlight::Selected => style.push_str(&format!("background-color: {};", colors::SELECTION_BG)),
+    }
+    style
+}
\ No newline at end of file
@@ -0,0 +1,65 @@
+// Dialog for reopening a file with an explicit encoding
+
+use dioxus::prelude::*;
+use crate::file::{FileEncoding, REOPEN_ENCODINGS};
+
+/// Modal list of encodings to reopen a file with
+#[component]
+pub fn EncodingDialog(
+    /// Name of the file being reopened
+    filename: String,
+    /// Whether reopening discards unsaved changes
+    discards_changes: bool,
+    /// Called with the chosen encoding
+    on_select: EventHandler<FileEncoding>,
+    /// Called when the dialog is dismissed
+    on_cancel: EventHandler<()>,
+) -> Element {
+    rsx! {
+        div {
+            class: "dialog-overlay",
+            tabindex: 0,
+            onmounted: move |evt| async move {
+                let _ = evt.set_focus(true).await;
+            },
+            onkeydown: move |evt| {
+                evt.stop_propagation();
+                if evt.key() == Key::Escape {
+                    on_cancel.call(());
+                }
+            },
+            onclick: move |_| on_cancel.call(()),
+
+            div {
+                class: "dialog-content",
+                onclick: move |evt| evt.stop_propagation(),
+
+                h2 { class: "text-lg font-bold mb-2", "Reopen with encoding" }
+                p { class: "text-secondary mb-4", "{filename}" }
+                if discards_changes {
+                    p { class: "text-warning mb-4", "Unsaved changes will be lost." }
+                }
+
+                div {
+                    class: "flex flex-col mb-4",
+                    for encoding in REOPEN_ENCODINGS.iter().copied() {
+                        div {
+                            class: "menu-item",
+                            onclick: move |_| on_select.call(encoding),
+                            "{encoding.label()}"
+                        }
+                    }
+                }
+
+                div {
+                    class: "flex justify-end",
+                    button {
+                        class: "btn-brutal",
+                        onclick: move |_| on_cancel.call(()),
+                        "Cancel"
+                    }
+                }
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,110 @@
+// Find/replace bar shown above the editor
+
+use dioxus::prelude::*;
+use crate::editor::SearchQuery;
+
+/// Incremental find bar with replace controls
+#[component]
+pub fn FindBar(
+    /// DOM id of the search input
+    input_id: String,
+    /// Current query and options
+    query: SearchQuery,
+    /// Replacement text ($1 / ${name} expand capture groups in regex mode)
+    replacement: String,
+    /// Error for an invalid regular expression
+    error: Option<String>,
+    /// Called whenever the query or an option changes
+    on_query: EventHandler<SearchQuery>,
+    /// Called whenever the replacement text changes
+    on_replacement: EventHandler<String>,
+    /// Go to the next match
+    on_next: EventHandler<()>,
+    /// Go to the previous match
+    on_prev: EventHandler<()>,
+    /// Replace the current match
+    on_replace: EventHandler<()>,
+    /// Replace every match
+    on_replace_all: EventHandler<()>,
+    /// Close the bar
+    on_close: EventHandler<()>,
+) -> Element {
+    // Enter/Shift+Enter navigate, Escape closes; keep keys away from the editor
+    let onkeydown = move |evt: Event<KeyboardData>| {
+        evt.stop_propagation();
+        match evt.key() {
+            Key::Enter if evt.modifiers().shift() => on_prev.call(()),
+            Key::Enter => on_next.call(()),
+            Key::Escape => on_close.call(()),
+            _ => {}
+        }
+    };
+
+    let toggle_class = |active: bool| if active { "find-button active" } else { "find-button" };
+    let regex_query = query.clone();
+    let case_query = query.clone();
+    let word_query = query.clone();
+    let pattern_query = query.clone();
+
+    rsx! {
+        div {
+            class: "find-bar",
+            onkeydown,
+
+            // Find row
+            div {
+                class: "find-row",
+                input {
+                    id: input_id,
+                    class: "input-brutal",
+                    placeholder: "Find",
+                    value: "{query.pattern}",
+                    onmounted: move |evt| async move {
+                        let _ = evt.set_focus(true).await;
+                    },
+                    oninput: move |evt| {
+                        on_query.call(SearchQuery { pattern: evt.value(), ..pattern_query.clone() })
+                    },
+                }
+                button {
+                    class: toggle_class(query.regex),
+                    title: "Regular expression",
+                    onclick: move |_| on_query.call(SearchQuery { regex: !regex_query.regex, ..regex_query.clone() }),
+                    ".*"
+                }
+                button {
+                    class: toggle_class(query.case_sensitive),
+                    title: "Match case",
+                    onclick: move |_| on_query.call(SearchQuery { case_sensitive: !case_query.case_sensitive, ..case_query.clone() }),
+                    "Aa"
+                }
+                button {
+                    class: toggle_class(query.whole_word),
+                    title: "Whole word",
+                    onclick: move |_| on_query.call(SearchQuery { whole_word: !word_query.whole_word, ..word_query.clone() }),
+                    "W"
+                }
+                button { class: "find-button", title: "Previous match (Shift+Enter)", onclick: move |_| on_prev.call(()), "↑" }
+                button { class: "find-button", title: "Next match (Enter)", onclick: move |_| on_next.call(()), "↓" }
+                button { class: "find-button", title: "Close (Escape)", onclick: move |_| on_close.call(()), "×" }
+            }
+
+            // Replace row
+            div {
+                class: "find-row",
+                input {
+                    class: "input-brutal",
+                    placeholder: "Replace",
+                    value: "{replacement}",
+                    oninput: move |evt| on_replacement.call(evt.value()),
+                }
+                button { class: "find-button", onclick: move |_| on_replace.call(()), "Replace" }
+                button { class: "find-button", onclick: move |_| on_replace_all.call(()), "Replace all" }
+            }
+
+            if let Some(error) = error {
+                span { class: "text-error", "{error}" }
+            }
+        }
+    }
+}
\ No newline at end of file
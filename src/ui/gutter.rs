@@ -0,0 +1,68 @@
+// Line-number gutter for the editor view
+// One cell per buffer line, rendered in the same row as the line so it stays
+// aligned when the line wraps.
+
+use dioxus::prelude::*;
+use crate::theme::editor::GUTTER_WIDTH;
+
+/// How line numbers are displayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineNumberMode {
+    /// 1-based line numbers
+    #[default]
+    Absolute,
+    /// Distance from the cursor line (the cursor line shows its absolute number)
+    Relative,
+}
+
+impl LineNumberMode {
+    /// Switch between absolute and relative numbering
+    pub fn toggled(self) -> Self {
+        match self {
+            LineNumberMode::Absolute => LineNumberMode::Relative,
+            LineNumberMode::Relative => LineNumberMode::Absolute,
+        }
+    }
+
+    /// Short label for the status bar
+    pub fn label(self) -> &'static str {
+        match self {
+            LineNumberMode::Absolute => "Abs",
+            LineNumberMode::Relative => "Rel",
+        }
+    }
+}
+
+/// Gutter cell for a single line
+#[component]
+pub fn GutterCell(
+    /// Line index (0-based)
+    line_idx: usize,
+    /// Line the cursor is on (0-based)
+    cursor_line: usize,
+    /// Numbering mode
+    mode: LineNumberMode,
+) -> Element {
+    let label = line_label(line_idx, cursor_line, mode);
+    let class = if line_idx == cursor_line { "gutter-cell current" } else { "gutter-cell" };
+
+    rsx! {
+        div {
+            class,
+            style: "width: {GUTTER_WIDTH}px; min-width: {GUTTER_WIDTH}px;",
+
+            // Room for future markers (diagnostics, diff status, folding)
+            span { class: "gutter-markers" }
+
+            span { class: "gutter-number", "{label}" }
+        }
+    }
+}
+
+// Text shown in the gutter for a line
+fn line_label(line_idx: usize, cursor_line: usize, mode: LineNumberMode) -> String {
+    match mode {
+        LineNumberMode::Relative if line_idx != cursor_line => line_idx.abs_diff(cursor_line).to_string(),
+        _ => (line_idx + 1).to_string(),
+    }
+}
\ No newline at end of file
@@ -1,7 +1,23 @@
 // UI Components module
 
+mod command_palette;
+mod confirm_dialog;
+mod editor_line;
+mod encoding_dialog;
+mod find_bar;
+mod gutter;
+mod recovery_dialog;
 mod status_bar;
+mod tab_bar;
 mod title_bar;
 
+pub use command_palette::{CommandPalette, PaletteCommand};
+pub use confirm_dialog::ConfirmDialog;
+pub use editor_line::EditorLine;
+pub use encoding_dialog::EncodingDialog;
+pub use find_bar::FindBar;
+pub use gutter::{GutterCell, LineNumberMode};
+pub use recovery_dialog::{RecoveryDialog, RecoveryItem};
 pub use status_bar::StatusBar;
-pub use title_bar::TitleBar;
\ No newline at end of file
+pub use tab_bar::{TabBar, TabInfo};
+pub use title_bar::{window_title, TitleBar};
\ No newline at end of file
@@ -0,0 +1,121 @@
+// Dialog offering to restore unsaved changes left behind by a crash
+
+use dioxus::prelude::*;
+
+/// One recovered buffer as listed in the dialog
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveryItem {
+    /// File name, or "Untitled"
+    pub title: String,
+    /// Full path and when the changes were recorded
+    pub detail: String,
+}
+
+/// Modal list of recovered buffers with restore, diff and discard actions
+#[component]
+pub fn RecoveryDialog(
+    /// Recovered buffers
+    items: Vec<RecoveryItem>,
+    /// Item whose diff against the file on disk is shown, and the diff
+    diff: Option<(usize, String)>,
+    /// Called with an item's index to show (or hide) its diff
+    on_diff: EventHandler<usize>,
+    /// Called to reopen every recovered buffer
+    on_restore: EventHandler<()>,
+    /// Called to delete the recovered changes
+    on_discard: EventHandler<()>,
+    /// Called when the dialog is dismissed; the changes are offered again next start
+    on_cancel: EventHandler<()>,
+) -> Element {
+    let shown = diff.as_ref().map(|(idx, _)| *idx);
+
+    rsx! {
+        div {
+            class: "dialog-overlay",
+            tabindex: 0,
+            onmounted: move |evt| async move {
+                let _ = evt.set_focus(true).await;
+            },
+            onkeydown: move |evt| {
+                evt.stop_propagation();
+                if evt.key() == Key::Escape {
+                    on_cancel.call(());
+                }
+            },
+
+            div {
+                class: "dialog-content",
+                onclick: move |evt| evt.stop_propagation(),
+
+                h2 { class: "text-lg font-bold mb-2", "Recover unsaved changes" }
+                p {
+                    class: "text-secondary mb-4",
+                    "Stringr didn't exit cleanly last time. These files had unsaved changes:"
+                }
+
+                div {
+                    class: "flex flex-col mb-4",
+                    for (idx, item) in items.into_iter().enumerate() {
+                        div {
+                            class: "recovery-item",
+                            div {
+                                class: "flex flex-col",
+                                span { "{item.title}" }
+                                span { class: "text-secondary", "{item.detail}" }
+                            }
+                            button {
+                                class: if shown == Some(idx) { "find-button active" } else { "find-button" },
+                                onclick: move |_| on_diff.call(idx),
+                                "Diff"
+                            }
+                        }
+                    }
+                }
+
+                if let Some((_, diff)) = diff {
+                    pre {
+                        class: "recovery-diff mb-4",
+                        for line in diff.lines().map(str::to_string) {
+                            div {
+                                class: diff_line_class(&line),
+                                "{line}"
+                            }
+                        }
+                    }
+                }
+
+                div {
+                    class: "flex justify-end gap-4",
+                    button {
+                        class: "btn-brutal",
+                        onclick: move |_| on_discard.call(()),
+                        "Discard"
+                    }
+                    button {
+                        class: "btn-brutal",
+                        onclick: move |_| on_cancel.call(()),
+                        "Later"
+                    }
+                    button {
+                        class: "btn-brutal",
+                        onclick: move |_| on_restore.call(()),
+                        "Restore"
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Color added and removed lines of a unified diff
+fn diff_line_class(line: &str) -> &'static str {
+    if line.starts_with("+++") || line.starts_with("---") || line.starts_with("@@") {
+        "text-secondary"
+    } else if line.starts_with('+') {
+        "diff-added"
+    } else if line.starts_with('-') {
+        "diff-removed"
+    } else {
+        ""
+    }
+}
\ No newline at end of file
@@ -1,6 +1,9 @@
 // Status bar component showing cursor position and file info
 
 use dioxus::prelude::*;
+use super::gutter::LineNumberMode;
+use crate::editor::Indent;
+use crate::file::{FileEncoding, LineEnding};
 
 /// Status bar at the bottom of the editor
 #[component]
@@ -11,7 +14,42 @@ pub fn StatusBar(
     column: usize,
     /// Total number of lines
     total_lines: usize,
+    /// Error message to show (e.g. a failed save)
+    error: Option<String>,
+    /// Current gutter numbering mode
+    line_number_mode: LineNumberMode,
+    /// Called when the line-number mode indicator is clicked
+    on_toggle_line_numbers: EventHandler<()>,
+    /// Line-ending style of the buffer
+    line_ending: LineEnding,
+    /// The file mixes line-ending styles; saving converts them to `line_ending`
+    #[props(default)]
+    mixed_line_endings: bool,
+    /// Called when the line-ending indicator is clicked (converts to the next style)
+    on_cycle_line_ending: EventHandler<()>,
+    /// Indentation style of the buffer
+    indent: Indent,
+    /// Called when the indentation indicator is clicked (switches to the next style)
+    on_cycle_indent: EventHandler<()>,
+    /// Encoding of the buffer's file
+    encoding: FileEncoding,
+    /// Called when the encoding indicator is clicked
+    on_reopen_with_encoding: EventHandler<()>,
+    /// Number of search matches while the find bar is open
+    #[props(default)]
+    match_count: Option<usize>,
+    /// Index of the selected match (0-based)
+    #[props(default)]
+    current_match: Option<usize>,
 ) -> Element {
+    let match_label = match (match_count, current_match) {
+        (Some(0), _) => Some("No matches".to_string()),
+        (Some(count), Some(current)) => Some(format!("{} of {count}", current + 1)),
+        (Some(1), None) => Some("1 match".to_string()),
+        (Some(count), None) => Some(format!("{count} matches")),
+        (None, _) => None,
+    };
+
     rsx! {
         div {
             class: "status-bar",
@@ -20,12 +58,52 @@ pub fn StatusBar(
             div {
                 class: "status-left",
                 span { class: "text-primary font-bold", "Stringr" }
+                if let Some(error) = error {
+                    span { class: "text-error ml-4", "{error}" }
+                }
             }
 
             // Right side - cursor position
             div {
                 class: "flex gap-4",
 
+                if let Some(label) = match_label {
+                    span { "{label}" }
+                }
+
+                span {
+                    class: "status-toggle",
+                    title: "Toggle relative line numbers",
+                    onclick: move |_| on_toggle_line_numbers.call(()),
+                    "{line_number_mode.label()}"
+                }
+                span {
+                    class: "status-toggle",
+                    title: "Change indentation",
+                    onclick: move |_| on_cycle_indent.call(()),
+                    "{indent.label()}"
+                }
+                span {
+                    class: "status-toggle",
+                    title: "Reopen with encoding",
+                    onclick: move |_| on_reopen_with_encoding.call(()),
+                    "{encoding.label()}"
+                }
+                if mixed_line_endings {
+                    span {
+                        class: "status-toggle text-warning",
+                        title: "Mixed line endings, saved as {line_ending.label()}. Click to convert",
+                        onclick: move |_| on_cycle_line_ending.call(()),
+                        "Mixed ({line_ending.label()})"
+                    }
+                } else {
+                    span {
+                        class: "status-toggle",
+                        title: "Convert line endings",
+                        onclick: move |_| on_cycle_line_ending.call(()),
+                        "{line_ending.label()}"
+                    }
+                }
                 span { "Ln {line}, Col {column}" }
                 span { "{total_lines} lines" }
             }
@@ -0,0 +1,54 @@
+// Tab strip for switching between open documents
+
+use dioxus::prelude::*;
+
+/// What a tab shows for one open document
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabInfo {
+    /// Display name ("Untitled" for new documents)
+    pub title: String,
+    /// Whether the document has unsaved changes
+    pub is_dirty: bool,
+}
+
+/// Tab strip above the editor
+#[component]
+pub fn TabBar(
+    /// One entry per open document, in order
+    tabs: Vec<TabInfo>,
+    /// Index of the active tab
+    active: usize,
+    /// Called with the index of a clicked tab
+    on_select: EventHandler<usize>,
+    /// Called with the index of a tab whose close button was clicked
+    on_close: EventHandler<usize>,
+) -> Element {
+    rsx! {
+        div {
+            class: "tab-bar",
+
+            for (idx, tab) in tabs.into_iter().enumerate() {
+                div {
+                    key: "{idx}",
+                    class: if idx == active { "tab-item active" } else { "tab-item" },
+                    // Keep focus in the editor when switching tabs
+                    onmousedown: move |evt| evt.prevent_default(),
+                    onclick: move |_| on_select.call(idx),
+
+                    span { "{tab.title}" }
+
+                    // Dirty dot doubles as the close button, like most editors
+                    span {
+                        class: "tab-close",
+                        title: if tab.is_dirty { "Unsaved changes" } else { "Close" },
+                        onclick: move |evt| {
+                            evt.stop_propagation();
+                            on_close.call(idx);
+                        },
+                        if tab.is_dirty { "●" } else { "×" }
+                    }
+                }
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -14,9 +14,6 @@ pub fn TitleBar(
     // Display name with fallback
     let display_name = filename.unwrap_or_else(|| "Untitled".to_string());
 
-    // Dirty indicator - yellow dot for unsaved changes
-    let dirty_indicator = if is_dirty { " •" } else { "" };
-
     rsx! {
         div {
             class: "title-bar",
@@ -49,4 +46,14 @@ pub fn TitleBar(
             }
         }
     }
+}
+
+// Native window title, e.g. "• notes.md — Stringr"
+pub fn window_title(filename: Option<&str>, is_dirty: bool) -> String {
+    let name = filename.unwrap_or("Untitled");
+    if is_dirty {
+        format!("• {} — Stringr", name)
+    } else {
+        format!("{} — Stringr", name)
+    }
 }
\ No newline at end of file